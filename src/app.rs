@@ -2,39 +2,56 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
+use std::collections::{HashMap, HashSet};
 use std::io::IsTerminal;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use console::style;
 use dialoguer::{Confirm, Editor, Input, Select};
+use futures::{StreamExt, TryStreamExt, stream};
 use globset::{Glob, GlobSetBuilder};
 use tokio::signal;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, warn};
 
-use crate::cli::{Cli, Commands, HookAction};
-use crate::config::Config;
+use crate::cli::{
+    CacheAction, ChangelogFormat, Cli, Commands, HookAction, HookKind, OutputFormat, SplitMode,
+};
+use crate::config::{Auth, Config, LintMode, NetworkConfig};
 use crate::domain::PromptContext;
-use crate::domain::{ChangeStatus, CodeSymbol, CommitType, FileCategory, StagedChanges};
+use crate::domain::{ChangeStatus, CodeSymbol, CommitType, FileCategory, StagedChanges, Trailer};
 use crate::error::{Error, Result};
 use crate::services::{
-    analyzer::AnalyzerService,
+    analyzer::{AnalyzerService, DiffHunk},
+    cache::ResponseCache,
+    changelog::ChangelogBuilder,
     context::ContextBuilder,
     git::GitService,
-    history::HistoryService,
+    history::{CommitRecord, HistoryService},
+    lint::CommitLinter,
     llm,
+    merge::{ConflictResolution, MergeBuilder, MergeInfo},
+    output::{CommitOutput, SplitPlan},
+    pr::{PR_SYSTEM_PROMPT, PrBuilder},
     progress::Progress,
+    revert::{REVERT_SEARCH_DEPTH, REVERT_SEARCH_MAX_LINES, RevertBuilder},
     safety,
-    sanitizer::{CommitSanitizer, CommitValidator},
+    sanitizer::{CommitSanitizer, CommitValidator, StructuredCommit},
+    split_editor::SplitEditor,
     splitter::{CommitSplitter, SplitSuggestion},
+    squash::SquashBuilder,
     template,
+    trailers::TrailerBuilder,
+    usage::{self, LedgerEntry, TokenUsage, UsageLedger, UsageLog, UsageTotals},
 };
 
 pub struct App {
     cli: Cli,
     config: Config,
     cancel_token: CancellationToken,
+    /// LLM requests made during this run, for the ledger and `--timings`
+    usage: UsageLog,
 }
 
 impl App {
@@ -51,9 +68,33 @@ impl App {
             cli,
             config,
             cancel_token,
+            usage: UsageLog::default(),
         })
     }
 
+    /// A copy of this app whose generation helpers observe `cancel_token`
+    /// instead of the process-wide Ctrl+C token.
+    pub(crate) fn with_cancel_token(&self, cancel_token: CancellationToken) -> Self {
+        Self {
+            cli: self.cli.clone(),
+            config: self.config.clone(),
+            cancel_token,
+            usage: self.usage.clone(),
+        }
+    }
+
+    pub(crate) fn cancel_token(&self) -> &CancellationToken {
+        &self.cancel_token
+    }
+
+    pub(crate) fn cli(&self) -> &Cli {
+        &self.cli
+    }
+
+    pub(crate) fn config(&self) -> &Config {
+        &self.config
+    }
+
     pub async fn run(&mut self) -> Result<()> {
         // Setup Ctrl+C handler with CancellationToken
         let cancel = self.cancel_token.clone();
@@ -63,11 +104,17 @@ impl App {
         });
 
         // Handle subcommands
-        if let Some(ref cmd) = self.cli.command {
-            return self.handle_command(cmd).await;
-        }
+        let result = match self.cli.command.clone() {
+            Some(cmd) => self.handle_command(&cmd).await,
+            None => self.generate_commit().await,
+        };
 
-        self.generate_commit().await
+        // Every request attempt counts, even when the run failed later
+        self.flush_usage();
+        if self.cli.timings {
+            self.print_usage_summary();
+        }
+        result
     }
 
     async fn generate_commit(&mut self) -> Result<()> {
@@ -77,12 +124,32 @@ impl App {
 
         // Step 1: Discover repo and get changes
         let progress = Progress::new(self.cli.verbose);
-        progress.phase("Analyzing staged changes...");
-
         let git = GitService::discover()?;
-        let (changes, full_diff) = git
-            .get_staged_changes(self.config.max_file_lines, self.config.rename_threshold)
-            .await?;
+        let (changes, full_diff) = if self.cli.amend {
+            progress.phase("Analyzing HEAD and staged changes...");
+            git.get_amend_changes(self.config.max_file_lines, self.config.rename_threshold)
+                .await?
+        } else {
+            progress.phase("Analyzing staged changes...");
+            git.get_staged_changes(self.config.max_file_lines, self.config.rename_threshold)
+                .await?
+        };
+
+        // Commits being squashed, when writing a squash message
+        let squashed = match self.cli.squash {
+            Some(ref range) => Some(Self::squashed_commits(&git, range).await?),
+            None => None,
+        };
+
+        // The earlier commit the staged changes undo, if any
+        // A merge waiting to be committed gets a merge message
+        let merging = !self.cli.amend && squashed.is_none() && git.merge_in_progress();
+
+        let reverted = if self.cli.amend || squashed.is_some() || merging {
+            None
+        } else {
+            Self::reverted_commit(&git, &changes).await
+        };
 
         progress.info(&format!(
             "{} files with changes detected (+{} -{})",
@@ -90,6 +157,13 @@ impl App {
             changes.stats.insertions,
             changes.stats.deletions
         ));
+        if let Some(ref commit) = reverted {
+            progress.info(&format!(
+                "Reverts {} ({})",
+                GitService::short_sha(&commit.sha),
+                commit.subject
+            ));
+        }
 
         // Step 1.5: Exclude files matching glob patterns
         let changes = self.apply_exclude_patterns(changes, &progress)?;
@@ -99,70 +173,14 @@ impl App {
             return Err(Error::MergeConflicts);
         }
 
-        // Scan the full untruncated diff for secrets (not the per-file truncated diffs)
-        let secret_patterns = safety::build_patterns(
-            &self.config.custom_secret_patterns,
-            &self.config.disabled_secret_patterns,
-        );
-        let secrets = safety::scan_full_diff_with_patterns(&full_diff, &secret_patterns);
-        if !secrets.is_empty() {
-            warn!(
-                count = secrets.len(),
-                "potential secrets detected in staged changes"
-            );
-            progress.warning("Potential secrets detected:");
-            for s in &secrets {
-                eprintln!(
-                    "  {} in {} (line ~{})",
-                    s.pattern_name,
-                    s.file,
-                    s.line.unwrap_or(0)
-                );
-            }
-
-            // Cloud providers: always block when secrets detected
-            if !self.cli.allow_secrets {
-                return Err(Error::SecretsDetected {
-                    patterns: secrets.iter().map(|s| s.pattern_name.clone()).collect(),
-                });
-            }
-
-            // --allow-secrets passed: always require interactive confirmation
-            if std::io::stdin().is_terminal() {
-                progress.finish();
-                eprintln!("\nwarning: Potential secrets detected in staged changes.");
-                for s in &secrets {
-                    eprintln!(
-                        "  {} in {} (line ~{})",
-                        s.pattern_name,
-                        s.file,
-                        s.line.unwrap_or(0)
-                    );
-                }
-                eprintln!(
-                    "Provider: {} ({})",
-                    self.config.provider,
-                    if self.config.provider == crate::config::Provider::Ollama {
-                        &self.config.ollama_host
-                    } else {
-                        "cloud API"
-                    }
-                );
-                eprint!("Send diff to LLM anyway? [y/N] ");
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input).ok();
-                if !input.trim().eq_ignore_ascii_case("y") {
-                    return Err(Error::SecretsDetected {
-                        patterns: secrets.iter().map(|s| s.pattern_name.clone()).collect(),
-                    });
-                }
-            } else {
-                // Non-interactive: always block even with --allow-secrets
-                return Err(Error::SecretsDetected {
-                    patterns: secrets.iter().map(|s| s.pattern_name.clone()).collect(),
-                });
-            }
-        }
+        // Scan the full untruncated diff for secrets (not the per-file truncated diffs).
+        // A split plan never reaches the LLM, so it doesn't need the guard.
+        let split_mode = self.cli.split;
+        let sensitive = if split_mode == Some(SplitMode::Plan) {
+            false
+        } else {
+            self.guard_secrets(&full_diff, &progress)?
+        };
 
         if self.cancel_token.is_cancelled() {
             return Err(Error::Cancelled);
@@ -175,7 +193,11 @@ impl App {
 
         // Fetch all file content concurrently (async I/O via tokio JoinSet)
         let file_paths: Vec<PathBuf> = changes.files.iter().map(|f| f.path.clone()).collect();
-        let (staged_map, head_map) = git.fetch_file_contents(&file_paths).await;
+        let (staged_map, head_map) = if self.cli.amend {
+            git.fetch_amend_file_contents(&file_paths).await
+        } else {
+            git.fetch_file_contents(&file_paths).await
+        };
 
         // Parse symbols in parallel across CPU cores (rayon)
         let (symbols, symbol_diffs) =
@@ -183,29 +205,93 @@ impl App {
 
         debug!(count = symbols.len(), "symbols extracted");
 
+        let merge = if merging {
+            progress.phase("Analyzing merge...");
+            let info = self.merge_info(&git, &analyzer).await?;
+            progress.info(&format!(
+                "Merging {} commits, {} resolved conflicts",
+                info.commits.len(),
+                info.conflicts.len()
+            ));
+            Some(info)
+        } else {
+            None
+        };
+
         // Finish analysis spinner before any interactive prompts
         progress.finish();
 
-        let is_interactive = std::io::stdout().is_terminal() && std::io::stdin().is_terminal();
+        // JSON output is for scripts and editors: never prompt
+        let json_output = self.cli.output == OutputFormat::Json;
+        let is_interactive =
+            std::io::stdout().is_terminal() && std::io::stdin().is_terminal() && !json_output;
 
         // Step 3.5: Split detection
-        if !self.cli.no_split && is_interactive && !self.cli.yes {
+        let mut split_groups = None;
+        if !self.cli.no_split
+            && !self.cli.amend
+            && squashed.is_none()
+            && reverted.is_none()
+            && merge.is_none()
+            && (split_mode.is_some() || json_output || (is_interactive && !self.cli.yes))
+        {
             let suggestion = CommitSplitter::analyze(&changes, &symbols);
 
-            if let SplitSuggestion::SuggestSplit(groups) = suggestion {
-                Self::display_split_suggestion(&groups, &changes);
-
-                let split_confirm = Confirm::new()
-                    .with_prompt("Split into separate commits?")
-                    .default(true)
-                    .interact()?;
+            if split_mode == Some(SplitMode::Plan) {
+                let groups = match suggestion {
+                    SplitSuggestion::SuggestSplit(groups) => Some(groups),
+                    _ => None,
+                };
+                println!("{}", SplitPlan::new(groups.as_deref()).to_json());
+                return Ok(());
+            }
 
-                if split_confirm {
+            if let SplitSuggestion::SuggestSplit(groups) = suggestion {
+                if split_mode == Some(SplitMode::Auto) {
+                    Self::display_split_suggestion(&groups, &changes);
                     return self
-                        .run_split_flow(&git, groups, &changes, &symbols, &symbol_diffs)
+                        .run_split_flow(&git, groups, &changes, &symbols, &symbol_diffs, sensitive)
                         .await;
                 }
-                progress.info("Proceeding with single commit");
+                if json_output {
+                    // Reported in the output instead of offered
+                    split_groups = Some(groups);
+                } else {
+                    let mut groups = groups;
+                    loop {
+                        Self::display_split_suggestion(&groups, &changes);
+
+                        let options = &["Yes", "Edit groups", "No, single commit"];
+                        let selection = Select::new()
+                            .with_prompt("Split into separate commits?")
+                            .items(options)
+                            .default(0)
+                            .interact()
+                            .map_err(|e| Error::Dialog(e.to_string()))?;
+
+                        match selection {
+                            0 => {
+                                return self
+                                    .run_split_flow(
+                                        &git,
+                                        groups,
+                                        &changes,
+                                        &symbols,
+                                        &symbol_diffs,
+                                        sensitive,
+                                    )
+                                    .await;
+                            }
+                            1 => match Self::edit_split_groups(&groups, &changes)? {
+                                Some(edited) if edited.is_empty() => break,
+                                Some(edited) => groups = edited,
+                                None => {}
+                            },
+                            _ => break,
+                        }
+                    }
+                    progress.info("Proceeding with single commit");
+                }
             }
         }
 
@@ -235,10 +321,27 @@ impl App {
         // Step 4: Build context
         let mut context = ContextBuilder::build(&changes, &symbols, &symbol_diffs, &self.config);
         context.history_context = history_prompt;
+        context.trailers = self.commit_trailers(&git).await?;
+        if reverted.is_some() {
+            context.suggested_type = CommitType::Revert;
+        }
         debug!(prompt_chars = context.to_prompt().len(), "context built");
 
         let system_prompt = self.resolve_system_prompt()?;
-        let prompt = self.resolve_user_prompt(&context)?;
+        let mut prompt = self.resolve_user_prompt(&context)?;
+        if self.cli.amend {
+            let current = git.commit_message("HEAD").await?;
+            prompt = self.resolve_amend_prompt(&prompt, current.trim());
+        }
+        if let Some(ref commits) = squashed {
+            prompt = SquashBuilder::build_prompt(&prompt, commits);
+        }
+        if let Some(ref commit) = reverted {
+            prompt = RevertBuilder::build_prompt(&prompt, commit);
+        }
+        if let Some(ref info) = merge {
+            prompt = MergeBuilder::build_prompt(&prompt, info);
+        }
 
         if self.cli.show_prompt {
             eprintln!("{}", style("--- PROMPT ---").dim());
@@ -252,40 +355,55 @@ impl App {
         }
 
         // Step 5: Generate commit message(s)
-        let num_candidates = self.cli.generate;
+        let num_candidates = usize::from(self.cli.generate);
 
         // Restart spinner for LLM generation phase
         let mut progress = Progress::new(self.cli.verbose);
-        progress.phase(&format!(
-            "Contacting {} ({})...",
-            self.config.provider, self.config.model
-        ));
 
-        let provider = llm::create_provider(&self.config)?;
-        debug!(provider = provider.name(), "verifying provider");
-        provider.verify().await?;
+        let provider = self.create_provider(&self.config)?;
+        if sensitive {
+            provider.mark_sensitive();
+        }
 
-        let mut candidates: Vec<String> = Vec::new();
+        // Reuse candidates generated earlier for the same prompt
+        let cache = self.response_cache();
+        let cache_key = ResponseCache::key(
+            &self.config.active_profile(),
+            &self.config.format,
+            &context.trailers,
+            &system_prompt,
+            &prompt,
+        );
+        let mut candidates = cache
+            .as_ref()
+            .map(|c| c.get(&cache_key))
+            .unwrap_or_default();
+        candidates.truncate(num_candidates);
+        let cached = candidates.len();
+        if cached > 0 {
+            debug!(cached, "reusing cached candidates");
+            progress.info(&format!(
+                "Reusing {cached} cached candidate(s) (--no-cache to regenerate)"
+            ));
+        }
 
-        for i in 0..num_candidates {
-            if self.cancel_token.is_cancelled() {
-                return Err(Error::Cancelled);
-            }
+        if cached < num_candidates {
+            progress.phase(&format!(
+                "Contacting {} ({})...",
+                self.config.active_profile().name,
+                self.config.model
+            ));
+            debug!(provider = provider.name(), "verifying provider");
+            provider.verify().await?;
+            Self::report_fallback(&provider, &progress);
+        }
 
-            if num_candidates > 1 {
-                progress.phase(&format!(
-                    "Generating candidate {}/{}...",
-                    i + 1,
-                    num_candidates
-                ));
-            } else {
-                progress.phase("Generating...");
-            }
+        let mut fresh: Vec<String> = Vec::new();
+        if num_candidates == 1 && cached == 0 {
+            progress.phase("Generating...");
 
+            // Stream tokens for single generation
             let (tx, mut rx) = mpsc::channel::<String>(64);
-
-            // Only stream output for single generation
-            let show_stream = num_candidates == 1;
             let cancel_for_printer = self.cancel_token.clone();
             let spinner = progress.take_bar();
 
@@ -295,59 +413,72 @@ impl App {
                     tokio::select! {
                         _ = cancel_for_printer.cancelled() => break,
                         token = rx.recv() => {
-                            match token {
-                                Some(t) if show_stream => {
-                                    if first {
-                                        if let Some(ref bar) = spinner {
-                                            bar.finish_and_clear();
-                                        }
-                                        first = false;
-                                    }
-                                    eprint!("{}", t);
+                            let Some(t) = token else { break };
+                            if first {
+                                if let Some(ref bar) = spinner {
+                                    bar.finish_and_clear();
                                 }
-                                Some(_) => {} // Suppress streaming for multi-gen
-                                None => break,
+                                first = false;
                             }
+                            eprint!("{}", t);
                         }
                     }
                 }
             });
 
-            let raw_message = provider
-                .generate(&prompt, &system_prompt, tx, self.cancel_token.clone())
-                .await?;
+            let result = self
+                .generate_candidate(0, &provider, &context, &prompt, &system_prompt, tx)
+                .await;
 
             if let Err(e) = print_handle.await {
                 warn!("print task panicked: {e}");
             }
+            eprintln!(); // Newline after streaming
+
+            fresh.extend(result?);
+        } else if cached < num_candidates {
+            progress.phase(&format!(
+                "Generating {} of {} candidates...",
+                num_candidates - cached,
+                num_candidates
+            ));
 
-            if num_candidates == 1 {
-                eprintln!(); // Newline after streaming
-            }
+            // Streaming is suppressed for multi-gen: the receiver is dropped
+            // right away, so providers skip forwarding tokens
+            let limit = self.config.max_concurrent_requests.max(1);
+            let results: Vec<Option<String>> = stream::iter(cached..num_candidates)
+                .map(|i| {
+                    let (tx, _) = mpsc::channel::<String>(1);
+                    self.generate_candidate(i, &provider, &context, &prompt, &system_prompt, tx)
+                })
+                .buffered(limit)
+                .try_collect()
+                .await?;
+            fresh.extend(results.into_iter().flatten());
+        }
 
-            if raw_message.trim().is_empty() {
-                warn!(candidate = i + 1, "empty response from LLM, skipping");
-                continue;
-            }
+        // The key names the primary provider, so don't store fallback output.
+        // Output for a diff with approved secrets may quote them: keep it off disk.
+        if let Some(ref cache) = cache
+            && !fresh.is_empty()
+            && !sensitive
+            && provider.fallback_label().is_none()
+            && let Err(e) = cache.extend(&cache_key, &fresh)
+        {
+            warn!(error = %e, "failed to write response cache");
+        }
 
+        candidates.extend(fresh);
+        let generated = candidates.len();
+        let mut candidates = CommitSanitizer::dedup_candidates(candidates);
+        for candidate in &mut candidates {
+            *candidate = Self::finalize_message(candidate, squashed.as_deref(), reverted.as_ref());
+        }
+        if candidates.len() < generated {
             debug!(
-                raw_len = raw_message.len(),
-                candidate = i + 1,
-                "sanitizing LLM response"
+                dropped = generated - candidates.len(),
+                "dropped near-identical candidates"
             );
-
-            // Validate against evidence and retry once if violations found
-            let raw_to_sanitize = self
-                .validate_and_retry(&raw_message, &context, &provider, &prompt, &system_prompt)
-                .await
-                .unwrap_or(raw_message);
-
-            match CommitSanitizer::sanitize(&raw_to_sanitize, &self.config.format) {
-                Ok(msg) => candidates.push(msg),
-                Err(e) => {
-                    warn!(candidate = i + 1, error = %e, "failed to sanitize candidate");
-                }
-            }
         }
 
         if candidates.is_empty() {
@@ -358,14 +489,14 @@ impl App {
         }
 
         // Step 6: Select message
-        let mut message = if candidates.len() == 1 {
-            candidates.into_iter().next().unwrap()
+        let mut message = if candidates.len() == 1 || json_output {
+            candidates[0].clone()
         } else {
             self.select_candidate(&candidates)?
         };
 
         // Step 6.5: Interactive Review / Edit
-        if !self.cli.yes && is_interactive && !self.cli.dry_run && !self.cli.clipboard {
+        if !self.auto_confirm() && is_interactive && !self.cli.dry_run && !self.cli.clipboard {
             loop {
                 eprintln!("\n{}", style("Commit message:").bold());
                 eprintln!("{}", style(&message).green());
@@ -412,7 +543,11 @@ impl App {
                                 .await
                             {
                                 Ok(refined) => {
-                                    message = refined;
+                                    message = Self::finalize_message(
+                                        &refined,
+                                        squashed.as_deref(),
+                                        reverted.as_ref(),
+                                    );
                                 }
                                 Err(e) => {
                                     warn!(error = %e, "failed to refine message");
@@ -430,7 +565,21 @@ impl App {
             }
         }
 
-        // Step 7: Clipboard / dry-run / commit
+        // Step 7: JSON / clipboard / dry-run / commit
+        if json_output {
+            let mut output =
+                CommitOutput::build(&message, candidates, &context, split_groups.as_deref());
+            if self.cli.yes && !self.cli.dry_run {
+                self.commit_or_amend(&git, &message).await?;
+                output.committed = true;
+            }
+            if self.cli.clipboard {
+                Self::copy_to_clipboard(&message)?;
+            }
+            println!("{}", output.to_json());
+            return Ok(());
+        }
+
         if self.cli.clipboard {
             Self::copy_to_clipboard(&message)?;
             eprintln!("{} Copied to clipboard!", style("✓").green().bold());
@@ -443,9 +592,9 @@ impl App {
             return Ok(());
         }
 
-        // Auto-commit if --yes is set
-        if self.cli.yes {
-            git.commit(&message).await?;
+        // Auto-commit with --yes or --split auto
+        if self.auto_confirm() {
+            self.commit_or_amend(&git, &message).await?;
             eprintln!("{} Committed!", style("✓").green().bold());
             return Ok(());
         }
@@ -457,13 +606,179 @@ impl App {
             return Ok(());
         }
 
-        git.commit(&message).await?;
+        self.commit_or_amend(&git, &message).await?;
 
         eprintln!("{} Committed!", style("✓").green().bold());
 
         Ok(())
     }
 
+    /// Whether to commit without prompting: `--yes`, or `--split auto`, which
+    /// also commits a single message when no split is found.
+    fn auto_confirm(&self) -> bool {
+        self.cli.yes || self.cli.split == Some(SplitMode::Auto)
+    }
+
+    /// Commits folded into a squash: parsed from `SQUASH_MSG` when `range` is
+    /// empty, otherwise read from `git log <range>`.
+    async fn squashed_commits(git: &GitService, range: &str) -> Result<Vec<CommitRecord>> {
+        let commits = if range.is_empty() {
+            let text = git.read_git_file("SQUASH_MSG").ok_or_else(|| {
+                Error::Git(
+                    "No squash in progress: run `git merge --squash <branch>` or pass --squash <range>"
+                        .into(),
+                )
+            })?;
+            SquashBuilder::parse_squash_msg(&text)
+        } else {
+            HistoryService::fetch_commits(git.work_dir(), range).await?
+        };
+
+        if commits.is_empty() {
+            return Err(Error::Git("No commits to squash".into()));
+        }
+        debug!(count = commits.len(), "squashed commits loaded");
+        Ok(commits)
+    }
+
+    /// The commit that the staged changes revert, from `REVERT_HEAD` or, for
+    /// small changes, by matching the diff against recent history. Detection
+    /// failures are logged and treated as "not a revert".
+    async fn reverted_commit(git: &GitService, changes: &StagedChanges) -> Option<CommitRecord> {
+        let searchable =
+            changes.stats.insertions + changes.stats.deletions <= REVERT_SEARCH_MAX_LINES;
+        // No paths: only REVERT_HEAD is checked
+        let paths: Vec<PathBuf> = if searchable {
+            changes
+                .files
+                .iter()
+                .flat_map(|f| std::iter::once(f.path.clone()).chain(f.old_path.clone()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let sha = match git.find_reverted_commit(&paths, REVERT_SEARCH_DEPTH).await {
+            Ok(sha) => sha?,
+            Err(e) => {
+                debug!(error = %e, "revert detection failed");
+                return None;
+            }
+        };
+
+        let message = git.commit_message(&sha).await.ok()?;
+        let message = message.trim();
+        let (subject, body) = message.split_once('\n').unwrap_or((message, ""));
+        debug!(sha = %sha, "staged changes revert an earlier commit");
+        Some(CommitRecord {
+            sha,
+            subject: subject.trim().to_string(),
+            body: body.trim().to_string(),
+        })
+    }
+
+    /// The merge being committed: its title, the commits it brings in, and
+    /// how each conflicted file was resolved relative to both parents.
+    async fn merge_info(&self, git: &GitService, analyzer: &AnalyzerService) -> Result<MergeInfo> {
+        let merge_msg = git.read_git_file("MERGE_MSG").unwrap_or_default();
+
+        let mut commits = Vec::new();
+        let mut seen = HashSet::new();
+        for head in git.merge_heads() {
+            let range = format!("HEAD..{head}");
+            for commit in HistoryService::fetch_commits(git.work_dir(), &range).await? {
+                if seen.insert(commit.sha.clone()) {
+                    commits.push(commit);
+                }
+            }
+        }
+
+        let paths = MergeBuilder::parse_conflicts(&merge_msg);
+        let mut conflicts = Vec::new();
+        if !paths.is_empty() {
+            let max_lines = self.config.max_file_lines;
+            let (resolved, ours, theirs) = git.fetch_merge_file_contents(&paths).await;
+            let (vs_ours, vs_theirs) = tokio::try_join!(
+                git.get_resolution_changes("HEAD", &paths, max_lines),
+                git.get_resolution_changes("MERGE_HEAD", &paths, max_lines),
+            )?;
+
+            // Where the result differs from one parent, it took the other's side
+            let (from_theirs, _) = analyzer.extract_symbols(&vs_ours.files, &resolved, &ours);
+            let (from_ours, _) = analyzer.extract_symbols(&vs_theirs.files, &resolved, &theirs);
+            let names_in = |symbols: &[CodeSymbol], path: &Path| {
+                let in_file: Vec<CodeSymbol> =
+                    symbols.iter().filter(|s| s.file == path).cloned().collect();
+                MergeBuilder::symbol_names(&in_file)
+            };
+
+            for path in paths {
+                conflicts.push(ConflictResolution {
+                    resolution: MergeBuilder::classify(
+                        resolved.get(&path).map(String::as_str),
+                        ours.get(&path).map(String::as_str),
+                        theirs.get(&path).map(String::as_str),
+                    ),
+                    from_theirs: names_in(&from_theirs, &path),
+                    from_ours: names_in(&from_ours, &path),
+                    path,
+                });
+            }
+        }
+
+        debug!(
+            commits = commits.len(),
+            conflicts = conflicts.len(),
+            "merge analyzed"
+        );
+        Ok(MergeInfo {
+            title: MergeBuilder::merge_title(&merge_msg),
+            commits,
+            conflicts,
+        })
+    }
+
+    /// Issue, co-author and sign-off trailers for a commit made in `git`.
+    async fn commit_trailers(&self, git: &GitService) -> Result<Vec<Trailer>> {
+        let branch = git.current_branch().await;
+        let identity = if self.config.trailers.sign_off {
+            Some(git.committer_identity().await.ok_or_else(|| {
+                Error::Git("Signing off needs a git identity: set user.name and user.email".into())
+            })?)
+        } else {
+            None
+        };
+        let trailers = TrailerBuilder::build(
+            &self.config.trailers,
+            branch.as_deref(),
+            identity.as_deref(),
+        );
+        debug!(count = trailers.len(), "trailers resolved");
+        Ok(trailers)
+    }
+
+    /// Carry the squashed commits' footers and trailers over verbatim, or
+    /// rewrite the message as a revert of `reverted`.
+    fn finalize_message(
+        message: &str,
+        squashed: Option<&[CommitRecord]>,
+        reverted: Option<&CommitRecord>,
+    ) -> String {
+        match (squashed, reverted) {
+            (Some(commits), _) => SquashBuilder::finalize(message, commits),
+            (None, Some(commit)) => RevertBuilder::finalize(message, commit),
+            (None, None) => message.to_string(),
+        }
+    }
+
+    /// Commit `message`, or replace `HEAD` with it under `--amend`.
+    async fn commit_or_amend(&self, git: &GitService, message: &str) -> Result<()> {
+        if self.cli.amend {
+            git.amend(message).await
+        } else {
+            git.commit(message).await
+        }
+    }
+
     async fn handle_command(&self, cmd: &Commands) -> Result<()> {
         match cmd {
             Commands::Init => {
@@ -473,6 +788,9 @@ impl App {
             }
             Commands::Config => {
                 println!("Provider: {}", self.config.provider);
+                if let Some(ref profile) = self.config.profile {
+                    println!("Profile: {}", profile);
+                }
                 println!("Model: {}", self.config.model);
                 println!("Ollama host: {}", self.config.ollama_host);
                 println!("Max diff lines: {}", self.config.max_diff_lines);
@@ -493,6 +811,7 @@ impl App {
                         self.config.exclude_patterns.join(", ")
                     );
                 }
+                println!("Lint mode: {}", self.config.lint_mode);
                 println!();
                 println!("[format]");
                 println!("  include_body: {}", self.config.format.include_body);
@@ -509,7 +828,29 @@ impl App {
                 clap_complete::generate(*shell, &mut cmd, "commitbee", &mut std::io::stdout());
                 Ok(())
             }
-            Commands::Hook { action } => self.handle_hook(action),
+            Commands::Reword { range } => self.run_reword(range).await,
+            Commands::Pr { base } => self.run_pr(base).await,
+            Commands::Changelog {
+                range,
+                format,
+                release,
+                bump,
+            } => {
+                self.run_changelog(range, *format, release.as_deref(), *bump)
+                    .await
+            }
+            Commands::Lint { range } => self.run_lint(range).await,
+            Commands::Hook { action } => self.handle_hook(action).await,
+            Commands::Cache { action } => self.handle_cache(action),
+            Commands::Usage => self.run_usage(),
+            Commands::Serve { stdio } => {
+                if !stdio {
+                    return Err(Error::Config(
+                        "serve needs a transport: use `commitbee serve --stdio`".into(),
+                    ));
+                }
+                crate::server::run_stdio(self).await
+            }
             #[cfg(feature = "secure-storage")]
             Commands::SetKey { provider } => self.set_api_key(provider),
             #[cfg(feature = "secure-storage")]
@@ -528,23 +869,78 @@ impl App {
     async fn run_doctor(&self) -> Result<()> {
         eprintln!("{} Running diagnostics...\n", style("→").cyan());
 
+        let profile = self.config.active_profile();
+
         // Config summary
         eprintln!("{}", style("Configuration").bold().underlined());
-        eprintln!("  Provider:    {}", self.config.provider);
-        eprintln!("  Model:       {}", self.config.model);
-        eprintln!("  Timeout:     {}s", self.config.timeout_secs);
+        if self.config.profile.is_some() {
+            eprintln!("  Profile:     {} ({})", profile.name, profile.kind);
+        } else {
+            eprintln!("  Provider:    {}", profile.kind);
+        }
+        eprintln!("  Model:       {}", profile.model);
+        if let Some(ref url) = profile.base_url {
+            eprintln!("  Endpoint:    {}", url);
+        }
+        eprintln!("  Timeout:     {}s", profile.timeout_secs);
+        if !self.config.fallback.is_empty() {
+            eprintln!("  Fallback:    {}", self.config.fallback.join(" → "));
+        }
         if let Some(ref path) = Config::config_path() {
             let status = if path.exists() { "found" } else { "not found" };
             eprintln!("  Config file: {} ({})", path.display(), status);
         }
         eprintln!();
 
+        // Proxy and TLS
+        let network = &profile.network;
+        eprintln!("{}", style("Network").bold().underlined());
+        match network.proxy {
+            Some(ref url) => {
+                let shown = if url.contains('@') { "[REDACTED]" } else { url };
+                eprintln!("  Proxy:       {}", shown);
+                if let Some(ref hosts) = network.no_proxy {
+                    eprintln!("  No proxy:    {}", hosts);
+                }
+            }
+            None => eprintln!("  Proxy:       {}", style("system settings").dim()),
+        }
+        if let Some(ref path) = network.ca_bundle_path {
+            eprintln!("  CA bundle:   {}", path.display());
+        }
+        if let Some(ref path) = network.client_cert_path {
+            eprintln!("  Client cert: {}", path.display());
+        }
+        eprint!("  Connection:  ");
+        match llm::create_backend(&profile) {
+            Ok(backend) => match backend.probe().await {
+                Ok(()) => eprintln!("{}", style("OK").green().bold()),
+                Err(Error::Tls { ref message, .. }) => {
+                    eprintln!("{}", style("TLS FAILED").red().bold());
+                    eprintln!("  {}", message);
+                    if let Some(hint) = Self::tls_hint(message, network) {
+                        eprintln!("  {}", style(hint).yellow());
+                    }
+                }
+                Err(Error::ProviderUnreachable { ref message, .. }) => {
+                    eprintln!("{}: {}", style("UNREACHABLE").red().bold(), message);
+                }
+                Err(e) => eprintln!("{}: {}", style("ERROR").red().bold(), e),
+            },
+            Err(e) => eprintln!("{}: {}", style("ERROR").red().bold(), e),
+        }
+        eprintln!();
+
         // Provider connectivity
         eprintln!("{}", style("Provider Check").bold().underlined());
-        match self.config.provider {
+        match profile.kind {
             crate::config::Provider::Ollama => {
-                eprint!("  Ollama ({}): ", self.config.ollama_host);
-                let provider = llm::create_provider(&self.config)?;
+                eprint!(
+                    "  Ollama ({}): ",
+                    profile.base_url.as_deref().unwrap_or_default()
+                );
+                // Check the primary only; failing over would hide its status
+                let provider = llm::create_backend(&profile)?;
                 match provider.verify().await {
                     Ok(()) => {
                         eprintln!("{}", style("OK").green().bold());
@@ -573,6 +969,9 @@ impl App {
                             eprintln!("  Available: {}", available.join(", "));
                         }
                     }
+                    Err(Error::Tls { .. }) => {
+                        eprintln!("{} (see Network above)", style("TLS FAILED").red().bold());
+                    }
                     Err(e) => {
                         eprintln!("{}: {}", style("ERROR").red().bold(), e);
                     }
@@ -580,11 +979,23 @@ impl App {
             }
             other => {
                 eprint!("  {} API key: ", other);
-                if self.config.api_key.is_some() {
+                if profile.auth == Auth::None {
+                    eprintln!("{}", style("not sent (auth = none)").dim());
+                } else if profile.api_key.is_some() {
                     eprintln!("{}", style("configured").green());
+                } else if profile.base_url.is_some() && self.config.profile.is_some() {
+                    eprintln!("{}", style("none (keyless endpoint)").dim());
                 } else {
                     eprintln!("{}", style("MISSING").red().bold());
                 }
+                eprintln!("  Auth: {}", profile.auth);
+                for (name, spec) in &profile.headers {
+                    eprint!("  Header {}: ", name);
+                    match Config::resolve_header_value(name, spec) {
+                        Ok(_) => eprintln!("{}", style("set").green()),
+                        Err(e) => eprintln!("{}: {}", style("ERROR").red().bold(), e),
+                    }
+                }
             }
         }
         eprintln!();
@@ -602,46 +1013,555 @@ impl App {
         Ok(())
     }
 
-    // ─── Split Detection ───
+    /// What to change in `[network]` for a TLS failure, judged from the
+    /// rustls error message.
+    fn tls_hint(message: &str, network: &NetworkConfig) -> Option<&'static str> {
+        let lower = message.to_lowercase();
+        if lower.contains("unknownissuer") || lower.contains("unknown issuer") {
+            Some(if network.ca_bundle_path.is_some() {
+                "The server's certificate isn't signed by ca_bundle_path or a system CA"
+            } else {
+                "The server's certificate isn't trusted: set ca_bundle_path to your CA bundle"
+            })
+        } else if lower.contains("certificate required")
+            || lower.contains("certificaterequired")
+            || lower.contains("handshake failure")
+            || lower.contains("handshakefailure")
+        {
+            Some(if network.client_cert_path.is_some() {
+                "The server rejected the client certificate in client_cert_path"
+            } else {
+                "The server may require a client certificate: set client_cert_path"
+            })
+        } else if lower.contains("corrupt message") {
+            Some("The endpoint doesn't seem to speak TLS: check http:// vs https://")
+        } else if lower.contains("expired") || lower.contains("notvalidyet") {
+            Some("A certificate is outside its validity period: check the system clock")
+        } else {
+            None
+        }
+    }
 
-    async fn run_split_flow(
-        &self,
-        git: &GitService,
-        groups: Vec<crate::services::splitter::CommitGroup>,
-        changes: &StagedChanges,
-        symbols: &[CodeSymbol],
-        symbol_diffs: &[crate::domain::diff::SymbolDiff],
-    ) -> Result<()> {
-        // Safety: check for files with both staged and unstaged changes
-        let overlap = git.has_unstaged_overlap().await?;
-        if !overlap.is_empty() {
-            eprintln!(
-                "{} Cannot split: some staged files also have unstaged changes:",
-                style("warning:").yellow().bold()
-            );
-            for path in &overlap {
-                eprintln!("  {}", path.display());
+    // ─── Reword ───
+
+    /// Regenerate the messages of existing commits and rewrite them in place.
+    async fn run_reword(&self, range: &str) -> Result<()> {
+        let git = GitService::discover()?;
+        let commits = git.resolve_range(range).await?;
+        // Fail before any LLM call if the range can't be replayed linearly
+        git.reword_chain(&commits).await?;
+
+        let progress = Progress::new(self.cli.verbose);
+        progress.phase(&format!(
+            "Contacting {} ({})...",
+            self.config.active_profile().name,
+            self.config.model
+        ));
+
+        let provider = self.create_provider(&self.config)?;
+        provider.verify().await?;
+        Self::report_fallback(&provider, &progress);
+
+        let analyzer = AnalyzerService::new()?;
+        let system_prompt = self.resolve_system_prompt()?;
+        let mut rewrites: Vec<(String, String, String)> = Vec::new();
+
+        for (i, sha) in commits.iter().enumerate() {
+            if self.cancel_token.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+
+            let short = GitService::short_sha(sha);
+            progress.phase(&format!(
+                "Rewording commit {}/{} ({})...",
+                i + 1,
+                commits.len(),
+                short
+            ));
+
+            let (changes, full_diff) = match git
+                .get_commit_changes(
+                    sha,
+                    self.config.max_file_lines,
+                    self.config.rename_threshold,
+                )
+                .await
+            {
+                Ok(result) => result,
+                Err(Error::NoStagedChanges) => {
+                    progress.info(&format!("{short}: no file changes, keeping message"));
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            let changes = match self.apply_exclude_patterns(changes, &progress) {
+                Ok(c) => c,
+                Err(Error::NoStagedChanges) => {
+                    progress.info(&format!("{short}: all files excluded, keeping message"));
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            if self.guard_secrets(&full_diff, &progress)? {
+                provider.mark_sensitive();
+            }
+
+            let file_paths: Vec<PathBuf> = changes.files.iter().map(|f| f.path.clone()).collect();
+            let (new_map, old_map) = git.fetch_commit_file_contents(sha, &file_paths).await;
+            let (symbols, symbol_diffs) =
+                analyzer.extract_symbols(&changes.files, &new_map, &old_map);
+
+            let context = ContextBuilder::build(&changes, &symbols, &symbol_diffs, &self.config);
+            let prompt = self.resolve_user_prompt(&context)?;
+
+            if self.cli.show_prompt {
+                progress.finish();
+                eprintln!("{}", style(format!("--- PROMPT ({short}) ---")).dim());
+                eprintln!("{}", prompt);
+                eprintln!("{}", style("--- END PROMPT ---").dim());
+                continue;
             }
+
+            let message = self
+                .generate_quiet(&provider, &context, &prompt, &system_prompt)
+                .await?;
+            let original = git.commit_message(sha).await?;
+            rewrites.push((sha.clone(), original, message));
+        }
+
+        progress.finish();
+
+        if self.cli.show_prompt {
+            return Ok(());
+        }
+
+        if rewrites.is_empty() {
+            eprintln!("{} Nothing to reword", style("info:").cyan());
+            return Ok(());
+        }
+
+        Self::display_reword_overview(&rewrites);
+
+        if self.cli.dry_run {
+            for (sha, _, message) in &rewrites {
+                println!("{}\n{}\n", GitService::short_sha(sha), message);
+            }
+            return Ok(());
+        }
+
+        let targets: Vec<String> = rewrites.iter().map(|(sha, _, _)| sha.clone()).collect();
+        let replayed = git.reword_chain(&targets).await?;
+        let signed = git.signed_commits(&replayed).await?;
+        if !signed.is_empty() {
             eprintln!(
-                "{} Stash or commit unstaged changes first, or use --no-split",
-                style("info:").cyan()
+                "{} {} of the {} replayed commits are signed; rewording drops their signatures.",
+                style("warning:").yellow().bold(),
+                signed.len(),
+                replayed.len()
             );
-            return Err(Error::SplitAborted);
+        }
+
+        let is_interactive = std::io::stdout().is_terminal() && std::io::stdin().is_terminal();
+        if !self.cli.yes {
+            if !is_interactive {
+                eprintln!("{}", style("warning:").yellow().bold());
+                eprintln!("  Not a terminal. Use --yes to rewrite commits in scripts.");
+                return Ok(());
+            }
+
+            let confirm = Confirm::new()
+                .with_prompt(format!("Rewrite {} commit messages?", rewrites.len()))
+                .default(true)
+                .interact()?;
+            if !confirm {
+                return Err(Error::Cancelled);
+            }
+        }
+
+        let messages: HashMap<String, String> = rewrites
+            .into_iter()
+            .map(|(sha, _, message)| (sha, message))
+            .collect();
+        let new_head = git.rewrite_messages(&messages).await?;
+
+        eprintln!(
+            "{} Reworded {} commits (HEAD is now {})",
+            style("✓").green().bold(),
+            messages.len(),
+            GitService::short_sha(&new_head),
+        );
+
+        Ok(())
+    }
+
+    fn display_reword_overview(rewrites: &[(String, String, String)]) {
+        eprintln!();
+        eprintln!("{}", style("→ Proposed messages:").cyan().bold());
+        eprintln!();
+
+        for (sha, original, message) in rewrites {
+            let old_subject = original.lines().next().unwrap_or("(empty)");
+            let new_subject = message.lines().next().unwrap_or("(empty)");
+            eprintln!("  {}", style(GitService::short_sha(sha)).yellow());
+            eprintln!("    - {}", style(old_subject).dim());
+            eprintln!("    + {}", style(new_subject).green());
+        }
+        eprintln!();
+    }
+
+    // ─── Pull Request ───
+
+    /// Generate a PR title and Markdown description for `base..HEAD`.
+    async fn run_pr(&self, base: &str) -> Result<()> {
+        // PR descriptions run much longer than commit messages
+        const PR_MIN_TOKENS: u32 = 1024;
+
+        let progress = Progress::new(self.cli.verbose);
+        progress.phase("Analyzing branch...");
+
+        let git = GitService::discover()?;
+        let merge_base = git.merge_base(base, "HEAD").await?;
+        let branch = git
+            .current_branch()
+            .await
+            .unwrap_or_else(|| "HEAD".to_string());
+
+        let commits =
+            HistoryService::fetch_commits(git.work_dir(), &format!("{merge_base}..HEAD")).await?;
+        if commits.is_empty() {
+            return Err(Error::Git(format!("No commits between '{base}' and HEAD")));
+        }
+
+        let (changes, full_diff) = match git
+            .get_range_changes(
+                &merge_base,
+                "HEAD",
+                self.config.max_file_lines,
+                self.config.rename_threshold,
+            )
+            .await
+        {
+            Err(Error::NoStagedChanges) => {
+                return Err(Error::Git(format!(
+                    "Branch has no net changes against '{base}'"
+                )));
+            }
+            other => other?,
+        };
+
+        progress.info(&format!(
+            "{} commits, {} files changed (+{} -{})",
+            commits.len(),
+            changes.files.len(),
+            changes.stats.insertions,
+            changes.stats.deletions
+        ));
+
+        let changes = self.apply_exclude_patterns(changes, &progress)?;
+        let sensitive = self.guard_secrets(&full_diff, &progress)?;
+
+        progress.phase("Extracting code symbols...");
+        let analyzer = AnalyzerService::new()?;
+        let file_paths: Vec<PathBuf> = changes.files.iter().map(|f| f.path.clone()).collect();
+        let (new_map, old_map) = git
+            .fetch_range_file_contents(&merge_base, "HEAD", &file_paths)
+            .await;
+        let (symbols, symbol_diffs) = analyzer.extract_symbols(&changes.files, &new_map, &old_map);
+
+        let mut context = ContextBuilder::build(&changes, &symbols, &symbol_diffs, &self.config);
+        if self.config.learn_from_history {
+            context.history_context =
+                HistoryService::analyze(git.work_dir(), self.config.history_sample_size)
+                    .await
+                    .map(|ctx| ctx.to_prompt_section(self.config.history_sample_size));
+        }
+
+        let prompt = PrBuilder::build_prompt(&context, &branch, base, &commits);
+        if self.cli.show_prompt {
+            progress.finish();
+            eprintln!("{}", style("--- PROMPT ---").dim());
+            eprintln!("{}", prompt);
+            eprintln!("{}", style("--- END PROMPT ---").dim());
+            return Ok(());
+        }
+
+        if self.cancel_token.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        progress.phase(&format!(
+            "Contacting {} ({})...",
+            self.config.active_profile().name,
+            self.config.model
+        ));
+
+        let mut pr_config = self.config.clone();
+        pr_config.num_predict = pr_config.num_predict.max(PR_MIN_TOKENS);
+        let provider = self.create_provider(&pr_config)?;
+        if sensitive {
+            provider.mark_sensitive();
+        }
+        provider.verify().await?;
+        Self::report_fallback(&provider, &progress);
+
+        progress.phase("Writing pull request description...");
+        let (tx, mut rx) = mpsc::channel::<String>(64);
+        let drain_handle = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+        let raw = provider
+            .generate(
+                &prompt,
+                PR_SYSTEM_PROMPT,
+                None,
+                tx,
+                self.cancel_token.clone(),
+            )
+            .await?;
+        let _ = drain_handle.await;
+        progress.finish();
+
+        let description = PrBuilder::parse(&raw).ok_or_else(|| Error::Provider {
+            provider: provider.name().into(),
+            message: "response was not a valid pull request description".into(),
+        })?;
+
+        let breaking = PrBuilder::collect_breaking(&description, &commits);
+        let title = PrBuilder::format_title(
+            &description,
+            context.suggested_type,
+            !breaking.is_empty(),
+            &self.config.format,
+        );
+        let body = PrBuilder::render_markdown(&description, &breaking, &commits, &changes);
+        let output = format!("{}\n\n{}", title, body);
+
+        if self.cli.clipboard {
+            Self::copy_to_clipboard(&output)?;
+            eprintln!("{} Copied to clipboard!", style("✓").green().bold());
+        }
+        println!("{}", output);
+
+        Ok(())
+    }
+
+    // ─── Changelog ───
+
+    /// Render release notes for `range` from its conventional commits.
+    async fn run_changelog(
+        &self,
+        range: &str,
+        format: ChangelogFormat,
+        release: Option<&str>,
+        bump_only: bool,
+    ) -> Result<()> {
+        let git = GitService::discover()?;
+
+        if range.contains("...") {
+            return Err(Error::Config(format!(
+                "'{range}' is a symmetric difference; only <from>..<to> ranges are supported"
+            )));
+        }
+        // A bare revision means "everything since <rev>"
+        let (from, to) = range.split_once("..").unwrap_or((range, ""));
+        let to = if to.is_empty() { "HEAD" } else { to };
+        let range = format!("{from}..{to}");
+
+        let commits = HistoryService::fetch_commits(git.work_dir(), &range).await?;
+        if commits.is_empty() {
+            return Err(Error::Git(format!("No commits in range '{range}'")));
+        }
+
+        let mut changelog = ChangelogBuilder::build(&range, &commits);
+        let suggested = changelog.bump.apply(from);
+
+        if bump_only {
+            println!(
+                "{}",
+                suggested.unwrap_or_else(|| changelog.bump.to_string())
+            );
+            return Ok(());
+        }
+
+        changelog.version = release.map(str::to_string).or(suggested);
+        if changelog.version.is_some() {
+            changelog.date = git.commit_date(to).await.ok();
+        }
+
+        eprintln!(
+            "{} {} commits, suggested bump: {}",
+            style("info:").cyan(),
+            commits.len(),
+            style(changelog.bump).bold()
+        );
+
+        let output = match format {
+            ChangelogFormat::Markdown => changelog.render_markdown(),
+            ChangelogFormat::Json => {
+                serde_json::to_string_pretty(&changelog).expect("Changelog serializes to JSON")
+            }
+        };
+
+        if self.cli.clipboard {
+            Self::copy_to_clipboard(&output)?;
+            eprintln!("{} Copied to clipboard!", style("✓").green().bold());
+        }
+        println!("{}", output);
+
+        Ok(())
+    }
+
+    // ─── Lint ───
+
+    /// Check every commit message in `range` against Conventional Commits
+    /// and the evidence from that commit's own diff.
+    async fn run_lint(&self, range: &str) -> Result<()> {
+        let git = GitService::discover()?;
+        let commits = git.resolve_range(range).await?;
+        let analyzer = AnalyzerService::new()?;
+        let progress = Progress::new(self.cli.verbose);
+
+        // (short sha, subject, issues); `None` issues means skipped
+        let mut reports: Vec<(String, String, Option<Vec<String>>)> = Vec::new();
+        for (i, sha) in commits.iter().enumerate() {
+            if self.cancel_token.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+
+            let short = GitService::short_sha(sha).to_string();
+            progress.phase(&format!(
+                "Linting commit {}/{} ({})...",
+                i + 1,
+                commits.len(),
+                short
+            ));
+
+            let message = git.commit_message(sha).await?;
+            let message = message.trim();
+            let subject = message.lines().next().unwrap_or_default().to_string();
+            if CommitLinter::is_exempt(message) {
+                reports.push((short, subject, None));
+                continue;
+            }
+
+            let context = self.commit_context(&git, &analyzer, sha, &progress).await?;
+            let issues = CommitLinter::lint(message, context.as_ref());
+            reports.push((short, subject, Some(issues)));
+        }
+        progress.finish();
+
+        let mut failed = 0;
+        let mut total_issues = 0;
+        for (short, subject, issues) in &reports {
+            match issues {
+                None => println!("{} {} {}", style("-").dim(), short, style(subject).dim()),
+                Some(issues) if issues.is_empty() => {
+                    println!("{} {} {}", style("✓").green(), short, subject);
+                }
+                Some(issues) => {
+                    failed += 1;
+                    total_issues += issues.len();
+                    println!("{} {} {}", style("✗").red().bold(), short, subject);
+                    for issue in issues {
+                        println!("    - {issue}");
+                    }
+                }
+            }
+        }
+
+        if failed > 0 {
+            return Err(Error::RangeLintFailed {
+                commits: failed,
+                issues: total_issues,
+            });
+        }
+
+        eprintln!(
+            "{} {} commit message(s) passed",
+            style("✓").green().bold(),
+            reports.len()
+        );
+        Ok(())
+    }
+
+    /// Evidence for one existing commit, diffed against its first parent.
+    /// `None` when the commit has no file changes left to check (empty,
+    /// merge, or everything excluded).
+    async fn commit_context(
+        &self,
+        git: &GitService,
+        analyzer: &AnalyzerService,
+        sha: &str,
+        progress: &Progress,
+    ) -> Result<Option<PromptContext>> {
+        let changes = match git
+            .get_commit_changes(
+                sha,
+                self.config.max_file_lines,
+                self.config.rename_threshold,
+            )
+            .await
+            .and_then(|(changes, _)| self.apply_exclude_patterns(changes, progress))
+        {
+            Ok(changes) => changes,
+            Err(Error::NoStagedChanges) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let file_paths: Vec<PathBuf> = changes.files.iter().map(|f| f.path.clone()).collect();
+        let (new_map, old_map) = git.fetch_commit_file_contents(sha, &file_paths).await;
+        let (symbols, symbol_diffs) = analyzer.extract_symbols(&changes.files, &new_map, &old_map);
+
+        Ok(Some(ContextBuilder::build(
+            &changes,
+            &symbols,
+            &symbol_diffs,
+            &self.config,
+        )))
+    }
+
+    // ─── Split Detection ───
+
+    async fn run_split_flow(
+        &self,
+        git: &GitService,
+        groups: Vec<crate::services::splitter::CommitGroup>,
+        changes: &StagedChanges,
+        symbols: &[CodeSymbol],
+        symbol_diffs: &[crate::domain::diff::SymbolDiff],
+        sensitive: bool,
+    ) -> Result<()> {
+        // Commits are built from this snapshot of the index, never from the
+        // working tree, so unstaged edits to staged files survive the split.
+        // Files split across groups are committed hunk by hunk from their
+        // staged diff, captured at the same time.
+        let snapshot = git.snapshot_index().await?;
+        let trailers = self.commit_trailers(git).await?;
+        let mut file_diffs: HashMap<&Path, String> = HashMap::new();
+        for path in groups.iter().flat_map(|g| g.hunks.keys()) {
+            if !file_diffs.contains_key(path.as_path()) {
+                file_diffs.insert(path, git.staged_file_diff(path).await?);
+            }
         }
 
         let progress = Progress::new(self.cli.verbose);
         // Generate messages for each group
         progress.phase(&format!(
             "Contacting {} ({})...",
-            self.config.provider, self.config.model
+            self.config.active_profile().name,
+            self.config.model
         ));
         progress.finish();
 
-        let provider = llm::create_provider(&self.config)?;
+        let provider = self.create_provider(&self.config)?;
+        if sensitive {
+            provider.mark_sensitive();
+        }
         provider.verify().await?;
+        Self::report_fallback(&provider, &progress);
 
         let system_prompt = self.resolve_system_prompt()?;
-        let mut commit_messages: Vec<(String, Vec<PathBuf>)> = Vec::new();
+        let mut commit_messages: Vec<String> = Vec::new();
 
         for (i, group) in groups.iter().enumerate() {
             if self.cancel_token.is_cancelled() {
@@ -656,12 +1576,7 @@ impl App {
             );
 
             // Build sub-context for this group
-            let sub_changes = changes.subset(&group.files);
-            let sub_symbols: Vec<CodeSymbol> = symbols
-                .iter()
-                .filter(|s| group.files.contains(&s.file))
-                .cloned()
-                .collect();
+            let (sub_changes, sub_symbols) = CommitSplitter::group_context(group, changes, symbols);
 
             let sub_diffs: Vec<_> = symbol_diffs
                 .iter()
@@ -670,10 +1585,14 @@ impl App {
                 .collect();
             let mut context =
                 ContextBuilder::build(&sub_changes, &sub_symbols, &sub_diffs, &self.config);
+            // The group's type and scope were shown to the user, who may have edited them
+            context.suggested_type = group.commit_type;
+            context.suggested_scope.clone_from(&group.scope);
             context.group_rationale = Some(Self::infer_group_rationale(
                 &sub_changes,
                 &group.commit_type,
             ));
+            context.trailers.clone_from(&trailers);
             let prompt = self.resolve_user_prompt(&context)?;
 
             if self.cli.show_prompt {
@@ -685,6 +1604,7 @@ impl App {
                 eprintln!("{}", style("--- END PROMPT ---").dim());
             }
 
+            self.check_budget(&provider)?;
             let (tx, mut rx) = mpsc::channel::<String>(64);
             let cancel_for_printer = self.cancel_token.clone();
             let print_handle = tokio::spawn(async move {
@@ -702,7 +1622,13 @@ impl App {
             });
 
             let raw_message = provider
-                .generate(&prompt, &system_prompt, tx, self.cancel_token.clone())
+                .generate(
+                    &prompt,
+                    &system_prompt,
+                    Some(StructuredCommit::json_schema()),
+                    tx,
+                    self.cancel_token.clone(),
+                )
                 .await?;
 
             if let Err(e) = print_handle.await {
@@ -727,39 +1653,62 @@ impl App {
                 .await
                 .unwrap_or(raw_message);
 
-            let message = CommitSanitizer::sanitize(&raw_to_sanitize, &self.config.format)?;
-            commit_messages.push((message, group.files.clone()));
+            let message = CommitSanitizer::sanitize_with_trailers(
+                &raw_to_sanitize,
+                &self.config.format,
+                &context.trailers,
+            )?;
+            commit_messages.push(message);
         }
 
         // Display overview
-        Self::display_split_overview(&commit_messages);
+        Self::display_split_overview(&commit_messages, &groups);
 
         // Dry run: stop here
         if self.cli.dry_run {
-            for (msg, _) in &commit_messages {
+            for msg in &commit_messages {
                 println!("\n{}", msg);
             }
             return Ok(());
         }
 
-        // Confirm
-        let confirm = Confirm::new()
-            .with_prompt(format!("Create {} commits?", commit_messages.len()))
-            .default(true)
-            .interact()?;
+        // Confirm, unless --split auto already did
+        if self.cli.split != Some(SplitMode::Auto) {
+            let confirm = Confirm::new()
+                .with_prompt(format!("Create {} commits?", commit_messages.len()))
+                .default(true)
+                .interact()?;
 
-        if !confirm {
-            return Err(Error::Cancelled);
+            if !confirm {
+                return Err(Error::Cancelled);
+            }
         }
 
-        // Execute: unstage all, then stage+commit per group.
+        // Execute: commit each group from the snapshot on top of the last.
         // NOTE: This is non-atomic — if an intermediate commit fails, earlier
         // commits are already applied with no automatic rollback. The index
-        // state between unstage_all() and stage_files() is also a TOCTOU window.
-        for (i, (message, files)) in commit_messages.iter().enumerate() {
-            git.unstage_all().await?;
-            git.stage_files(files).await?;
-            git.commit(message).await?;
+        // and working tree are never modified, so nothing staged is lost.
+        for (i, (message, group)) in commit_messages.iter().zip(&groups).enumerate() {
+            let mut whole: Vec<PathBuf> = Vec::new();
+            for path in group.files.iter().filter(|p| !group.hunks.contains_key(*p)) {
+                whole.push(path.clone());
+                // A rename also removes the old path
+                if let Some(old) = changes
+                    .files
+                    .iter()
+                    .find(|f| &f.path == path)
+                    .and_then(|f| f.old_path.clone())
+                {
+                    whole.push(old);
+                }
+            }
+            let patches: Vec<String> = group
+                .hunks
+                .iter()
+                .map(|(path, indices)| DiffHunk::select(&file_diffs[path.as_path()], indices))
+                .collect();
+            git.commit_from_snapshot(&snapshot, &whole, &patches, message)
+                .await?;
 
             eprintln!(
                 "{} Commit {}/{}: {}",
@@ -858,33 +1807,81 @@ impl App {
                 files_label,
             );
 
-            for file_path in &group.files {
-                if let Some(fc) = changes.files.iter().find(|f| f.path == *file_path) {
-                    let status = match fc.status {
-                        ChangeStatus::Added => "[+]",
-                        ChangeStatus::Modified => "[M]",
-                        ChangeStatus::Deleted => "[-]",
-                        ChangeStatus::Renamed => "[R]",
-                    };
-                    eprintln!(
-                        "    {} {} (+{} -{})",
-                        status,
-                        file_path.display(),
-                        fc.additions,
-                        fc.deletions,
-                    );
-                }
+            let (sub_changes, _) = CommitSplitter::group_context(group, changes, &[]);
+            for fc in &sub_changes.files {
+                let status = match fc.status {
+                    ChangeStatus::Added => "[+]",
+                    ChangeStatus::Modified => "[M]",
+                    ChangeStatus::Deleted => "[-]",
+                    ChangeStatus::Renamed => "[R]",
+                };
+                eprintln!(
+                    "    {} {} (+{} -{})",
+                    status,
+                    group.file_label(&fc.path),
+                    fc.additions,
+                    fc.deletions,
+                );
             }
             eprintln!();
         }
     }
 
-    fn display_split_overview(commits: &[(String, Vec<PathBuf>)]) {
+    /// Open the split plan in `$EDITOR` until it parses. Returns `None` when
+    /// the user keeps the current groups, and an empty list when the plan was
+    /// emptied to cancel the split.
+    fn edit_split_groups(
+        groups: &[crate::services::splitter::CommitGroup],
+        changes: &StagedChanges,
+    ) -> Result<Option<Vec<crate::services::splitter::CommitGroup>>> {
+        let mut plan = SplitEditor::render(groups);
+        loop {
+            let Some(edited) = Editor::new()
+                .extension(".txt")
+                .edit(&plan)
+                .map_err(|e| Error::Dialog(e.to_string()))?
+            else {
+                return Ok(None);
+            };
+
+            match SplitEditor::parse(&edited, changes) {
+                Ok(parsed) => {
+                    let left_out = SplitEditor::left_out(&parsed, changes);
+                    if !parsed.is_empty() && !left_out.is_empty() {
+                        eprintln!(
+                            "{} Left out of the split (stays staged):",
+                            style("info:").cyan()
+                        );
+                        for path in &left_out {
+                            eprintln!("  {}", path.display());
+                        }
+                    }
+                    return Ok(Some(parsed));
+                }
+                Err(e) => {
+                    eprintln!("{} {}", style("error:").red().bold(), e);
+                    let retry = Confirm::new()
+                        .with_prompt("Edit the plan again?")
+                        .default(true)
+                        .interact()?;
+                    if !retry {
+                        return Ok(None);
+                    }
+                    plan = edited;
+                }
+            }
+        }
+    }
+
+    fn display_split_overview(
+        commits: &[String],
+        groups: &[crate::services::splitter::CommitGroup],
+    ) {
         eprintln!();
         eprintln!("{}", style("→ Proposed commits:").cyan().bold());
         eprintln!();
 
-        for (i, (message, files)) in commits.iter().enumerate() {
+        for (i, (message, group)) in commits.iter().zip(groups).enumerate() {
             let first_line = message.lines().next().unwrap_or("(empty)");
             eprintln!(
                 "  Commit {}/{}: {}",
@@ -893,16 +1890,75 @@ impl App {
                 style(first_line).green(),
             );
 
-            let files_str: Vec<String> = files.iter().map(|p| p.display().to_string()).collect();
+            let files_str: Vec<String> = group.files.iter().map(|p| group.file_label(p)).collect();
             eprintln!("    Files: {}", files_str.join(", "));
             eprintln!();
         }
     }
 
+    // ─── Candidate Generation ───
+
+    /// Generate, validate and sanitize one candidate, forwarding streamed
+    /// tokens to `token_tx`. Returns `None` when the response was empty or
+    /// couldn't be sanitized.
+    pub(crate) async fn generate_candidate(
+        &self,
+        index: usize,
+        provider: &llm::LlmBackend,
+        context: &PromptContext,
+        prompt: &str,
+        system_prompt: &str,
+        token_tx: mpsc::Sender<String>,
+    ) -> Result<Option<String>> {
+        if self.cancel_token.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        self.check_budget(provider)?;
+
+        let raw_message = provider
+            .generate(
+                prompt,
+                system_prompt,
+                Some(StructuredCommit::json_schema()),
+                token_tx,
+                self.cancel_token.clone(),
+            )
+            .await?;
+
+        if raw_message.trim().is_empty() {
+            warn!(candidate = index + 1, "empty response from LLM, skipping");
+            return Ok(None);
+        }
+
+        debug!(
+            raw_len = raw_message.len(),
+            candidate = index + 1,
+            "sanitizing LLM response"
+        );
+
+        // Validate against evidence and retry once if violations found
+        let raw_to_sanitize = self
+            .validate_and_retry(&raw_message, context, provider, prompt, system_prompt)
+            .await
+            .unwrap_or(raw_message);
+
+        match CommitSanitizer::sanitize_with_trailers(
+            &raw_to_sanitize,
+            &self.config.format,
+            &context.trailers,
+        ) {
+            Ok(msg) => Ok(Some(msg)),
+            Err(e) => {
+                warn!(candidate = index + 1, error = %e, "failed to sanitize candidate");
+                Ok(None)
+            }
+        }
+    }
+
     // ─── Candidate Selection ───
 
     fn select_candidate(&self, candidates: &[String]) -> Result<String> {
-        if self.cli.yes {
+        if self.auto_confirm() {
             return Ok(candidates[0].clone());
         }
 
@@ -935,21 +1991,296 @@ impl App {
             .interact()
             .map_err(|e| Error::Dialog(e.to_string()))?;
 
-        let chosen = &candidates[selection];
-        eprintln!("\n{}", style("Selected:").bold());
-        eprintln!("{}", style(chosen).green());
-        eprintln!();
+        let chosen = &candidates[selection];
+        eprintln!("\n{}", style("Selected:").bold());
+        eprintln!("{}", style(chosen).green());
+        eprintln!();
+
+        Ok(chosen.clone())
+    }
+
+    // ─── Usage ───
+
+    /// Build the provider for `config`, recording its requests in this run's
+    /// usage log. Once a budget is reached, a cloud primary is refused and
+    /// cloud fallbacks are dropped from the chain.
+    pub(crate) fn create_provider(&self, config: &Config) -> Result<llm::LlmBackend> {
+        if let Some(message) = self.budget_exceeded() {
+            let active = config.active_profile();
+            if active.is_cloud() {
+                return Err(Error::BudgetExceeded {
+                    provider: active.name,
+                    message,
+                });
+            }
+
+            let mut config = config.clone();
+            config.fallback = config
+                .fallback
+                .iter()
+                .filter(|name| {
+                    let cloud = config
+                        .named_profile(name)
+                        .is_some_and(|profile| profile.is_cloud());
+                    if cloud {
+                        warn!(provider = %name, "skipping cloud fallback: {message}");
+                    }
+                    !cloud
+                })
+                .cloned()
+                .collect();
+            return Ok(llm::create_provider(&config)?.with_usage_log(self.usage.clone()));
+        }
+
+        Ok(llm::create_provider(config)?.with_usage_log(self.usage.clone()))
+    }
+
+    /// Refuse to send another request through `provider` if it's a cloud
+    /// endpoint and a budget has been reached. Checked before every request,
+    /// so candidates, refinements and split groups stop at the budget too.
+    pub(crate) fn check_budget(&self, provider: &llm::LlmBackend) -> Result<()> {
+        if !provider.is_cloud() {
+            return Ok(());
+        }
+        match self.budget_exceeded() {
+            Some(message) => Err(Error::BudgetExceeded {
+                provider: provider.name().into(),
+                message,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// The budget that cloud usage has reached, counting this run's
+    /// requests. `None` without budgets or a ledger location.
+    fn budget_exceeded(&self) -> Option<String> {
+        if !self.config.usage.has_budget() {
+            return None;
+        }
+        self.flush_usage();
+        let ledger = Self::usage_ledger()?;
+        usage::budget_exceeded(&self.config.usage, &ledger.entries(), usage::now())
+    }
+
+    fn usage_ledger() -> Option<UsageLedger> {
+        Config::data_dir().map(|dir| UsageLedger::new(dir.join("usage.jsonl")))
+    }
+
+    /// Append requests completed since the last flush to the usage ledger.
+    /// A ledger that can't be written is reported but never fails the run.
+    pub(crate) fn flush_usage(&self) {
+        let requests = self.usage.take_unflushed();
+        if requests.is_empty() || !self.config.usage.ledger {
+            return;
+        }
+        let Some(ledger) = Self::usage_ledger() else {
+            return;
+        };
+        let now = usage::now();
+        let entries: Vec<LedgerEntry> = requests
+            .iter()
+            .map(|r| LedgerEntry::new(r, &self.config.usage, now))
+            .collect();
+        if let Err(e) = ledger.append(&entries) {
+            warn!(path = %ledger.path().display(), error = %e, "failed to write usage ledger");
+        }
+    }
+
+    /// `--timings`: tokens, time and cost of each request made this run.
+    fn print_usage_summary(&self) {
+        let requests = self.usage.requests();
+        eprintln!();
+        eprintln!("{}", style("LLM Requests").bold().underlined());
+        if requests.is_empty() {
+            eprintln!("  {}", style("none").dim());
+            return;
+        }
+
+        let mut total = TokenUsage::default();
+        let mut elapsed = std::time::Duration::ZERO;
+        let mut cost = 0.0;
+        let mut unpriced = 0;
+        for (i, request) in requests.iter().enumerate() {
+            let tokens = request.tokens.unwrap_or_default();
+            total.input_tokens += tokens.input_tokens;
+            total.output_tokens += tokens.output_tokens;
+            elapsed += request.elapsed;
+
+            let counts = match request.tokens {
+                Some(t) => format!("{} in / {} out", t.input_tokens, t.output_tokens),
+                None => "tokens not reported".into(),
+            };
+            let price = match self.config.usage.cost(
+                &request.model,
+                tokens.input_tokens,
+                tokens.output_tokens,
+            ) {
+                Some(c) => {
+                    cost += c;
+                    format!(", cost {c:.4}")
+                }
+                None => {
+                    unpriced += 1;
+                    String::new()
+                }
+            };
+            eprintln!(
+                "  {}. {} ({}): {}, {:.2}s{}",
+                i + 1,
+                request.profile,
+                request.model,
+                counts,
+                request.elapsed.as_secs_f64(),
+                price
+            );
+        }
+
+        let mut summary = format!(
+            "  Total: {} request(s), {} in / {} out, {:.2}s",
+            requests.len(),
+            total.input_tokens,
+            total.output_tokens,
+            elapsed.as_secs_f64()
+        );
+        if unpriced < requests.len() {
+            summary.push_str(&format!(", cost {cost:.4}"));
+        }
+        eprintln!("{}", style(summary).bold());
+    }
+
+    /// `commitbee usage`: today's and this month's totals from the ledger,
+    /// per model, against the configured budgets.
+    fn run_usage(&self) -> Result<()> {
+        let Some(ledger) = Self::usage_ledger() else {
+            return Err(Error::Config("Cannot determine data directory".into()));
+        };
+        let entries = ledger.entries();
+        let now = usage::now();
+        let day = usage::day_start(now);
+        let month = usage::month_start(now);
+
+        println!("Ledger: {}", ledger.path().display());
+        if !self.config.usage.ledger {
+            println!(
+                "Recording: {}",
+                style("off (usage.ledger = false)").yellow()
+            );
+        }
+
+        let describe = |totals: &UsageTotals| {
+            let mut line = format!(
+                "{} request(s), {} tokens ({} in / {} out)",
+                totals.requests,
+                totals.tokens(),
+                totals.input_tokens,
+                totals.output_tokens
+            );
+            if totals.unpriced < totals.requests {
+                line.push_str(&format!(", cost {:.4}", totals.cost));
+            }
+            if totals.unpriced > 0 && totals.unpriced < totals.requests {
+                line.push_str(&format!(" ({} unpriced)", totals.unpriced));
+            }
+            line
+        };
+
+        let today = UsageTotals::sum(entries.iter().filter(|e| e.timestamp >= day));
+        println!();
+        println!(
+            "Today ({} UTC): {}",
+            usage::format_date(now),
+            describe(&today)
+        );
+
+        let this_month: Vec<&LedgerEntry> =
+            entries.iter().filter(|e| e.timestamp >= month).collect();
+        println!(
+            "This month (since {}): {}",
+            usage::format_date(month),
+            describe(&UsageTotals::sum(this_month.iter().copied()))
+        );
+
+        let mut by_model: std::collections::BTreeMap<(&str, &str), Vec<&LedgerEntry>> =
+            std::collections::BTreeMap::new();
+        for entry in &this_month {
+            by_model
+                .entry((entry.profile.as_str(), entry.model.as_str()))
+                .or_default()
+                .push(entry);
+        }
+        for ((profile, model), entries) in by_model {
+            println!(
+                "  {} ({}): {}",
+                profile,
+                model,
+                describe(&UsageTotals::sum(entries.iter().copied()))
+            );
+        }
+
+        let budget = &self.config.usage;
+        if budget.has_budget() {
+            let cloud_today =
+                UsageTotals::sum(entries.iter().filter(|e| e.cloud && e.timestamp >= day));
+            let cloud_month =
+                UsageTotals::sum(entries.iter().filter(|e| e.cloud && e.timestamp >= month));
+            println!();
+            println!("Budgets (cloud providers only):");
+            if let Some(limit) = budget.daily_tokens {
+                println!("  Daily tokens: {} / {}", cloud_today.tokens(), limit);
+            }
+            if let Some(limit) = budget.monthly_tokens {
+                println!("  Monthly tokens: {} / {}", cloud_month.tokens(), limit);
+            }
+            if let Some(limit) = budget.daily_cost {
+                println!("  Daily cost: {:.2} / {:.2}", cloud_today.cost, limit);
+            }
+            if let Some(limit) = budget.monthly_cost {
+                println!("  Monthly cost: {:.2} / {:.2}", cloud_month.cost, limit);
+            }
+            if let Some(message) = usage::budget_exceeded(budget, &entries, now) {
+                println!("  {}: {}", style("REACHED").red().bold(), message);
+            }
+        }
+        Ok(())
+    }
+
+    // ─── Cache Commands ───
 
-        Ok(chosen.clone())
+    /// The response cache, or `None` with `--no-cache` or without a cache dir.
+    fn response_cache(&self) -> Option<ResponseCache> {
+        if self.cli.no_cache {
+            return None;
+        }
+        Config::cache_dir().map(|dir| ResponseCache::new(dir.join("responses")))
+    }
+
+    fn handle_cache(&self, action: &CacheAction) -> Result<()> {
+        match action {
+            CacheAction::Clear => {
+                let Some(dir) = Config::cache_dir() else {
+                    return Err(Error::Config("Cannot determine cache directory".into()));
+                };
+                let cache = ResponseCache::new(dir.join("responses"));
+                let removed = cache.clear()?;
+                eprintln!(
+                    "{} Removed {} cached response(s) from {}",
+                    style("✓").green().bold(),
+                    removed,
+                    cache.dir().display()
+                );
+                Ok(())
+            }
+        }
     }
 
     // ─── Hook Commands ───
 
-    fn handle_hook(&self, action: &HookAction) -> Result<()> {
+    async fn handle_hook(&self, action: &HookAction) -> Result<()> {
         match action {
-            HookAction::Install => self.hook_install(),
-            HookAction::Uninstall => self.hook_uninstall(),
+            HookAction::Install { kind } => self.hook_install(*kind),
+            HookAction::Uninstall { kind } => self.hook_uninstall(*kind),
             HookAction::Status => self.hook_status(),
+            HookAction::Lint { file } => self.hook_lint(file).await,
         }
     }
 
@@ -969,14 +2300,65 @@ impl App {
         Ok(PathBuf::from(git_dir).join("hooks"))
     }
 
-    fn hook_path(&self) -> Result<PathBuf> {
-        Ok(self.hook_dir()?.join("prepare-commit-msg"))
+    fn hook_script(kind: HookKind) -> &'static str {
+        match kind {
+            HookKind::PrepareCommitMsg => {
+                r#"#!/bin/sh
+# commitbee hook — auto-generated, do not edit
+# Generates commit messages using commitbee when committing interactively.
+# Squash commits get one message for all squashed commits.
+# Skips merge, amend, and message-provided commits.
+
+COMMIT_MSG_FILE="$1"
+COMMIT_SOURCE="$2"
+MODE=""
+
+# Skip non-interactive commits (merge, message, amend).
+# Amends can't be told apart from `--amend --no-edit`, which would silently
+# replace the message; use `commitbee --amend` for those instead.
+case "$COMMIT_SOURCE" in
+    squash)
+        MODE="--squash"
+        ;;
+    merge|message|commit)
+        exit 0
+        ;;
+esac
+
+# Only run if commitbee is available
+if ! command -v commitbee >/dev/null 2>&1; then
+    exit 0
+fi
+
+# Generate commit message and write to file
+MSG=$(commitbee $MODE --yes --dry-run 2>/dev/null)
+if [ $? -eq 0 ] && [ -n "$MSG" ]; then
+    printf '%s\n' "$MSG" > "$COMMIT_MSG_FILE"
+fi
+"#
+            }
+            HookKind::CommitMsg => {
+                r#"#!/bin/sh
+# commitbee hook — auto-generated, do not edit
+# Lints the commit message against the staged changes.
+# Rejects the commit only when lint_mode = "error"; bypass with --no-verify.
+
+# Only run if commitbee is available
+if ! command -v commitbee >/dev/null 2>&1; then
+    exit 0
+fi
+
+exec commitbee hook lint "$1"
+"#
+            }
+        }
     }
 
-    fn hook_install(&self) -> Result<()> {
+    fn hook_install(&self, kind: HookKind) -> Result<()> {
         let hooks_dir = self.hook_dir()?;
-        let hook_path = hooks_dir.join("prepare-commit-msg");
-        let backup_path = hooks_dir.join("prepare-commit-msg.commitbee-backup");
+        let name = kind.file_name();
+        let hook_path = hooks_dir.join(name);
+        let backup_path = hooks_dir.join(format!("{name}.commitbee-backup"));
 
         // Create hooks directory if needed
         std::fs::create_dir_all(&hooks_dir)?;
@@ -1006,36 +2388,9 @@ impl App {
             );
         }
 
-        let hook_script = r#"#!/bin/sh
-# commitbee hook — auto-generated, do not edit
-# Generates commit messages using commitbee when committing interactively.
-# Skips merge, squash, amend, and message-provided commits.
-
-COMMIT_MSG_FILE="$1"
-COMMIT_SOURCE="$2"
-
-# Skip non-interactive commits (merge, squash, message, amend)
-case "$COMMIT_SOURCE" in
-    merge|squash|message|commit)
-        exit 0
-        ;;
-esac
-
-# Only run if commitbee is available
-if ! command -v commitbee >/dev/null 2>&1; then
-    exit 0
-fi
-
-# Generate commit message and write to file
-MSG=$(commitbee --yes --dry-run 2>/dev/null)
-if [ $? -eq 0 ] && [ -n "$MSG" ]; then
-    printf '%s\n' "$MSG" > "$COMMIT_MSG_FILE"
-fi
-"#;
-
         // Write to temp file first, then rename (atomic)
-        let temp_path = hooks_dir.join(".prepare-commit-msg.tmp");
-        std::fs::write(&temp_path, hook_script)?;
+        let temp_path = hooks_dir.join(format!(".{name}.tmp"));
+        std::fs::write(&temp_path, Self::hook_script(kind))?;
 
         // Set executable permissions
         #[cfg(unix)]
@@ -1056,10 +2411,11 @@ fi
         Ok(())
     }
 
-    fn hook_uninstall(&self) -> Result<()> {
+    fn hook_uninstall(&self, kind: HookKind) -> Result<()> {
         let hooks_dir = self.hook_dir()?;
-        let hook_path = hooks_dir.join("prepare-commit-msg");
-        let backup_path = hooks_dir.join("prepare-commit-msg.commitbee-backup");
+        let name = kind.file_name();
+        let hook_path = hooks_dir.join(name);
+        let backup_path = hooks_dir.join(format!("{name}.commitbee-backup"));
 
         if !hook_path.exists() {
             eprintln!(
@@ -1105,54 +2461,134 @@ fi
     }
 
     fn hook_status(&self) -> Result<()> {
-        let hook_path = self.hook_path()?;
+        let hooks_dir = self.hook_dir()?;
 
-        if !hook_path.exists() {
-            eprintln!(
-                "{} No prepare-commit-msg hook installed",
-                style("✗").red().bold()
-            );
-            eprintln!(
-                "  Install with: {}",
-                style("commitbee hook install").yellow()
-            );
+        for kind in [HookKind::PrepareCommitMsg, HookKind::CommitMsg] {
+            let name = kind.file_name();
+            let hook_path = hooks_dir.join(name);
+
+            if !hook_path.exists() {
+                eprintln!("{} No {name} hook installed", style("✗").red().bold());
+                let install = match kind {
+                    HookKind::PrepareCommitMsg => "commitbee hook install".to_string(),
+                    HookKind::CommitMsg => format!("commitbee hook install --kind {name}"),
+                };
+                eprintln!("  Install with: {}", style(install).yellow());
+                continue;
+            }
+
+            let content = match std::fs::read_to_string(&hook_path) {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!(path = %hook_path.display(), error = %e, "failed to read hook file");
+                    String::new()
+                }
+            };
+            if content.contains("# commitbee hook") {
+                eprintln!(
+                    "{} CommitBee {name} hook is installed at {}",
+                    style("✓").green().bold(),
+                    hook_path.display()
+                );
+            } else {
+                eprintln!(
+                    "{} A {name} hook exists but was not installed by commitbee",
+                    style("info:").cyan()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lint the message git is about to commit. Evidence rules use the staged
+    /// diff; with nothing staged (e.g. `--allow-empty`) only format rules run.
+    async fn hook_lint(&self, file: &Path) -> Result<()> {
+        let message = CommitLinter::clean_message(&std::fs::read_to_string(file)?);
+        if CommitLinter::is_exempt(&message) {
+            debug!("message exempt from linting");
             return Ok(());
         }
 
-        let content = match std::fs::read_to_string(&hook_path) {
-            Ok(c) => c,
-            Err(e) => {
-                warn!(path = %hook_path.display(), error = %e, "failed to read hook file");
-                String::new()
-            }
+        let context = match self.staged_context().await {
+            Ok(context) => Some(context),
+            Err(Error::NoStagedChanges) => None,
+            Err(e) => return Err(e),
         };
-        if content.contains("# commitbee hook") {
-            eprintln!(
-                "{} CommitBee hook is installed at {}",
-                style("✓").green().bold(),
-                hook_path.display()
-            );
-        } else {
-            eprintln!(
-                "{} A prepare-commit-msg hook exists but was not installed by commitbee",
-                style("info:").cyan()
-            );
+
+        let issues = CommitLinter::lint(&message, context.as_ref());
+        if issues.is_empty() {
+            return Ok(());
         }
 
-        Ok(())
+        let label = match self.config.lint_mode {
+            LintMode::Error => style("error:").red().bold(),
+            LintMode::Warn => style("warning:").yellow().bold(),
+        };
+        eprintln!(
+            "{label} commit message: {}",
+            message.lines().next().unwrap_or_default()
+        );
+        for issue in &issues {
+            eprintln!("  - {issue}");
+        }
+
+        match self.config.lint_mode {
+            LintMode::Error => Err(Error::LintFailed {
+                count: issues.len(),
+            }),
+            LintMode::Warn => Ok(()),
+        }
+    }
+
+    /// Evidence for the staged changes, without prompts or LLM calls.
+    async fn staged_context(&self) -> Result<PromptContext> {
+        let progress = Progress::new(self.cli.verbose);
+        progress.phase("Analyzing staged changes...");
+        let git = GitService::discover()?;
+        let (changes, _) = git
+            .get_staged_changes(self.config.max_file_lines, self.config.rename_threshold)
+            .await?;
+        let changes = self.apply_exclude_patterns(changes, &progress)?;
+
+        let analyzer = AnalyzerService::new()?;
+        let file_paths: Vec<PathBuf> = changes.files.iter().map(|f| f.path.clone()).collect();
+        let (staged_map, head_map) = git.fetch_file_contents(&file_paths).await;
+        let (symbols, symbol_diffs) =
+            analyzer.extract_symbols(&changes.files, &staged_map, &head_map);
+
+        Ok(ContextBuilder::build(
+            &changes,
+            &symbols,
+            &symbol_diffs,
+            &self.config,
+        ))
     }
 
     // ─── Keyring Commands ───
 
+    /// Keyring entry for a cloud provider (`openai`, `anthropic`) or a
+    /// `[providers.<name>]` profile.
     #[cfg(feature = "secure-storage")]
-    fn set_api_key(&self, provider: &str) -> Result<()> {
+    fn keyring_entry_name(&self, provider: &str) -> Result<String> {
+        if self.config.providers.contains_key(provider)
+            || self.config.header_keyring_entries().contains(&provider)
+        {
+            return Ok(provider.to_string());
+        }
         let provider_lower = provider.to_lowercase();
         if provider_lower != "openai" && provider_lower != "anthropic" {
             return Err(Error::Config(format!(
-                "Keyring storage is only for cloud providers (openai, anthropic), got '{}'",
+                "Keyring storage is only for cloud providers (openai, anthropic) or provider profiles, got '{}'",
                 provider
             )));
         }
+        Ok(provider_lower)
+    }
+
+    #[cfg(feature = "secure-storage")]
+    fn set_api_key(&self, provider: &str) -> Result<()> {
+        let provider_lower = self.keyring_entry_name(provider)?;
 
         eprintln!(
             "Enter API key for {} (input will be hidden):",
@@ -1184,13 +2620,7 @@ fi
 
     #[cfg(feature = "secure-storage")]
     fn get_api_key(&self, provider: &str) -> Result<()> {
-        let provider_lower = provider.to_lowercase();
-        if provider_lower != "openai" && provider_lower != "anthropic" {
-            return Err(Error::Config(format!(
-                "Keyring storage is only for cloud providers (openai, anthropic), got '{}'",
-                provider
-            )));
-        }
+        let provider_lower = self.keyring_entry_name(provider)?;
 
         let entry = keyring::Entry::new("commitbee", &provider_lower)
             .map_err(|e| Error::Keyring(e.to_string()))?;
@@ -1250,13 +2680,18 @@ fi
                 }
             };
 
-            let violations = CommitValidator::validate(
+            let mut violations = CommitValidator::validate(
                 &structured,
                 context.has_bug_evidence,
                 context.is_mechanical,
                 context.public_api_removed_count,
                 context.is_dependency_only,
             );
+            violations.extend(CommitValidator::validate_issue_refs(
+                &structured,
+                &TrailerBuilder::issue_refs(&context.trailers, &self.config.trailers),
+                original_prompt,
+            ));
 
             if violations.is_empty() {
                 return if attempt == 1 {
@@ -1276,6 +2711,11 @@ fi
                 "retrying with corrections"
             );
 
+            if let Err(e) = self.check_budget(provider) {
+                warn!(attempt, error = %e, "not retrying");
+                break;
+            }
+
             let corrections = CommitValidator::format_corrections(&violations);
             let retry_prompt = format!("{}\n{}", original_prompt, corrections);
 
@@ -1284,7 +2724,13 @@ fi
             let drain_handle = tokio::spawn(async move { while rx.recv().await.is_some() {} });
 
             match provider
-                .generate(&retry_prompt, system_prompt, tx, cancel)
+                .generate(
+                    &retry_prompt,
+                    system_prompt,
+                    Some(StructuredCommit::json_schema()),
+                    tx,
+                    cancel,
+                )
                 .await
             {
                 Ok(retry_raw) if !retry_raw.trim().is_empty() => {
@@ -1310,8 +2756,51 @@ fi
 
     // ─── Prompt Helpers ───
 
+    /// Generate a single message without streaming output: generate, validate
+    /// against evidence (with retries), then sanitize.
+    async fn generate_quiet(
+        &self,
+        provider: &llm::LlmBackend,
+        context: &PromptContext,
+        prompt: &str,
+        system_prompt: &str,
+    ) -> Result<String> {
+        self.check_budget(provider)?;
+        let (tx, mut rx) = mpsc::channel::<String>(64);
+        let drain_handle = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+
+        let raw_message = provider
+            .generate(
+                prompt,
+                system_prompt,
+                Some(StructuredCommit::json_schema()),
+                tx,
+                self.cancel_token.clone(),
+            )
+            .await?;
+        let _ = drain_handle.await;
+
+        if raw_message.trim().is_empty() {
+            return Err(Error::Provider {
+                provider: provider.name().into(),
+                message: "Empty response from LLM".into(),
+            });
+        }
+
+        let raw_to_sanitize = self
+            .validate_and_retry(&raw_message, context, provider, prompt, system_prompt)
+            .await
+            .unwrap_or(raw_message);
+
+        CommitSanitizer::sanitize_with_trailers(
+            &raw_to_sanitize,
+            &self.config.format,
+            &context.trailers,
+        )
+    }
+
     /// Resolve the system prompt: load from file if configured, otherwise use built-in.
-    fn resolve_system_prompt(&self) -> Result<String> {
+    pub(crate) fn resolve_system_prompt(&self) -> Result<String> {
         if let Some(ref path) = self.config.system_prompt_path {
             template::load_file(path)
         } else {
@@ -1320,7 +2809,7 @@ fi
     }
 
     /// Resolve the user prompt: render from template if configured, otherwise use default.
-    fn resolve_user_prompt(&self, context: &PromptContext) -> Result<String> {
+    pub(crate) fn resolve_user_prompt(&self, context: &PromptContext) -> Result<String> {
         if let Some(ref path) = self.config.template_path {
             let symbols_text = self.build_symbols_text(context);
             let scope_text = context.suggested_scope.as_deref().unwrap_or("");
@@ -1374,6 +2863,7 @@ fi
         feedback: &str,
         context: &PromptContext,
     ) -> Result<String> {
+        self.check_budget(provider)?;
         let refinement_prompt =
             self.resolve_refinement_prompt(original_prompt, current_message, feedback)?;
 
@@ -1388,7 +2878,13 @@ fi
         });
 
         let raw_refined = provider
-            .generate(&refinement_prompt, system_prompt, tx, cancel)
+            .generate(
+                &refinement_prompt,
+                system_prompt,
+                Some(StructuredCommit::json_schema()),
+                tx,
+                cancel,
+            )
             .await?;
 
         print_handle.await.ok();
@@ -1406,11 +2902,28 @@ fi
             .await
             .unwrap_or(raw_refined);
 
-        CommitSanitizer::sanitize(&refined_to_sanitize, &self.config.format)
+        CommitSanitizer::sanitize_with_trailers(
+            &refined_to_sanitize,
+            &self.config.format,
+            &context.trailers,
+        )
+    }
+
+    /// Append the message being amended to the generation prompt, so the new
+    /// message can keep what still applies to the combined changes.
+    pub(crate) fn resolve_amend_prompt(
+        &self,
+        original_prompt: &str,
+        current_message: &str,
+    ) -> String {
+        format!(
+            "{}\n\n---\nThese changes amend an existing commit whose current message is:\n\"{}\"\n\nWrite a message for the combined changes. Keep details from the current message that still apply.\n\nRespond with ONLY the JSON object.",
+            original_prompt, current_message
+        )
     }
 
     /// Resolve the refinement prompt.
-    fn resolve_refinement_prompt(
+    pub(crate) fn resolve_refinement_prompt(
         &self,
         original_prompt: &str,
         previous_message: &str,
@@ -1422,11 +2935,91 @@ fi
         ))
     }
 
+    // ─── Safety Helpers ───
+
+    /// Scan a full unified diff for secrets and enforce the `--allow-secrets`
+    /// policy before anything is sent to the LLM.
+    ///
+    /// Returns `true` when secrets were found and the user approved sending
+    /// them, so callers can keep the diff away from remote fallback providers.
+    fn guard_secrets(&self, full_diff: &str, progress: &Progress) -> Result<bool> {
+        let secret_patterns = safety::build_patterns(
+            &self.config.custom_secret_patterns,
+            &self.config.disabled_secret_patterns,
+        );
+        let secrets = safety::scan_full_diff_with_patterns(full_diff, &secret_patterns);
+        if !secrets.is_empty() {
+            warn!(
+                count = secrets.len(),
+                "potential secrets detected in staged changes"
+            );
+            progress.warning("Potential secrets detected:");
+            for s in &secrets {
+                eprintln!(
+                    "  {} in {} (line ~{})",
+                    s.pattern_name,
+                    s.file,
+                    s.line.unwrap_or(0)
+                );
+            }
+
+            // Cloud providers: always block when secrets detected
+            if !self.cli.allow_secrets {
+                return Err(Error::SecretsDetected {
+                    patterns: secrets.iter().map(|s| s.pattern_name.clone()).collect(),
+                });
+            }
+
+            // --allow-secrets passed: always require interactive confirmation
+            if std::io::stdin().is_terminal() {
+                progress.finish();
+                eprintln!("\nwarning: Potential secrets detected in staged changes.");
+                for s in &secrets {
+                    eprintln!(
+                        "  {} in {} (line ~{})",
+                        s.pattern_name,
+                        s.file,
+                        s.line.unwrap_or(0)
+                    );
+                }
+                let profile = self.config.active_profile();
+                eprintln!(
+                    "Provider: {} ({})",
+                    profile.name,
+                    profile.base_url.as_deref().unwrap_or("cloud API")
+                );
+                eprint!("Send diff to LLM anyway? [y/N] ");
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input).ok();
+                if !input.trim().eq_ignore_ascii_case("y") {
+                    return Err(Error::SecretsDetected {
+                        patterns: secrets.iter().map(|s| s.pattern_name.clone()).collect(),
+                    });
+                }
+            } else {
+                // Non-interactive: always block even with --allow-secrets
+                return Err(Error::SecretsDetected {
+                    patterns: secrets.iter().map(|s| s.pattern_name.clone()).collect(),
+                });
+            }
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Tell the user when a fallback provider took over from the primary.
+    fn report_fallback(provider: &llm::LlmBackend, progress: &Progress) {
+        if let Some(label) = provider.fallback_label() {
+            progress.info(&format!("Using fallback provider {label}"));
+        }
+    }
+
     // ─── Exclude Helpers ───
 
     /// Filter staged changes by removing files matching exclude glob patterns.
     /// Returns the filtered changes. Excluded files are listed in output.
-    fn apply_exclude_patterns(
+    pub(crate) fn apply_exclude_patterns(
         &self,
         mut changes: StagedChanges,
         progress: &Progress,
@@ -1509,6 +3102,7 @@ mod tests {
             cli: Cli::default(),
             config: Config::default(),
             cancel_token: CancellationToken::new(),
+            usage: UsageLog::default(),
         };
 
         let original_prompt = "Original prompt";
@@ -1524,4 +3118,20 @@ mod tests {
         assert!(result.contains("Make it shorter"));
         assert!(result.contains("Respond with ONLY the refined JSON object."));
     }
+
+    #[test]
+    fn test_resolve_amend_prompt() {
+        let app = App {
+            cli: Cli::default(),
+            config: Config::default(),
+            cancel_token: CancellationToken::new(),
+            usage: UsageLog::default(),
+        };
+
+        let result = app.resolve_amend_prompt("Original prompt", "fix: old subject");
+
+        assert!(result.starts_with("Original prompt"));
+        assert!(result.contains("\"fix: old subject\""));
+        assert!(result.contains("Respond with ONLY the JSON object."));
+    }
 }
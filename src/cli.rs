@@ -4,12 +4,12 @@
 
 use clap::Parser;
 
-#[derive(Parser, Debug, Default)]
+#[derive(Parser, Clone, Debug, Default)]
 #[command(name = "commitbee")]
 #[command(version)]
 #[command(about = "AI-powered commit message generator", long_about = None)]
 pub struct Cli {
-    /// LLM provider (ollama, openai, anthropic)
+    /// LLM provider (ollama, openai, anthropic) or a [providers.<name>] profile
     #[arg(short, long, env = "COMMITBEE_PROVIDER")]
     pub provider: Option<String>,
 
@@ -25,6 +25,20 @@ pub struct Cli {
     #[arg(long)]
     pub dry_run: bool,
 
+    /// Regenerate the message for HEAD from its changes plus anything staged, then amend it
+    #[arg(long, conflicts_with = "split")]
+    pub amend: bool,
+
+    /// Write one message for squashed commits, listed in SQUASH_MSG (`git merge --squash`) or RANGE
+    #[arg(
+        long,
+        value_name = "RANGE",
+        num_args = 0..=1,
+        default_missing_value = "",
+        conflicts_with_all = ["amend", "split"]
+    )]
+    pub squash: Option<String>,
+
     /// Allow committing with detected secrets (local only)
     #[arg(long)]
     pub allow_secrets: bool,
@@ -41,6 +55,10 @@ pub struct Cli {
     #[arg(long)]
     pub no_split: bool,
 
+    /// Split without prompting: auto commits each group, plan prints groups as JSON
+    #[arg(long, value_enum, conflicts_with_all = ["no_split", "output"])]
+    pub split: Option<SplitMode>,
+
     /// Disable scope in commit messages
     #[arg(long)]
     pub no_scope: bool,
@@ -49,10 +67,26 @@ pub struct Cli {
     #[arg(long)]
     pub clipboard: bool,
 
+    /// Always query the LLM instead of reusing cached responses
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Output format: human-readable text or a versioned JSON document
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
     /// Exclude files matching glob pattern (repeatable)
     #[arg(long = "exclude", value_name = "GLOB")]
     pub exclude: Vec<String>,
 
+    /// Add Co-authored-by trailers for these [trailers.roster] handles
+    #[arg(long, value_name = "HANDLE", value_delimiter = ',')]
+    pub pair: Vec<String>,
+
+    /// Add a Signed-off-by trailer with your git identity
+    #[arg(short = 's', long)]
+    pub signoff: bool,
+
     /// Generate commit message in specified language (e.g., de, ja, fr)
     #[arg(long)]
     pub locale: Option<String>,
@@ -61,21 +95,88 @@ pub struct Cli {
     #[arg(short, long)]
     pub verbose: bool,
 
+    /// Print token usage, time and cost per LLM request when done
+    #[arg(long, visible_alias = "stats")]
+    pub timings: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
-#[derive(clap::Subcommand, Debug)]
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Commit message text
+    #[default]
+    Text,
+    /// JSON with the parsed message, candidates, evidence flags and split suggestion
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitMode {
+    /// Generate a message per suggested group and commit them in sequence
+    Auto,
+    /// Print the suggested groups as JSON and exit without committing
+    Plan,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChangelogFormat {
+    /// Keep-a-Changelog Markdown
+    #[default]
+    Markdown,
+    /// Machine-readable JSON
+    Json,
+}
+
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum CacheAction {
+    /// Delete all cached LLM responses
+    Clear,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HookKind {
+    /// Write a generated message before the editor opens
+    #[default]
+    PrepareCommitMsg,
+    /// Lint the message you wrote against the staged changes
+    CommitMsg,
+}
+
+impl HookKind {
+    /// File name of the hook in `.git/hooks`
+    #[must_use]
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::PrepareCommitMsg => "prepare-commit-msg",
+            Self::CommitMsg => "commit-msg",
+        }
+    }
+}
+
+#[derive(clap::Subcommand, Clone, Debug)]
 pub enum HookAction {
-    /// Install prepare-commit-msg hook
-    Install,
-    /// Remove prepare-commit-msg hook
-    Uninstall,
-    /// Check if hook is installed
+    /// Install a git hook (prepare-commit-msg by default)
+    Install {
+        #[arg(long, value_enum, default_value_t = HookKind::PrepareCommitMsg)]
+        kind: HookKind,
+    },
+    /// Remove a git hook installed by commitbee
+    Uninstall {
+        #[arg(long, value_enum, default_value_t = HookKind::PrepareCommitMsg)]
+        kind: HookKind,
+    },
+    /// Check which hooks are installed
     Status,
+    /// Lint a commit message file against the staged changes (run by the commit-msg hook)
+    Lint {
+        /// Path to the commit message file
+        file: std::path::PathBuf,
+    },
 }
 
-#[derive(clap::Subcommand, Debug)]
+#[derive(clap::Subcommand, Clone, Debug)]
 pub enum Commands {
     /// Initialize config file
     Init,
@@ -89,21 +190,67 @@ pub enum Commands {
         #[arg(value_enum)]
         shell: clap_complete::Shell,
     },
-    /// Manage prepare-commit-msg git hook
+    /// Regenerate messages for existing commits
+    Reword {
+        /// Commit or range to reword (e.g., HEAD, HEAD~3..HEAD, main..feature)
+        range: String,
+    },
+    /// Generate a pull request title and description for the current branch
+    Pr {
+        /// Base branch to compare against (uses the merge-base with HEAD)
+        #[arg(long, default_value = "main")]
+        base: String,
+    },
+    /// Generate release notes from conventional commits in a range
+    Changelog {
+        /// Commit range (e.g., v1.2.0..HEAD); a single revision means <rev>..HEAD
+        range: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ChangelogFormat::Markdown)]
+        format: ChangelogFormat,
+
+        /// Release version for the heading (default: suggested from the range start tag)
+        #[arg(long)]
+        release: Option<String>,
+
+        /// Print only the suggested next version (or bump level) and exit
+        #[arg(long)]
+        bump: bool,
+    },
+    /// Check commit messages in a range against Conventional Commits and their diffs
+    Lint {
+        /// Commit or range to check (e.g., origin/main..HEAD)
+        range: String,
+    },
+    /// Manage commitbee git hooks
     Hook {
         #[command(subcommand)]
         action: HookAction,
     },
+    /// Manage the LLM response cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Show token usage and cost from the local usage ledger
+    Usage,
+    /// Run a JSON-RPC server for editor integrations
+    Serve {
+        /// Speak JSON-RPC over stdin/stdout (the only transport)
+        #[arg(long)]
+        stdio: bool,
+    },
     /// Store API key in system keychain
     #[cfg(feature = "secure-storage")]
     SetKey {
-        /// Provider to store key for (openai, anthropic)
+        /// Provider to store key for (openai, anthropic, or a profile name)
         provider: String,
     },
     /// Check if API key exists in system keychain
     #[cfg(feature = "secure-storage")]
     GetKey {
-        /// Provider to check key for (openai, anthropic)
+        /// Provider to check key for (openai, anthropic, or a profile name)
         provider: String,
     },
     /// Run evaluation against fixture diffs (dev only)
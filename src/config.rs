@@ -4,11 +4,12 @@
 
 use directories::ProjectDirs;
 use figment::Figment;
-use figment::providers::{Env, Format, Serialized, Toml};
+use figment::providers::{Data, Env, Format, Serialized, Toml};
 use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::warn;
 
 use crate::cli::Cli;
@@ -40,10 +41,190 @@ impl Default for CommitFormat {
     }
 }
 
+/// Trailers added to generated commit messages (`[trailers]` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrailerConfig {
+    /// Regex matched against the branch name. The first capture group (or
+    /// the whole match) is the issue; bare numbers get a `#`. Empty (the
+    /// default) disables.
+    #[serde(default)]
+    pub issue_pattern: String,
+
+    /// Trailer key for the branch's issue, e.g. Refs (default) or Closes
+    #[serde(default = "default_issue_trailer")]
+    pub issue_trailer: String,
+
+    /// Pair-programming roster: handle → `Name <email>`
+    #[serde(default)]
+    pub roster: BTreeMap<String, String>,
+
+    /// Roster handles added as `Co-authored-by` (overridden by --pair)
+    #[serde(default)]
+    pub pair: Vec<String>,
+
+    /// Add `Signed-off-by` with the git committer identity (default: false)
+    #[serde(default)]
+    pub sign_off: bool,
+}
+
+impl Default for TrailerConfig {
+    fn default() -> Self {
+        Self {
+            issue_pattern: String::new(),
+            issue_trailer: default_issue_trailer(),
+            roster: BTreeMap::new(),
+            pair: Vec::new(),
+            sign_off: false,
+        }
+    }
+}
+
+fn default_issue_trailer() -> String {
+    "Refs".into()
+}
+
 fn default_true() -> bool {
     true
 }
 
+/// Retries for throttled or failed LLM requests (`[retry]` table)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Attempts per request, including the first (1 disables retries)
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+
+    /// No retry is started once this many seconds have passed since the
+    /// first attempt
+    #[serde(default = "default_retry_deadline_secs")]
+    pub deadline_secs: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            deadline_secs: default_retry_deadline_secs(),
+        }
+    }
+}
+
+/// Token accounting and spending limits for LLM requests (`[usage]` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageConfig {
+    /// Append every request's token counts to the local usage ledger
+    #[serde(default = "default_true")]
+    pub ledger: bool,
+
+    /// Cloud tokens (input + output) allowed per UTC day
+    #[serde(default)]
+    pub daily_tokens: Option<u64>,
+
+    /// Cloud tokens (input + output) allowed per UTC month
+    #[serde(default)]
+    pub monthly_tokens: Option<u64>,
+
+    /// Cloud spend allowed per UTC day, in the currency of `prices`
+    #[serde(default)]
+    pub daily_cost: Option<f64>,
+
+    /// Cloud spend allowed per UTC month, in the currency of `prices`
+    #[serde(default)]
+    pub monthly_cost: Option<f64>,
+
+    /// Prices per million tokens, keyed by model name
+    #[serde(default)]
+    pub prices: BTreeMap<String, ModelPrice>,
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            ledger: true,
+            daily_tokens: None,
+            monthly_tokens: None,
+            daily_cost: None,
+            monthly_cost: None,
+            prices: BTreeMap::new(),
+        }
+    }
+}
+
+impl UsageConfig {
+    /// Whether any budget is set.
+    #[must_use]
+    pub fn has_budget(&self) -> bool {
+        self.daily_tokens.is_some()
+            || self.monthly_tokens.is_some()
+            || self.daily_cost.is_some()
+            || self.monthly_cost.is_some()
+    }
+
+    /// Cost of a request to `model`, if its price is configured.
+    #[must_use]
+    pub fn cost(&self, model: &str, input_tokens: u64, output_tokens: u64) -> Option<f64> {
+        let price = self.prices.get(model)?;
+        Some((input_tokens as f64 * price.input + output_tokens as f64 * price.output) / 1e6)
+    }
+}
+
+/// Price of a model per million tokens (`[usage.prices."<model>"]`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// Proxy and TLS settings for LLM requests (`[network]` table)
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// HTTP(S) proxy for all provider requests, e.g. `http://proxy:3128`.
+    /// Without it, `HTTPS_PROXY`/`HTTP_PROXY` from the environment apply.
+    #[serde(default)]
+    pub proxy: Option<String>,
+
+    /// Comma-separated hosts that bypass `proxy`, e.g. `localhost,.internal`
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+
+    /// PEM bundle of extra root certificates, e.g. an internal CA
+    #[serde(default)]
+    pub ca_bundle_path: Option<PathBuf>,
+
+    /// PEM client certificate for mTLS. May also hold the private key.
+    #[serde(default)]
+    pub client_cert_path: Option<PathBuf>,
+
+    /// PEM private key for `client_cert_path`
+    #[serde(default)]
+    pub client_key_path: Option<PathBuf>,
+}
+
+impl std::fmt::Debug for NetworkConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Proxy URLs may carry credentials
+        let proxy = self
+            .proxy
+            .as_deref()
+            .map(|url| if url.contains('@') { "[REDACTED]" } else { url });
+        f.debug_struct("NetworkConfig")
+            .field("proxy", &proxy)
+            .field("no_proxy", &self.no_proxy)
+            .field("ca_bundle_path", &self.ca_bundle_path)
+            .field("client_cert_path", &self.client_cert_path)
+            .field("client_key_path", &self.client_key_path)
+            .finish()
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_deadline_secs() -> u64 {
+    120
+}
+
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[non_exhaustive]
 #[serde(rename_all = "lowercase")]
@@ -64,6 +245,204 @@ impl std::fmt::Display for Provider {
     }
 }
 
+/// How a provider profile sends its API key (`auth` in `[providers.<name>]`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthStyle {
+    /// `Authorization: Bearer <key>` (OpenAI default)
+    Bearer,
+    /// The key as the value of `auth_header` (Anthropic default: `x-api-key`)
+    Header,
+    /// Send no key
+    None,
+}
+
+/// Resolved auth for an endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Auth {
+    Bearer,
+    /// Header carrying the raw key
+    Header(String),
+    None,
+}
+
+impl Auth {
+    /// The provider's native scheme.
+    #[must_use]
+    pub fn default_for(kind: Provider) -> Self {
+        match kind {
+            Provider::OpenAI => Self::Bearer,
+            Provider::Anthropic => Self::Header("x-api-key".into()),
+            Provider::Ollama => Self::None,
+        }
+    }
+}
+
+impl std::fmt::Display for Auth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bearer => write!(f, "bearer"),
+            Self::Header(name) => write!(f, "header {name}"),
+            Self::None => write!(f, "none"),
+        }
+    }
+}
+
+/// What the `commit-msg` hook does when a message fails linting.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[non_exhaustive]
+#[serde(rename_all = "lowercase")]
+pub enum LintMode {
+    /// Print the issues and let the commit through
+    #[default]
+    Warn,
+    /// Print the issues and reject the commit
+    Error,
+}
+
+impl std::fmt::Display for LintMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warn => write!(f, "warn"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A named endpoint from a `[providers.<name>]` table, selected with `--provider <name>`.
+///
+/// Unset `timeout_secs` and `temperature` fall back to the top-level values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderProfile {
+    /// Wire protocol: ollama, openai (any OpenAI-compatible server), anthropic
+    pub kind: Provider,
+
+    /// Model name served by this endpoint
+    pub model: String,
+
+    /// Endpoint URL (default: the provider's public API, or the local Ollama host)
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Environment variable holding the API key. Falls back to the keychain
+    /// entry named after the profile.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+
+    #[serde(default)]
+    pub temperature: Option<f32>,
+
+    /// Overrides the top-level `structured_output` for this endpoint
+    #[serde(default)]
+    pub structured_output: Option<bool>,
+
+    /// Overrides the top-level `stream_usage` for this endpoint
+    #[serde(default)]
+    pub stream_usage: Option<bool>,
+
+    /// How the API key is sent: bearer, header or none (openai and
+    /// anthropic only; default: the provider's own scheme)
+    #[serde(default)]
+    pub auth: Option<AuthStyle>,
+
+    /// Header carrying the key when `auth = "header"`
+    #[serde(default)]
+    pub auth_header: Option<String>,
+
+    /// Extra request headers. Values are literal, or `env:VAR` /
+    /// `keyring:ENTRY` to keep secrets out of the file.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl ProviderProfile {
+    /// The configured auth, or the provider's own scheme.
+    #[must_use]
+    pub fn resolved_auth(&self) -> Auth {
+        match self.auth {
+            None => Auth::default_for(self.kind),
+            Some(AuthStyle::Bearer) => Auth::Bearer,
+            Some(AuthStyle::None) => Auth::None,
+            Some(AuthStyle::Header) => match self.auth_header {
+                Some(ref name) => Auth::Header(name.clone()),
+                None => Auth::default_for(self.kind),
+            },
+        }
+    }
+}
+
+/// Connection and sampling settings for one LLM endpoint, resolved from the
+/// selected profile or the top-level fields. This is what backends are built from.
+#[derive(Debug, Clone)]
+pub struct ResolvedProfile {
+    /// Profile name, or the provider name when no profile is selected
+    pub name: String,
+    pub kind: Provider,
+    pub model: String,
+    /// `None` means the provider's default public endpoint
+    pub base_url: Option<String>,
+    pub api_key: Option<SecretString>,
+    pub timeout_secs: u64,
+    pub temperature: f32,
+    pub num_predict: u32,
+    pub think: bool,
+    /// Constrain output to the commit JSON schema
+    pub structured_output: bool,
+    /// Ask OpenAI-compatible servers for a final usage chunk
+    pub stream_usage: bool,
+    pub retry: RetryConfig,
+    pub network: NetworkConfig,
+    pub auth: Auth,
+    /// Extra headers; values are unresolved `env:`/`keyring:` references or literals
+    pub headers: BTreeMap<String, String>,
+}
+
+impl ResolvedProfile {
+    /// Whether requests are billed: a remote OpenAI or Anthropic endpoint.
+    /// Ollama is never billed, wherever it runs.
+    #[must_use]
+    pub fn is_cloud(&self) -> bool {
+        self.kind != Provider::Ollama && !self.is_local()
+    }
+
+    /// Whether the endpoint is on this machine or a private network
+    /// (loopback, RFC 1918 / unique-local addresses, `.local` or single-label
+    /// hostnames). Anything else, including the default public APIs, is remote.
+    #[must_use]
+    pub fn is_local(&self) -> bool {
+        let Some(ref url) = self.base_url else {
+            return false;
+        };
+        let authority = url
+            .split_once("://")
+            .map_or(url.as_str(), |(_, rest)| rest)
+            .split(['/', '?', '#'])
+            .next()
+            .unwrap_or_default();
+        let authority = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+        let host = if let Some(bracketed) = authority.strip_prefix('[') {
+            bracketed.split(']').next().unwrap_or_default()
+        } else {
+            authority.split(':').next().unwrap_or_default()
+        };
+        let host = host.to_ascii_lowercase();
+
+        match host.parse::<std::net::IpAddr>() {
+            Ok(std::net::IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private(),
+            Ok(std::net::IpAddr::V6(ip)) => ip.is_loopback() || ip.is_unique_local(),
+            Err(_) => {
+                host == "localhost"
+                    || host.ends_with(".localhost")
+                    || host.ends_with(".local")
+                    || (!host.is_empty() && !host.contains('.'))
+            }
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
@@ -93,7 +472,7 @@ pub struct Config {
     #[serde(default = "default_timeout_secs")]
     pub timeout_secs: u64,
 
-    /// LLM temperature (0.0-1.0, default 0.3)
+    /// LLM temperature (0.0-2.0, default 0.3)
     #[serde(default = "default_temperature")]
     pub temperature: f32,
 
@@ -107,6 +486,18 @@ pub struct Config {
     #[serde(default)]
     pub think: bool,
 
+    /// Constrain commit messages to a JSON schema using the provider's native
+    /// structured output (default: true). Disable for OpenAI-compatible
+    /// servers that reject `response_format`.
+    #[serde(default = "default_true")]
+    pub structured_output: bool,
+
+    /// Request token usage from OpenAI-compatible servers with
+    /// `stream_options.include_usage` (default: true). Disable for servers
+    /// that reject `stream_options`.
+    #[serde(default = "default_true")]
+    pub stream_usage: bool,
+
     /// Base URL for OpenAI-compatible APIs (default: https://api.openai.com/v1)
     #[serde(default)]
     pub openai_base_url: Option<String>,
@@ -120,6 +511,11 @@ pub struct Config {
     #[serde(default = "default_rename_threshold")]
     pub rename_threshold: u8,
 
+    /// Maximum concurrent LLM requests when generating multiple candidates
+    /// (1-16, default 3). Set to 1 for local servers that queue requests anyway.
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+
     /// Additional custom secret patterns (regex strings)
     #[serde(default)]
     pub custom_secret_patterns: Vec<String>,
@@ -149,6 +545,10 @@ pub struct Config {
     #[serde(default)]
     pub exclude_patterns: Vec<String>,
 
+    /// What the `commit-msg` hook does with lint issues: warn (default) or error
+    #[serde(default)]
+    pub lint_mode: LintMode,
+
     /// Path to custom system prompt file (overrides built-in SYSTEM_PROMPT)
     #[serde(default)]
     pub system_prompt_path: Option<PathBuf>,
@@ -160,6 +560,35 @@ pub struct Config {
     /// Commit message format options
     #[serde(default)]
     pub format: CommitFormat,
+
+    /// Issue, co-author and sign-off trailers
+    #[serde(default)]
+    pub trailers: TrailerConfig,
+
+    /// Retries for throttled or failed LLM requests
+    #[serde(default)]
+    pub retry: RetryConfig,
+
+    /// Proxy, CA bundle and client certificate for LLM requests
+    #[serde(default)]
+    pub network: NetworkConfig,
+
+    /// Usage ledger, model prices and budgets
+    #[serde(default)]
+    pub usage: UsageConfig,
+
+    /// Named provider profile to use instead of the top-level provider fields
+    #[serde(default)]
+    pub profile: Option<String>,
+
+    /// Named provider profiles (`[providers.<name>]` tables)
+    #[serde(default)]
+    pub providers: BTreeMap<String, ProviderProfile>,
+
+    /// Profiles to try, in order, when the active provider is unreachable,
+    /// times out, or doesn't serve the model
+    #[serde(default)]
+    pub fallback: Vec<String>,
 }
 
 fn default_max_context_chars() -> usize {
@@ -190,6 +619,9 @@ fn default_num_predict() -> u32 {
 fn default_rename_threshold() -> u8 {
     70
 }
+fn default_max_concurrent_requests() -> usize {
+    3
+}
 fn default_history_sample_size() -> usize {
     50
 }
@@ -208,18 +640,29 @@ impl Default for Config {
             temperature: default_temperature(),
             num_predict: default_num_predict(),
             think: false,
+            structured_output: true,
+            stream_usage: true,
             openai_base_url: None,
             anthropic_base_url: None,
             rename_threshold: default_rename_threshold(),
+            max_concurrent_requests: default_max_concurrent_requests(),
             custom_secret_patterns: Vec::new(),
             disabled_secret_patterns: Vec::new(),
             locale: None,
             learn_from_history: false,
             history_sample_size: default_history_sample_size(),
             exclude_patterns: Vec::new(),
+            lint_mode: LintMode::default(),
             system_prompt_path: None,
             template_path: None,
             format: CommitFormat::default(),
+            trailers: TrailerConfig::default(),
+            retry: RetryConfig::default(),
+            network: NetworkConfig::default(),
+            usage: UsageConfig::default(),
+            profile: None,
+            providers: BTreeMap::new(),
+            fallback: Vec::new(),
         }
     }
 }
@@ -238,18 +681,28 @@ impl std::fmt::Debug for Config {
             .field("temperature", &self.temperature)
             .field("num_predict", &self.num_predict)
             .field("think", &self.think)
+            .field("structured_output", &self.structured_output)
+            .field("stream_usage", &self.stream_usage)
             .field("openai_base_url", &self.openai_base_url)
             .field("anthropic_base_url", &self.anthropic_base_url)
             .field("rename_threshold", &self.rename_threshold)
+            .field("max_concurrent_requests", &self.max_concurrent_requests)
             .field("custom_secret_patterns", &self.custom_secret_patterns)
             .field("disabled_secret_patterns", &self.disabled_secret_patterns)
             .field("locale", &self.locale)
             .field("learn_from_history", &self.learn_from_history)
             .field("history_sample_size", &self.history_sample_size)
             .field("exclude_patterns", &self.exclude_patterns)
+            .field("lint_mode", &self.lint_mode)
             .field("system_prompt_path", &self.system_prompt_path)
             .field("template_path", &self.template_path)
             .field("format", &self.format)
+            .field("trailers", &self.trailers)
+            .field("retry", &self.retry)
+            .field("network", &self.network)
+            .field("profile", &self.profile)
+            .field("providers", &self.providers)
+            .field("fallback", &self.fallback)
             .finish()
     }
 }
@@ -260,14 +713,10 @@ impl Config {
         let mut figment = Figment::new().merge(Serialized::defaults(Config::default()));
 
         // Project-level config (.commitbee.toml in repo root)
-        // Security: project config can override settings but should not
-        // be able to set api_key or redirect cloud provider base URLs.
-        let mut has_project_config = false;
         if let Ok(cwd) = std::env::current_dir() {
             let project_config = cwd.join(".commitbee.toml");
             if project_config.exists() {
-                has_project_config = true;
-                figment = figment.merge(Toml::file(&project_config));
+                figment = figment.merge(Self::project_layer(&project_config));
             }
         }
 
@@ -286,84 +735,119 @@ impl Config {
             .extract()
             .map_err(|e| Error::Config(e.to_string()))?;
 
-        // Security: warn if project-level config overrides security-sensitive fields
-        if has_project_config
-            && let Ok(cwd) = std::env::current_dir()
-            && let Ok(content) = fs::read_to_string(cwd.join(".commitbee.toml"))
-            && let Ok(table) = content.parse::<toml::Table>()
-        {
-            if table.contains_key("api_key") {
-                warn!("project .commitbee.toml sets api_key — ignoring for security");
-                config.api_key = None;
-            }
-            if table.contains_key("openai_base_url") {
-                warn!("project .commitbee.toml sets openai_base_url — blocked for security");
-                config.openai_base_url = None;
-            }
-            if table.contains_key("anthropic_base_url") {
-                warn!("project .commitbee.toml sets anthropic_base_url — blocked for security");
-                config.anthropic_base_url = None;
-            }
-            if table.contains_key("ollama_host") {
-                warn!("project .commitbee.toml sets ollama_host — blocked for security");
-                config.ollama_host = Config::default().ollama_host;
-            }
-        }
-
         // CLI overrides (highest priority — must run before API key resolution
         // so that --provider is applied before keyring/env var lookup)
         config.apply_cli(cli)?;
 
-        // Provider-specific API key fallback (after CLI overrides set the provider)
-        if config.api_key.is_none() {
-            config.api_key = match config.provider {
-                Provider::OpenAI => std::env::var("OPENAI_API_KEY").ok().map(SecretString::from),
-                Provider::Anthropic => std::env::var("ANTHROPIC_API_KEY")
-                    .ok()
-                    .map(SecretString::from),
-                Provider::Ollama => None,
-            };
+        // API key resolution (after CLI overrides select the provider or profile).
+        // A profile never inherits the top-level key, so a key meant for one
+        // endpoint is not sent to another.
+        if let Some(name) = config.profile.clone() {
+            config.api_key = config
+                .providers
+                .get(&name)
+                .and_then(|profile| Self::profile_api_key(&name, profile));
+        } else if config.api_key.is_none() {
+            config.api_key = Self::default_api_key(config.provider);
         }
 
-        // Keyring fallback (if still no key and secure-storage feature is enabled)
-        #[cfg(feature = "secure-storage")]
-        if config.api_key.is_none() && config.provider != Provider::Ollama {
-            let provider_name = config.provider.to_string();
-            if let Ok(entry) = keyring::Entry::new("commitbee", &provider_name)
-                && let Ok(key) = entry.get_password()
-            {
-                config.api_key = Some(SecretString::from(key));
+        config.validate(&cli.command)?;
+        Ok(config)
+    }
+
+    /// The project config with security-sensitive keys removed.
+    ///
+    /// A cloned repository must not be able to read out or redirect API keys,
+    /// so it can't set `api_key`, provider endpoints, `[network]`, `[usage]`
+    /// or provider profiles. Only the project's own values are dropped; the
+    /// user's settings for those keys still apply.
+    fn project_layer(path: &Path) -> Data<Toml> {
+        let Ok(mut table) = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|c| c.parse::<toml::Table>().map_err(|e| e.to_string()))
+        else {
+            // Let figment report the read or parse error
+            return Toml::file(path);
+        };
+
+        for (key, reason) in [
+            ("api_key", "ignoring for security"),
+            ("openai_base_url", "blocked for security"),
+            ("anthropic_base_url", "blocked for security"),
+            ("ollama_host", "blocked for security"),
+            ("network", "blocked for security"),
+            ("usage", "blocked so budgets can't be raised"),
+            ("providers", "blocked for security"),
+        ] {
+            if table.remove(key).is_some() {
+                warn!("project .commitbee.toml sets {key} — {reason}");
             }
         }
 
-        config.validate(&cli.command)?;
-        Ok(config)
+        Toml::string(&table.to_string())
     }
 
     pub fn config_dir() -> Option<PathBuf> {
         ProjectDirs::from("", "", "commitbee").map(|dirs| dirs.config_dir().to_path_buf())
     }
 
+    pub fn cache_dir() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "commitbee").map(|dirs| dirs.cache_dir().to_path_buf())
+    }
+
+    pub fn data_dir() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "commitbee").map(|dirs| dirs.data_dir().to_path_buf())
+    }
+
     pub fn config_path() -> Option<PathBuf> {
         Self::config_dir().map(|d| d.join("config.toml"))
     }
 
     fn apply_cli(&mut self, cli: &Cli) -> Result<()> {
         if let Some(ref p) = cli.provider {
-            self.provider = match p.to_lowercase().as_str() {
-                "ollama" => Provider::Ollama,
-                "openai" => Provider::OpenAI,
-                "anthropic" => Provider::Anthropic,
-                other => {
-                    return Err(Error::Config(format!(
-                        "Unknown provider '{}'. Valid options: ollama, openai, anthropic",
-                        other
-                    )));
-                }
-            };
+            if self.providers.contains_key(p) {
+                self.profile = Some(p.clone());
+            } else {
+                self.profile = None;
+                self.provider = match p.to_lowercase().as_str() {
+                    "ollama" => Provider::Ollama,
+                    "openai" => Provider::OpenAI,
+                    "anthropic" => Provider::Anthropic,
+                    other => {
+                        let mut valid = vec!["ollama", "openai", "anthropic"];
+                        valid.extend(self.providers.keys().map(String::as_str));
+                        return Err(Error::Config(format!(
+                            "Unknown provider '{}'. Valid options: {}",
+                            other,
+                            valid.join(", ")
+                        )));
+                    }
+                };
+            }
         }
+
+        // A selected profile drives `provider` and `model` so everything that
+        // reports them shows the endpoint actually in use
+        if let Some(ref name) = self.profile {
+            let profile = self.providers.get(name).ok_or_else(|| {
+                Error::Config(format!(
+                    "Provider profile '{}' is not defined (add a [providers.{}] table)",
+                    name, name
+                ))
+            })?;
+            self.provider = profile.kind;
+            self.model = profile.model.clone();
+        }
+
         if let Some(ref m) = cli.model {
             self.model = m.clone();
+            if let Some(profile) = self
+                .profile
+                .as_ref()
+                .and_then(|name| self.providers.get_mut(name))
+            {
+                profile.model = m.clone();
+            }
         }
         if cli.no_scope {
             self.format.include_scope = false;
@@ -374,6 +858,12 @@ impl Config {
         if !cli.exclude.is_empty() {
             self.exclude_patterns.extend(cli.exclude.iter().cloned());
         }
+        if !cli.pair.is_empty() {
+            self.trailers.pair.clone_from(&cli.pair);
+        }
+        if cli.signoff {
+            self.trailers.sign_off = true;
+        }
         Ok(())
     }
 
@@ -382,16 +872,33 @@ impl Config {
     fn requires_api_key(command: &Option<crate::cli::Commands>) -> bool {
         match command {
             None => true, // Default command (generate commit) needs a key
-            Some(cmd) => matches!(cmd, crate::cli::Commands::Doctor),
+            Some(cmd) => matches!(
+                cmd,
+                crate::cli::Commands::Doctor
+                    | crate::cli::Commands::Reword { .. }
+                    | crate::cli::Commands::Pr { .. }
+                    | crate::cli::Commands::Serve { .. }
+            ),
             // Init, Config, Completions, Hook, SetKey, GetKey, Eval — don't need a key
         }
     }
 
     fn validate(&self, command: &Option<crate::cli::Commands>) -> Result<()> {
+        // Self-hosted OpenAI-compatible servers commonly run without auth
+        let custom_endpoint = self
+            .selected_profile()
+            .is_some_and(|(_, p)| p.base_url.is_some() || p.auth == Some(AuthStyle::None));
         if Self::requires_api_key(command)
             && self.provider != Provider::Ollama
             && self.api_key.is_none()
+            && !custom_endpoint
         {
+            if let Some(ref name) = self.profile {
+                return Err(Error::Config(format!(
+                    "Provider profile '{}' requires an API key. Set api_key_env in [providers.{}], or store securely with: commitbee set-key {}",
+                    name, name, name
+                )));
+            }
             return Err(Error::Config(format!(
                 "{} requires an API key. Set COMMITBEE_API_KEY, {}_API_KEY, or store securely with: commitbee config set-key {}",
                 self.provider,
@@ -430,7 +937,7 @@ impl Config {
 
         if !(0.0..=2.0).contains(&self.temperature) {
             return Err(Error::Config(format!(
-                "temperature must be 0.0–1.0, got {}",
+                "temperature must be 0.0–2.0, got {}",
                 self.temperature
             )));
         }
@@ -442,6 +949,73 @@ impl Config {
             )));
         }
 
+        if !(1..=16).contains(&self.max_concurrent_requests) {
+            return Err(Error::Config(format!(
+                "max_concurrent_requests must be 1–16, got {}",
+                self.max_concurrent_requests
+            )));
+        }
+
+        if !(1..=10).contains(&self.retry.max_attempts) {
+            return Err(Error::Config(format!(
+                "retry.max_attempts must be 1–10, got {}",
+                self.retry.max_attempts
+            )));
+        }
+
+        if !(1..=3600).contains(&self.retry.deadline_secs) {
+            return Err(Error::Config(format!(
+                "retry.deadline_secs must be 1–3600, got {}",
+                self.retry.deadline_secs
+            )));
+        }
+
+        if let Some(ref url) = self.network.proxy
+            && !url.starts_with("http://")
+            && !url.starts_with("https://")
+        {
+            return Err(Error::Config(format!(
+                "network.proxy must start with http:// or https://, got '{}'",
+                url
+            )));
+        }
+
+        if self.usage.has_budget() && !self.usage.ledger {
+            return Err(Error::Config(
+                "usage budgets are counted from the ledger: remove them or set usage.ledger = true"
+                    .into(),
+            ));
+        }
+
+        for (key, limit) in [
+            ("usage.daily_cost", self.usage.daily_cost),
+            ("usage.monthly_cost", self.usage.monthly_cost),
+        ] {
+            if let Some(limit) = limit
+                && !(limit.is_finite() && limit >= 0.0)
+            {
+                return Err(Error::Config(format!(
+                    "{} must be a non-negative number, got {}",
+                    key, limit
+                )));
+            }
+        }
+
+        // reqwest only takes a bypass list along with an explicit proxy
+        if self.network.no_proxy.is_some() && self.network.proxy.is_none() {
+            return Err(Error::Config(
+                "network.no_proxy only applies to network.proxy; for a proxy from \
+                 HTTPS_PROXY/HTTP_PROXY, use the NO_PROXY environment variable"
+                    .into(),
+            ));
+        }
+
+        if self.network.client_key_path.is_some() && self.network.client_cert_path.is_none() {
+            return Err(Error::Config(
+                "network.client_key_path requires network.client_cert_path".into(),
+            ));
+        }
+
         if self.ollama_host.is_empty() {
             return Err(Error::Config("ollama_host cannot be empty".into()));
         }
@@ -473,9 +1047,291 @@ impl Config {
             )));
         }
 
+        if !self.trailers.issue_pattern.is_empty()
+            && let Err(e) = regex::Regex::new(&self.trailers.issue_pattern)
+        {
+            return Err(Error::Config(format!(
+                "trailers.issue_pattern is not a valid regex: {}",
+                e
+            )));
+        }
+
+        if !crate::domain::Trailer::is_valid_key(&self.trailers.issue_trailer) {
+            return Err(Error::Config(format!(
+                "trailers.issue_trailer must be a single word like Refs or Closes, got '{}'",
+                self.trailers.issue_trailer
+            )));
+        }
+
+        for (handle, person) in &self.trailers.roster {
+            if !(person.contains(" <") && person.ends_with('>')) {
+                return Err(Error::Config(format!(
+                    "trailers.roster.{} must look like 'Name <email>', got '{}'",
+                    handle, person
+                )));
+            }
+        }
+
+        for handle in &self.trailers.pair {
+            if !self.trailers.roster.contains_key(handle) {
+                return Err(Error::Config(format!(
+                    "'{}' is not in the pair roster (add it to [trailers.roster])",
+                    handle
+                )));
+            }
+        }
+
+        for name in &self.fallback {
+            if !self.providers.contains_key(name) {
+                return Err(Error::Config(format!(
+                    "fallback entry '{}' is not a provider profile (add a [providers.{}] table)",
+                    name, name
+                )));
+            }
+        }
+
+        // Cloud requests without a price add nothing to cost, so a cost
+        // budget would never stop them
+        if self.usage.daily_cost.is_some() || self.usage.monthly_cost.is_some() {
+            let fallbacks = self.fallback.iter().filter_map(|name| {
+                let profile = self.providers.get(name)?;
+                Some(self.resolve_profile(name, profile, None))
+            });
+            for profile in std::iter::once(self.active_profile()).chain(fallbacks) {
+                if profile.is_cloud() && !self.usage.prices.contains_key(&profile.model) {
+                    return Err(Error::Config(format!(
+                        "cost budgets can't count '{}' of profile '{}': add a [usage.prices.\"{}\"] table",
+                        profile.model, profile.name, profile.model
+                    )));
+                }
+            }
+        }
+
+        for (name, profile) in &self.providers {
+            if let Some(ref url) = profile.base_url
+                && !url.starts_with("http://")
+                && !url.starts_with("https://")
+            {
+                return Err(Error::Config(format!(
+                    "providers.{}.base_url must start with http:// or https://, got '{}'",
+                    name, url
+                )));
+            }
+            if let Some(timeout) = profile.timeout_secs
+                && !(1..=3600).contains(&timeout)
+            {
+                return Err(Error::Config(format!(
+                    "providers.{}.timeout_secs must be 1–3600, got {}",
+                    name, timeout
+                )));
+            }
+            if let Some(temperature) = profile.temperature
+                && !(0.0..=2.0).contains(&temperature)
+            {
+                return Err(Error::Config(format!(
+                    "providers.{}.temperature must be 0.0–2.0, got {}",
+                    name, temperature
+                )));
+            }
+            if profile.kind == Provider::Ollama && profile.auth.is_some() {
+                return Err(Error::Config(format!(
+                    "providers.{}.auth is only supported for openai and anthropic profiles",
+                    name
+                )));
+            }
+            if profile.auth == Some(AuthStyle::Header)
+                && profile.auth_header.is_none()
+                && profile.kind != Provider::Anthropic
+            {
+                return Err(Error::Config(format!(
+                    "providers.{}.auth = \"header\" needs auth_header, e.g. auth_header = \"api-key\"",
+                    name
+                )));
+            }
+            for header in profile.auth_header.iter().chain(profile.headers.keys()) {
+                if reqwest::header::HeaderName::from_bytes(header.as_bytes()).is_err() {
+                    return Err(Error::Config(format!(
+                        "providers.{}: '{}' is not a valid header name",
+                        name, header
+                    )));
+                }
+            }
+        }
+
         Ok(())
     }
 
+    // ─── Provider Profiles ───
+
+    /// The selected `[providers.<name>]` profile, if any.
+    fn selected_profile(&self) -> Option<(&str, &ProviderProfile)> {
+        let name = self.profile.as_deref()?;
+        self.providers.get(name).map(|p| (name, p))
+    }
+
+    /// Settings for the endpoint this run talks to: the selected profile, or
+    /// the top-level provider fields when no profile is selected.
+    #[must_use]
+    pub fn active_profile(&self) -> ResolvedProfile {
+        if let Some((name, profile)) = self.selected_profile() {
+            // The profile's key was resolved into `api_key` at load
+            return self.resolve_profile(name, profile, self.api_key.clone());
+        }
+
+        let base_url = match self.provider {
+            Provider::Ollama => Some(self.ollama_host.clone()),
+            Provider::OpenAI => self.openai_base_url.clone(),
+            Provider::Anthropic => self.anthropic_base_url.clone(),
+        };
+        ResolvedProfile {
+            name: self.provider.to_string(),
+            kind: self.provider,
+            model: self.model.clone(),
+            base_url,
+            api_key: self.api_key.clone(),
+            timeout_secs: self.timeout_secs,
+            temperature: self.temperature,
+            num_predict: self.num_predict,
+            think: self.think,
+            structured_output: self.structured_output,
+            stream_usage: self.stream_usage,
+            retry: self.retry,
+            network: self.network.clone(),
+            auth: Auth::default_for(self.provider),
+            headers: BTreeMap::new(),
+        }
+    }
+
+    /// Resolve a named profile, looking up its API key. Returns `None` if no
+    /// `[providers.<name>]` table exists.
+    #[must_use]
+    pub fn named_profile(&self, name: &str) -> Option<ResolvedProfile> {
+        let profile = self.providers.get(name)?;
+        Some(self.resolve_profile(name, profile, Self::profile_api_key(name, profile)))
+    }
+
+    /// The active profile followed by each `fallback` profile, without repeats.
+    #[must_use]
+    pub fn provider_chain(&self) -> Vec<ResolvedProfile> {
+        let mut chain = vec![self.active_profile()];
+        for name in &self.fallback {
+            let repeated = self.profile.as_deref() == Some(name.as_str())
+                || chain[1..].iter().any(|p| &p.name == name);
+            if repeated {
+                continue;
+            }
+            if let Some(profile) = self.named_profile(name) {
+                chain.push(profile);
+            }
+        }
+        chain
+    }
+
+    fn resolve_profile(
+        &self,
+        name: &str,
+        profile: &ProviderProfile,
+        api_key: Option<SecretString>,
+    ) -> ResolvedProfile {
+        let base_url = match profile.kind {
+            Provider::Ollama => Some(profile.base_url.clone().unwrap_or_else(default_ollama_host)),
+            _ => profile.base_url.clone(),
+        };
+        ResolvedProfile {
+            name: name.to_string(),
+            kind: profile.kind,
+            model: profile.model.clone(),
+            base_url,
+            api_key,
+            timeout_secs: profile.timeout_secs.unwrap_or(self.timeout_secs),
+            temperature: profile.temperature.unwrap_or(self.temperature),
+            num_predict: self.num_predict,
+            think: self.think,
+            structured_output: profile.structured_output.unwrap_or(self.structured_output),
+            stream_usage: profile.stream_usage.unwrap_or(self.stream_usage),
+            retry: self.retry,
+            network: self.network.clone(),
+            auth: profile.resolved_auth(),
+            headers: profile.headers.clone(),
+        }
+    }
+
+    /// Resolve a header value from a profile's `headers` table: `env:VAR`
+    /// reads an environment variable, `keyring:ENTRY` a keychain entry
+    /// (stored with `commitbee set-key ENTRY`), anything else is literal.
+    pub fn resolve_header_value(name: &str, spec: &str) -> Result<SecretString> {
+        if let Some(var) = spec.strip_prefix("env:") {
+            return std::env::var(var).map(SecretString::from).map_err(|_| {
+                Error::Config(format!(
+                    "header {name}: environment variable {var} is not set"
+                ))
+            });
+        }
+        if let Some(entry) = spec.strip_prefix("keyring:") {
+            return Self::keyring_api_key(entry).ok_or_else(|| {
+                Error::Config(format!(
+                    "header {name}: no keychain entry '{entry}' (store it with: commitbee set-key {entry})"
+                ))
+            });
+        }
+        Ok(SecretString::from(spec.to_string()))
+    }
+
+    /// Keychain entries referenced as `keyring:ENTRY` by profile headers.
+    #[must_use]
+    pub fn header_keyring_entries(&self) -> Vec<&str> {
+        self.providers
+            .values()
+            .flat_map(|p| p.headers.values())
+            .filter_map(|v| v.strip_prefix("keyring:"))
+            .collect()
+    }
+
+    /// API key for a profile: its `api_key_env` variable, then the keychain
+    /// entry named after the profile. Profiles targeting the provider's own
+    /// public API (no `base_url`) also accept the provider's default key.
+    fn profile_api_key(name: &str, profile: &ProviderProfile) -> Option<SecretString> {
+        profile
+            .api_key_env
+            .as_deref()
+            .and_then(|var| std::env::var(var).ok())
+            .map(SecretString::from)
+            .or_else(|| Self::keyring_api_key(name))
+            .or_else(|| {
+                profile
+                    .base_url
+                    .is_none()
+                    .then(|| Self::default_api_key(profile.kind))
+                    .flatten()
+            })
+    }
+
+    /// Provider-specific API key: `OPENAI_API_KEY` / `ANTHROPIC_API_KEY`, then
+    /// the keychain entry named after the provider.
+    fn default_api_key(provider: Provider) -> Option<SecretString> {
+        let var = match provider {
+            Provider::OpenAI => "OPENAI_API_KEY",
+            Provider::Anthropic => "ANTHROPIC_API_KEY",
+            Provider::Ollama => return None,
+        };
+        std::env::var(var)
+            .ok()
+            .map(SecretString::from)
+            .or_else(|| Self::keyring_api_key(&provider.to_string()))
+    }
+
+    /// Keyring lookup (only when the secure-storage feature is enabled).
+    #[cfg_attr(not(feature = "secure-storage"), allow(unused_variables))]
+    fn keyring_api_key(entry_name: &str) -> Option<SecretString> {
+        #[cfg(feature = "secure-storage")]
+        if let Ok(entry) = keyring::Entry::new("commitbee", entry_name)
+            && let Ok(key) = entry.get_password()
+        {
+            return Some(SecretString::from(key));
+        }
+        None
+    }
+
     /// Create default config file with secure permissions
     pub fn create_default() -> Result<PathBuf> {
         let Some(dir) = Self::config_dir() else {
@@ -575,6 +1431,21 @@ impl Config {
                 show: Show::CommentedOut,
                 example: None,
             },
+            Field {
+                key: "structured_output",
+                comment: "Constrain output to the commit JSON schema (Ollama format, OpenAI\n\
+                          response_format, Anthropic tool use). Disable for OpenAI-compatible\n\
+                          servers that reject response_format.",
+                show: Show::CommentedOut,
+                example: None,
+            },
+            Field {
+                key: "stream_usage",
+                comment: "Ask OpenAI-compatible servers for token usage (stream_options.include_usage).\n\
+                          Disable for servers that reject stream_options.",
+                show: Show::CommentedOut,
+                example: None,
+            },
             Field {
                 key: "max_context_chars",
                 comment: "Maximum context characters for LLM prompt (~4 chars per token)\n\
@@ -590,7 +1461,7 @@ impl Config {
             },
             Field {
                 key: "temperature",
-                comment: "LLM temperature (0.0-1.0, default 0.3)",
+                comment: "LLM temperature (0.0-2.0, default 0.3)",
                 show: Show::CommentedOut,
                 example: None,
             },
@@ -601,6 +1472,13 @@ impl Config {
                 show: Show::CommentedOut,
                 example: None,
             },
+            Field {
+                key: "max_concurrent_requests",
+                comment: "Maximum concurrent LLM requests for multi-candidate generation (1-16)\n\
+                          Set to 1 for local servers that process one request at a time",
+                show: Show::CommentedOut,
+                example: None,
+            },
             Field {
                 key: "locale",
                 comment: "Language for commit message generation (ISO 639-1 code)\n\
@@ -641,6 +1519,12 @@ impl Config {
                 show: Show::CommentedOut,
                 example: Some("[\"*.lock\", \"**/*.generated.*\"]"),
             },
+            Field {
+                key: "lint_mode",
+                comment: "commit-msg hook behavior on lint issues: warn or error (reject the commit)",
+                show: Show::CommentedOut,
+                example: None,
+            },
             Field {
                 key: "openai_base_url",
                 comment: "Base URL for OpenAI-compatible APIs",
@@ -653,6 +1537,19 @@ impl Config {
                 show: Show::CommentedOut,
                 example: Some("\"https://api.anthropic.com/v1\""),
             },
+            Field {
+                key: "profile",
+                comment: "Use a named [providers.<name>] profile by default (same as --provider <name>)",
+                show: Show::CommentedOut,
+                example: Some("\"local\""),
+            },
+            Field {
+                key: "fallback",
+                comment: "Profiles to try, in order, when the active provider is unreachable,\n\
+                          times out, or doesn't have the model",
+                show: Show::CommentedOut,
+                example: Some("[\"gpu-box\", \"cloud\"]"),
+            },
             Field {
                 key: "system_prompt_path",
                 comment: "Custom system prompt file (overrides built-in prompt)",
@@ -722,6 +1619,81 @@ impl Config {
             }
         }
 
+        // Trailers (examples only; all are off or empty by default)
+        out.push_str(
+            "\n# Trailers added to generated messages\n\
+             # [trailers]\n\
+             # Issue reference taken from the branch name (off by default). This\n\
+             # one matches feature/123-login, issue-17 and fix/PROJ-42-oauth.\n\
+             # issue_pattern = '^(?:feature|feat|fix|bugfix|issue)[/-](#?\\d+|[A-Z][A-Z0-9]+-\\d+)(?:[/_-]|$)'\n\
+             # issue_trailer = \"Refs\"\n\
+             # Co-authors, selected with pair = [...] or --pair <handle>\n\
+             # pair = [\"ada\"]\n\
+             # sign_off = false\n\
+             # [trailers.roster]\n\
+             # ada = \"Ada Lovelace <ada@example.com>\"\n",
+        );
+
+        // Retries (defaults shown commented out)
+        out.push_str(
+            "\n# Retries for throttled (429) or failed (5xx, dropped stream) LLM requests\n\
+             # Backoff is exponential with jitter; Retry-After is honored.\n\
+             # [retry]\n\
+             # Attempts per request, including the first (1 disables retries)\n\
+             # max_attempts = 3\n\
+             # Don't start a retry after this many seconds\n\
+             # deadline_secs = 120\n",
+        );
+
+        // Network (examples only; system proxy settings apply by default)
+        out.push_str(
+            "\n# Proxy and TLS settings for LLM requests\n\
+             # [network]\n\
+             # proxy = \"http://proxy.corp:3128\"\n\
+             # no_proxy = \"localhost,127.0.0.1,.internal\"\n\
+             # Extra root certificates (PEM), e.g. an internal CA\n\
+             # ca_bundle_path = \"/etc/ssl/corp-ca.pem\"\n\
+             # Client certificate and key (PEM) for mTLS\n\
+             # client_cert_path = \"/path/to/client.pem\"\n\
+             # client_key_path = \"/path/to/client.key\"\n",
+        );
+
+        // Usage (ledger on, no prices or budgets by default)
+        out.push_str(
+            "\n# Token accounting. Every request is logged to usage.jsonl in the data\n\
+             # directory; budgets refuse cloud requests once reached (UTC periods).\n\
+             # [usage]\n\
+             # ledger = true\n\
+             # daily_tokens = 200000\n\
+             # monthly_tokens = 2000000\n\
+             # daily_cost = 1.0\n\
+             # monthly_cost = 20.0\n\
+             # Prices per million tokens, keyed by model\n\
+             # [usage.prices.\"gpt-4o-mini\"]\n\
+             # input = 0.15\n\
+             # output = 0.60\n",
+        );
+
+        // Provider profiles (examples only; no profiles exist by default)
+        out.push_str(
+            "\n# Named provider profiles, selected with --provider <name>\n\
+             # kind: ollama, openai (any OpenAI-compatible server), anthropic\n\
+             # API keys come from api_key_env, then `commitbee set-key <name>`.\n\
+             # [providers.local]\n\
+             # kind = \"openai\"\n\
+             # base_url = \"http://localhost:8080/v1\"\n\
+             # model = \"qwen2.5-coder-7b-instruct\"\n\
+             # api_key_env = \"LOCAL_LLM_KEY\"\n\
+             # timeout_secs = 120\n\
+             # temperature = 0.2\n\
+             # How the key is sent: bearer, header (with auth_header) or none\n\
+             # auth = \"header\"\n\
+             # auth_header = \"api-key\"\n\
+             # Extra headers: literal, env:VAR or keyring:ENTRY\n\
+             # [providers.local.headers]\n\
+             # X-Team-Id = \"env:TEAM_ID\"\n",
+        );
+
         out
     }
 }
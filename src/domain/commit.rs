@@ -2,6 +2,8 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
+use serde::Serialize;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
 #[allow(dead_code)]
@@ -68,3 +70,42 @@ impl std::fmt::Display for CommitType {
         f.write_str(self.as_str())
     }
 }
+
+/// A git trailer such as `Refs: #12` or `Co-authored-by: Name <mail>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+}
+
+impl Trailer {
+    #[must_use]
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Parse a `Key: value` line. Keys are single tokens, so a
+    /// `BREAKING CHANGE:` footer isn't a trailer.
+    #[must_use]
+    pub fn parse(line: &str) -> Option<Self> {
+        let (key, value) = line.split_once(": ")?;
+        let value = value.trim();
+        (Self::is_valid_key(key) && !value.is_empty()).then(|| Self::new(key, value))
+    }
+
+    /// Letters, digits and dashes, starting with a letter.
+    #[must_use]
+    pub fn is_valid_key(key: &str) -> bool {
+        key.starts_with(|c: char| c.is_ascii_alphabetic())
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    }
+}
+
+impl std::fmt::Display for Trailer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.key, self.value)
+    }
+}
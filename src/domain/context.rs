@@ -2,8 +2,8 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
-use super::CommitType;
 use super::diff::SymbolDiff;
+use super::{CommitType, Trailer};
 
 /// A detected change intent pattern from diff analysis.
 #[derive(Debug, Clone)]
@@ -82,6 +82,8 @@ pub struct PromptContext {
     pub intents: Vec<ChangeIntent>,
     /// Whether any modified symbol added `unsafe` (Rust)
     pub has_unsafe_addition: bool,
+    /// Trailers appended to the sanitized message (issue, co-authors, sign-off)
+    pub trailers: Vec<Trailer>,
 }
 
 impl PromptContext {
@@ -265,7 +267,7 @@ Respond with ONLY this JSON:
         )
     }
 
-    fn format_symbols_section(&self) -> String {
+    pub(crate) fn format_symbols_section(&self) -> String {
         let has_added = !self.symbols_added.is_empty();
         let has_removed = !self.symbols_removed.is_empty();
         let has_modified = !self.symbols_modified.is_empty();
@@ -297,7 +299,7 @@ Respond with ONLY this JSON:
         section
     }
 
-    fn format_breaking_warning(&self) -> String {
+    pub(crate) fn format_breaking_warning(&self) -> String {
         if self.public_api_removed.is_empty() {
             return String::new();
         }
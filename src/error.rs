@@ -49,12 +49,12 @@ pub enum Error {
     )]
     SecretsDetected { patterns: Vec<String> },
 
-    #[error("Commit split aborted: files with both staged and unstaged changes")]
+    #[error("Invalid split plan: {0}")]
     #[diagnostic(
-        code(commitbee::split::aborted),
-        help("Stash or commit unstaged changes first, or use --no-split")
+        code(commitbee::split::plan),
+        help("Fix the plan, or leave it empty to cancel the split")
     )]
-    SplitAborted,
+    SplitPlan(String),
 
     #[error("Cannot connect to Ollama at {host}")]
     #[diagnostic(
@@ -77,6 +77,42 @@ pub enum Error {
     #[diagnostic(code(commitbee::provider::error))]
     Provider { provider: String, message: String },
 
+    #[error("Provider '{provider}' unreachable: {message}")]
+    #[diagnostic(
+        code(commitbee::provider::unreachable),
+        help("Check the endpoint, or list fallback profiles in `fallback`")
+    )]
+    ProviderUnreachable { provider: String, message: String },
+
+    #[error("TLS connection to '{provider}' failed: {message}")]
+    #[diagnostic(
+        code(commitbee::provider::tls),
+        help(
+            "Trust an internal CA with ca_bundle_path, or set client_cert_path for mTLS, in [network]. Run commitbee doctor for details."
+        )
+    )]
+    Tls { provider: String, message: String },
+
+    #[error("Usage budget reached for '{provider}': {message}")]
+    #[diagnostic(
+        code(commitbee::usage::budget),
+        help(
+            "Use a local provider, or raise the limit in [usage]. Run commitbee usage for totals."
+        )
+    )]
+    BudgetExceeded { provider: String, message: String },
+
+    #[error("Commit message failed linting ({count} issue(s))")]
+    #[diagnostic(
+        code(commitbee::lint::failed),
+        help("Fix the issues listed above, or set lint_mode = \"warn\" to report without failing")
+    )]
+    LintFailed { count: usize },
+
+    #[error("{commits} commit message(s) failed linting with {issues} issue(s)")]
+    #[diagnostic(code(commitbee::lint::range), help("Fix the issues listed above"))]
+    RangeLintFailed { commits: usize, issues: usize },
+
     #[error("Invalid commit message: {0}")]
     #[diagnostic(code(commitbee::commit::invalid))]
     InvalidCommitMessage(String),
@@ -89,6 +125,10 @@ pub enum Error {
     #[diagnostic(code(commitbee::git::error))]
     Git(String),
 
+    #[error("Protocol error: {0}")]
+    #[diagnostic(code(commitbee::serve::protocol))]
+    Protocol(String),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
@@ -113,4 +153,20 @@ impl From<dialoguer::Error> for Error {
     }
 }
 
+impl Error {
+    /// Whether a fallback provider should be tried after this error: the
+    /// endpoint could not be reached (including TLS failures), timed out, or
+    /// does not serve the model.
+    #[must_use]
+    pub fn is_failover(&self) -> bool {
+        matches!(
+            self,
+            Self::OllamaNotRunning { .. }
+                | Self::ModelNotFound { .. }
+                | Self::ProviderUnreachable { .. }
+                | Self::Tls { .. }
+        )
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
@@ -16,6 +16,7 @@ pub mod domain;
 pub mod error;
 #[cfg(feature = "eval")]
 pub mod eval;
+pub mod server;
 pub mod services;
 
 pub use app::App;
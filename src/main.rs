@@ -12,6 +12,7 @@ mod domain;
 mod error;
 #[cfg(feature = "eval")]
 mod eval;
+mod server;
 mod services;
 
 use app::App;
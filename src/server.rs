@@ -0,0 +1,679 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use futures::{StreamExt, TryStreamExt, stream};
+use miette::Diagnostic;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{OnceCell, mpsc};
+use tokio::task::LocalSet;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::app::App;
+use crate::domain::{PromptContext, StagedChanges};
+use crate::error::{Error, Result};
+use crate::services::{
+    analyzer::AnalyzerService,
+    context::ContextBuilder,
+    git::GitService,
+    history::HistoryService,
+    llm,
+    output::{CommitOutput, Evidence, SCHEMA_VERSION, SplitOutput},
+    progress::Progress,
+    safety,
+    sanitizer::CommitSanitizer,
+    splitter::{CommitGroup, CommitSplitter, SplitSuggestion},
+};
+
+/// Upper bound on a single message body, so a bogus `Content-Length`
+/// can't make the server allocate unbounded memory.
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Most candidates a single `generate` request may ask for (same as `-n`).
+const MAX_CANDIDATES: usize = 5;
+
+const METHODS: &[&str] = &[
+    "initialize",
+    "analyze",
+    "generate",
+    "refine",
+    "cancel",
+    "shutdown",
+    "exit",
+];
+
+// JSON-RPC 2.0 error codes, plus the LSP code for cancelled requests
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+const REQUEST_CANCELLED: i64 = -32800;
+
+// ─── Framing ───
+
+/// Read one `Content-Length`-framed message (the LSP base protocol).
+/// Returns `None` at end of input.
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<String>> {
+    let mut content_length: Option<usize> = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end_matches(['\r', '\n']);
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            // Tolerate stray blank lines between messages
+            continue;
+        }
+        // Other headers (Content-Type) carry nothing we need
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            let len = value.trim().parse().map_err(|_| {
+                Error::Protocol(format!("invalid Content-Length: {}", value.trim()))
+            })?;
+            content_length = Some(len);
+        }
+    }
+
+    let len = content_length.unwrap_or_default();
+    if len > MAX_MESSAGE_BYTES {
+        return Err(Error::Protocol(format!(
+            "message of {len} bytes exceeds the {MAX_MESSAGE_BYTES} byte limit"
+        )));
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).await?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|_| Error::Protocol("message body is not valid UTF-8".into()))
+}
+
+/// Write one `Content-Length`-framed message and flush it.
+pub async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &str) -> Result<()> {
+    let header = format!("Content-Length: {}\r\n\r\n", message.len());
+    writer.write_all(header.as_bytes()).await?;
+    writer.write_all(message.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+// ─── Errors ───
+
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut error = json!({ "code": self.code, "message": self.message });
+        if let Some(ref data) = self.data {
+            error["data"] = data.clone();
+        }
+        error
+    }
+}
+
+impl From<Error> for RpcError {
+    fn from(e: Error) -> Self {
+        if matches!(e, Error::Cancelled) {
+            return Self::new(REQUEST_CANCELLED, "request cancelled");
+        }
+        // Carry the diagnostic code so clients can branch on it
+        let data = e.code().map(|code| json!({ "code": code.to_string() }));
+        Self {
+            code: SERVER_ERROR,
+            message: e.to_string(),
+            data,
+        }
+    }
+}
+
+type RpcResult = std::result::Result<Value, RpcError>;
+
+fn parse_params<T: DeserializeOwned + Default>(params: Value) -> std::result::Result<T, RpcError> {
+    if params.is_null() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+// ─── Params ───
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct AnalyzeParams {
+    /// Directory inside the server's repository; defaults to the server's cwd
+    cwd: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct GenerateParams {
+    cwd: Option<PathBuf>,
+    candidates: Option<usize>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RefineParams {
+    cwd: Option<PathBuf>,
+    message: String,
+    feedback: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct CancelParams {
+    id: Value,
+}
+
+/// Everything the LLM methods need from one pass over the staged changes.
+struct Analysis {
+    changes: StagedChanges,
+    context: PromptContext,
+    split_groups: Option<Vec<CommitGroup>>,
+    system_prompt: String,
+    prompt: String,
+}
+
+// ─── Server ───
+
+/// JSON-RPC server state shared by all in-flight requests.
+///
+/// The analyzer (with its compiled tree-sitter queries) and the provider
+/// (with its HTTP connection pool) live as long as the server, so repeated
+/// requests from an editor skip the cold start.
+pub struct Server {
+    app: App,
+    /// Work tree the server was started in. Config, excludes and secret
+    /// patterns were loaded for it, so requests can't point elsewhere.
+    repo: Option<PathBuf>,
+    analyzer: AnalyzerService,
+    provider: llm::LlmBackend,
+    verified: OnceCell<()>,
+    in_flight: Mutex<HashMap<String, CancellationToken>>,
+    out: mpsc::Sender<String>,
+}
+
+/// Serve JSON-RPC over stdin/stdout until `exit` or end of input.
+pub async fn run_stdio(app: &App) -> Result<()> {
+    let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+    serve(app, stdin, tokio::io::stdout()).await
+}
+
+/// Serve JSON-RPC over any framed byte stream.
+pub async fn serve<R, W>(app: &App, mut reader: R, mut writer: W) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (out_tx, mut out_rx) = mpsc::channel::<String>(256);
+    let writer_handle = tokio::spawn(async move {
+        while let Some(message) = out_rx.recv().await {
+            if let Err(e) = write_message(&mut writer, &message).await {
+                warn!(error = %e, "failed to write response");
+                break;
+            }
+        }
+    });
+
+    let server = Arc::new(Server {
+        app: app.with_cancel_token(app.cancel_token().clone()),
+        repo: GitService::discover()
+            .ok()
+            .and_then(|git| git.work_dir().canonicalize().ok()),
+        analyzer: AnalyzerService::new()?,
+        provider: app.create_provider(app.config())?,
+        verified: OnceCell::new(),
+        in_flight: Mutex::new(HashMap::new()),
+        out: out_tx,
+    });
+
+    // Git handles aren't `Send`, so requests run as local tasks on this thread
+    let requests = LocalSet::new();
+    let result = requests.run_until(server.read_loop(&mut reader)).await;
+
+    // Stop outstanding work; the writer exits once every request has
+    // finished and dropped its handle on the server
+    server.cancel_all();
+    drop(server);
+    requests.await;
+    if let Err(e) = writer_handle.await {
+        warn!("writer task panicked: {e}");
+    }
+    result
+}
+
+impl Server {
+    async fn read_loop<R: AsyncBufRead + Unpin>(self: &Arc<Self>, reader: &mut R) -> Result<()> {
+        let mut shutting_down = false;
+        let stop = self.app.cancel_token().clone();
+        loop {
+            // Ctrl+C ends the server like `exit`
+            let raw = tokio::select! {
+                _ = stop.cancelled() => break,
+                raw = read_message(reader) => raw?,
+            };
+            let Some(raw) = raw else { break };
+            let message: Value = match serde_json::from_str(&raw) {
+                Ok(v) => v,
+                Err(e) => {
+                    self.reply(Value::Null, Err(RpcError::new(PARSE_ERROR, e.to_string())))
+                        .await;
+                    continue;
+                }
+            };
+
+            let id = message.get("id").cloned();
+            let Some(method) = message.get("method").and_then(Value::as_str) else {
+                if let Some(id) = id {
+                    self.reply(id, Err(RpcError::new(INVALID_REQUEST, "missing method")))
+                        .await;
+                }
+                continue;
+            };
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+            debug!(method, "rpc message");
+
+            match (method, id) {
+                ("exit", _) => break,
+                ("cancel" | "$/cancelRequest", id) => {
+                    let result = parse_params::<CancelParams>(params).map(|p| {
+                        let found = self.cancel(&p.id);
+                        json!({ "cancelled": found })
+                    });
+                    if let Some(id) = id {
+                        self.reply(id, result).await;
+                    }
+                }
+                (_, None) => debug!(method, "ignoring unknown notification"),
+                ("shutdown", Some(id)) => {
+                    shutting_down = true;
+                    self.cancel_all();
+                    self.reply(id, Ok(Value::Null)).await;
+                }
+                (_, Some(id)) if shutting_down => {
+                    let error = RpcError::new(INVALID_REQUEST, "server is shutting down");
+                    self.reply(id, Err(error)).await;
+                }
+                (method, Some(id)) => {
+                    // Register before spawning so a `cancel` right behind
+                    // this request always finds it
+                    let token = self.app.cancel_token().child_token();
+                    self.lock_in_flight().insert(id.to_string(), token.clone());
+                    let server = Arc::clone(self);
+                    let method = method.to_string();
+                    tokio::task::spawn_local(async move {
+                        server.dispatch(id, method, params, token).await;
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn dispatch(
+        self: Arc<Self>,
+        id: Value,
+        method: String,
+        params: Value,
+        token: CancellationToken,
+    ) {
+        let result = match method.as_str() {
+            "initialize" => Ok(Self::initialize()),
+            "analyze" => match parse_params(params) {
+                Ok(p) => self.analyze(p, &token).await,
+                Err(e) => Err(e),
+            },
+            "generate" => match parse_params(params) {
+                Ok(p) => self.generate(&id, p, &token).await,
+                Err(e) => Err(e),
+            },
+            "refine" => match parse_params(params) {
+                Ok(p) => self.refine(&id, p, &token).await,
+                Err(e) => Err(e),
+            },
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method: {method}"),
+            )),
+        };
+
+        self.lock_in_flight().remove(&id.to_string());
+        // The server runs indefinitely, so log usage as requests finish
+        self.app.flush_usage();
+        self.reply(id, result).await;
+    }
+
+    fn lock_in_flight(&self) -> std::sync::MutexGuard<'_, HashMap<String, CancellationToken>> {
+        self.in_flight.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn cancel(&self, id: &Value) -> bool {
+        match self.lock_in_flight().get(&id.to_string()) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn cancel_all(&self) {
+        for token in self.lock_in_flight().values() {
+            token.cancel();
+        }
+    }
+
+    async fn send(&self, message: Value) {
+        // Only fails once the writer is gone, i.e. the client hung up
+        let _ = self.out.send(message.to_string()).await;
+    }
+
+    async fn reply(&self, id: Value, result: RpcResult) {
+        let message = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error.to_json() }),
+        };
+        self.send(message).await;
+    }
+
+    // ─── Methods ───
+
+    fn initialize() -> Value {
+        json!({
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+            "schema_version": SCHEMA_VERSION,
+            "methods": METHODS,
+        })
+    }
+
+    async fn analyze(&self, params: AnalyzeParams, token: &CancellationToken) -> RpcResult {
+        let analysis = self.analyze_repo(params.cwd, token).await?;
+        let files: Vec<Value> = analysis
+            .changes
+            .files
+            .iter()
+            .map(|f| {
+                json!({
+                    "path": f.path.display().to_string(),
+                    "status": format!("{:?}", f.status).to_lowercase(),
+                    "additions": f.additions,
+                    "deletions": f.deletions,
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "schema_version": SCHEMA_VERSION,
+            "files": files,
+            "insertions": analysis.changes.stats.insertions,
+            "deletions": analysis.changes.stats.deletions,
+            "suggested_type": analysis.context.suggested_type.as_str(),
+            "suggested_scope": analysis.context.suggested_scope,
+            "evidence": Evidence::from_context(&analysis.context),
+            "split": SplitOutput::from_groups(analysis.split_groups.as_deref()),
+        }))
+    }
+
+    async fn generate(
+        &self,
+        id: &Value,
+        params: GenerateParams,
+        token: &CancellationToken,
+    ) -> RpcResult {
+        let num_candidates = params.candidates.unwrap_or(1);
+        if !(1..=MAX_CANDIDATES).contains(&num_candidates) {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("candidates must be between 1 and {MAX_CANDIDATES}"),
+            ));
+        }
+
+        let analysis = self.analyze_repo(params.cwd, token).await?;
+        self.ensure_verified().await?;
+        self.app.check_budget(&self.provider)?;
+
+        let app = self.app.with_cancel_token(token.clone());
+        let limit = app.config().max_concurrent_requests.max(1);
+        let results: Vec<Option<String>> = stream::iter(0..num_candidates)
+            .map(|i| {
+                self.stream_candidate(
+                    &app,
+                    id,
+                    i,
+                    &analysis.context,
+                    &analysis.prompt,
+                    &analysis.system_prompt,
+                )
+            })
+            .buffered(limit)
+            .try_collect()
+            .await?;
+
+        let candidates = CommitSanitizer::dedup_candidates(results.into_iter().flatten().collect());
+        let Some(message) = candidates.first().cloned() else {
+            return Err(self.no_message_error().into());
+        };
+
+        let output = CommitOutput::build(
+            &message,
+            candidates,
+            &analysis.context,
+            analysis.split_groups.as_deref(),
+        );
+        Ok(serde_json::to_value(output).expect("CommitOutput serializes to JSON"))
+    }
+
+    async fn refine(
+        &self,
+        id: &Value,
+        params: RefineParams,
+        token: &CancellationToken,
+    ) -> RpcResult {
+        if params.message.trim().is_empty() || params.feedback.trim().is_empty() {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                "message and feedback are required",
+            ));
+        }
+
+        let analysis = self.analyze_repo(params.cwd, token).await?;
+        self.ensure_verified().await?;
+        self.app.check_budget(&self.provider)?;
+
+        let app = self.app.with_cancel_token(token.clone());
+        let prompt =
+            app.resolve_refinement_prompt(&analysis.prompt, &params.message, &params.feedback)?;
+        let message = self
+            .stream_candidate(
+                &app,
+                id,
+                0,
+                &analysis.context,
+                &prompt,
+                &analysis.system_prompt,
+            )
+            .await?
+            .ok_or_else(|| self.no_message_error())?;
+
+        let output = CommitOutput::build(
+            &message,
+            vec![message.clone()],
+            &analysis.context,
+            analysis.split_groups.as_deref(),
+        );
+        Ok(serde_json::to_value(output).expect("CommitOutput serializes to JSON"))
+    }
+
+    // ─── Helpers ───
+
+    /// Generate one candidate, forwarding streamed tokens to the client as
+    /// `$/token` notifications. All tokens are sent before this returns, so
+    /// they always arrive ahead of the response.
+    async fn stream_candidate(
+        &self,
+        app: &App,
+        id: &Value,
+        index: usize,
+        context: &PromptContext,
+        prompt: &str,
+        system_prompt: &str,
+    ) -> Result<Option<String>> {
+        let (tx, mut rx) = mpsc::channel::<String>(64);
+        let out = self.out.clone();
+        let request_id = id.clone();
+        let forward = tokio::spawn(async move {
+            while let Some(token) = rx.recv().await {
+                if token.is_empty() {
+                    continue;
+                }
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "$/token",
+                    "params": { "id": request_id, "candidate": index, "token": token },
+                });
+                if out.send(notification.to_string()).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let result = app
+            .generate_candidate(index, &self.provider, context, prompt, system_prompt, tx)
+            .await;
+        if let Err(e) = forward.await {
+            warn!("token forwarder panicked: {e}");
+        }
+        result
+    }
+
+    /// Verify the provider once; later requests reuse the result.
+    async fn ensure_verified(&self) -> Result<()> {
+        self.verified
+            .get_or_try_init(|| self.provider.verify())
+            .await?;
+        Ok(())
+    }
+
+    fn no_message_error(&self) -> Error {
+        Error::Provider {
+            provider: self.provider.name().into(),
+            message: "No valid commit message generated".into(),
+        }
+    }
+
+    /// Run the analysis half of the CLI pipeline against the staged changes.
+    ///
+    /// Never prompts: secrets always fail the request, as in a git hook.
+    async fn analyze_repo(
+        &self,
+        cwd: Option<PathBuf>,
+        token: &CancellationToken,
+    ) -> Result<Analysis> {
+        let app = &self.app;
+        let config = app.config();
+        let progress = Progress::new(true);
+
+        let git = match cwd {
+            Some(ref dir) => {
+                let git = GitService::discover_at(dir)?;
+                let root = git.work_dir().canonicalize().ok();
+                if root.is_none() || root != self.repo {
+                    return Err(Error::Protocol(format!(
+                        "cwd {} is outside the repository the server was started in",
+                        dir.display()
+                    )));
+                }
+                git
+            }
+            None => GitService::discover()?,
+        };
+        let (changes, full_diff) = git
+            .get_staged_changes(config.max_file_lines, config.rename_threshold)
+            .await?;
+        let changes = app.apply_exclude_patterns(changes, &progress)?;
+
+        if safety::check_for_conflicts(&changes) {
+            return Err(Error::MergeConflicts);
+        }
+
+        let patterns = safety::build_patterns(
+            &config.custom_secret_patterns,
+            &config.disabled_secret_patterns,
+        );
+        let secrets = safety::scan_full_diff_with_patterns(&full_diff, &patterns);
+        if !secrets.is_empty() {
+            return Err(Error::SecretsDetected {
+                patterns: secrets.into_iter().map(|s| s.pattern_name).collect(),
+            });
+        }
+
+        if token.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        let file_paths: Vec<PathBuf> = changes.files.iter().map(|f| f.path.clone()).collect();
+        let (staged_map, head_map) = git.fetch_file_contents(&file_paths).await;
+        let (symbols, symbol_diffs) =
+            self.analyzer
+                .extract_symbols(&changes.files, &staged_map, &head_map);
+
+        let split_groups = if app.cli().no_split {
+            None
+        } else {
+            match CommitSplitter::analyze(&changes, &symbols) {
+                SplitSuggestion::SuggestSplit(groups) => Some(groups),
+                _ => None,
+            }
+        };
+
+        let mut context = ContextBuilder::build(&changes, &symbols, &symbol_diffs, config);
+        if config.learn_from_history {
+            context.history_context =
+                HistoryService::analyze(git.work_dir(), config.history_sample_size)
+                    .await
+                    .map(|ctx| ctx.to_prompt_section(config.history_sample_size));
+        }
+
+        let system_prompt = app.resolve_system_prompt()?;
+        let prompt = app.resolve_user_prompt(&context)?;
+
+        if token.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        Ok(Analysis {
+            changes,
+            context,
+            split_groups,
+            system_prompt,
+            prompt,
+        })
+    }
+}
@@ -4,7 +4,7 @@
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock, Mutex};
 
 use rayon::prelude::*;
 use regex::Regex;
@@ -69,7 +69,54 @@ impl DiffHunk {
         hunks
     }
 
-    fn parse_hunk_header(line: &str) -> Option<Self> {
+    /// Keep the file header of a single-file diff and only the hunks at
+    /// `indices` (0-based, in diff order). Line endings are preserved and the
+    /// result ends with a newline, so it can be fed to `git apply`.
+    #[must_use]
+    pub fn select(diff: &str, indices: &[usize]) -> String {
+        let mut out = String::with_capacity(diff.len());
+        let mut current: Option<usize> = None;
+
+        for line in diff.split_inclusive('\n') {
+            if Self::parse_hunk_header(line).is_some() {
+                current = Some(current.map_or(0, |i| i + 1));
+            }
+            if current.is_none_or(|i| indices.contains(&i)) {
+                out.push_str(line);
+            }
+        }
+
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Count added and removed lines in a unified diff. `+++`/`---` lines
+    /// are file headers only before a file's first hunk; inside a hunk they
+    /// are content, e.g. a removed `-- comment` line in SQL.
+    #[must_use]
+    pub fn count_changes(diff: &str) -> (usize, usize) {
+        let mut additions = 0;
+        let mut deletions = 0;
+        let mut in_hunk = false;
+        for line in diff.lines() {
+            if line.starts_with("diff --git ") {
+                in_hunk = false;
+            } else if line.starts_with("@@") {
+                in_hunk = true;
+            } else if !in_hunk {
+                continue;
+            } else if line.starts_with('+') {
+                additions += 1;
+            } else if line.starts_with('-') {
+                deletions += 1;
+            }
+        }
+        (additions, deletions)
+    }
+
+    pub(crate) fn parse_hunk_header(line: &str) -> Option<Self> {
         let caps = HUNK_REGEX.captures(line)?;
 
         let old_start: usize = caps.get(1)?.as_str().parse().ok()?;
@@ -112,7 +159,12 @@ struct LanguageConfig {
     file_ext: &'static str,
 }
 
-pub struct AnalyzerService;
+/// Symbol extractor. Compiled queries are cached per language for the
+/// lifetime of the service, so long-running callers (`commitbee serve`)
+/// only pay the compile cost once.
+pub struct AnalyzerService {
+    queries: Mutex<HashMap<&'static str, Arc<Query>>>,
+}
 
 impl AnalyzerService {
     /// Body-like node kinds across all supported languages.
@@ -134,7 +186,20 @@ impl AnalyzerService {
     const MAX_SIGNATURE_LEN: usize = 200;
 
     pub fn new() -> Result<Self> {
-        Ok(Self)
+        Ok(Self {
+            queries: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Compiled query for a language, compiling it on first use.
+    fn query(&self, config: &LanguageConfig) -> Option<Arc<Query>> {
+        let mut queries = self.queries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(query) = queries.get(config.file_ext) {
+            return Some(Arc::clone(query));
+        }
+        let query = Arc::new(Query::new(&config.language, config.query_source).ok()?);
+        queries.insert(config.file_ext, Arc::clone(&query));
+        Some(query)
     }
 
     /// Extract symbols from file changes using full file content + hunk mapping.
@@ -220,9 +285,17 @@ impl AnalyzerService {
                 };
 
                 config
-                    .map(|cfg| {
+                    .and_then(|cfg| {
+                        let query = self.query(&cfg)?;
                         let hunks = DiffHunk::parse_from_diff(&change.diff);
-                        Self::extract_for_file(cfg, change, &hunks, staged_content, head_content)
+                        Some(Self::extract_for_file(
+                            cfg,
+                            &query,
+                            change,
+                            &hunks,
+                            staged_content,
+                            head_content,
+                        ))
                     })
                     .unwrap_or_default()
             })
@@ -239,6 +312,7 @@ impl AnalyzerService {
 
     fn extract_for_file(
         config: LanguageConfig,
+        query: &Query,
         change: &FileChange,
         hunks: &[DiffHunk],
         staged_content: &HashMap<PathBuf, String>,
@@ -249,10 +323,6 @@ impl AnalyzerService {
             return (Vec::new(), Vec::new());
         }
 
-        let Ok(query) = Query::new(&config.language, config.query_source) else {
-            return (Vec::new(), Vec::new());
-        };
-
         let mut staged_symbols = Vec::new();
         let mut head_symbols = Vec::new();
 
@@ -260,7 +330,7 @@ impl AnalyzerService {
         if let Some(content) = staged_content.get(&change.path) {
             let changed = Self::extract_changed_symbols_with_query(
                 &mut parser,
-                &query,
+                query,
                 config.file_ext,
                 &change.path,
                 content,
@@ -274,7 +344,7 @@ impl AnalyzerService {
         if let Some(content) = head_content.get(&change.path) {
             let changed = Self::extract_changed_symbols_with_query(
                 &mut parser,
-                &query,
+                query,
                 config.file_ext,
                 &change.path,
                 content,
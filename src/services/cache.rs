@@ -0,0 +1,188 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::config::{CommitFormat, ResolvedProfile};
+use crate::domain::Trailer;
+use crate::error::Result;
+
+/// Bumped whenever the entry layout or key derivation changes.
+const CACHE_VERSION: u32 = 3;
+
+/// Distinguishes temp files written by concurrent `extend` calls in one process.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// On-disk cache of sanitized commit message candidates, keyed by a hash of
+/// everything that shapes the LLM response.
+///
+/// One JSON file per key. Read failures are treated as misses, so a corrupt
+/// or foreign file never blocks generation. Entries can quote the diff, so
+/// the directory and files are only readable by the owner.
+pub struct ResponseCache {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    version: u32,
+    candidates: Vec<String>,
+}
+
+impl ResponseCache {
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Cache key for a generation request: provider endpoint, model,
+    /// sampling settings, both prompts, and the format rules and trailers
+    /// used to sanitize.
+    #[must_use]
+    pub fn key(
+        profile: &ResolvedProfile,
+        format: &CommitFormat,
+        trailers: &[Trailer],
+        system_prompt: &str,
+        prompt: &str,
+    ) -> String {
+        let format = serde_json::to_string(format).expect("CommitFormat serializes to JSON");
+        let kind = profile.kind.to_string();
+        let temperature = profile.temperature.to_bits().to_string();
+        let num_predict = profile.num_predict.to_string();
+        let think = profile.think.to_string();
+        let trailers = trailers
+            .iter()
+            .map(Trailer::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let fields = [
+            kind.as_str(),
+            profile.base_url.as_deref().unwrap_or_default(),
+            profile.model.as_str(),
+            temperature.as_str(),
+            num_predict.as_str(),
+            think.as_str(),
+            format.as_str(),
+            trailers.as_str(),
+            system_prompt,
+            prompt,
+        ];
+
+        let mut hasher = Sha256::new();
+        hasher.update(CACHE_VERSION.to_le_bytes());
+        for field in fields {
+            // Length-prefix each field so ("ab", "c") and ("a", "bc") differ
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+
+    /// Cached candidates for `key`, oldest first. Empty on a miss.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Vec<String> {
+        let path = self.path(key);
+        let entry = fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<CacheEntry>(&s).ok());
+        match entry {
+            Some(entry) if entry.version == CACHE_VERSION => entry.candidates,
+            _ => {
+                debug!(key, "response cache miss");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Append candidates to the entry for `key`, skipping ones already stored.
+    pub fn extend(&self, key: &str, candidates: &[String]) -> Result<()> {
+        let mut stored = self.get(key);
+        for candidate in candidates {
+            if !stored.contains(candidate) {
+                stored.push(candidate.clone());
+            }
+        }
+
+        let entry = CacheEntry {
+            version: CACHE_VERSION,
+            candidates: stored,
+        };
+        let json = serde_json::to_string_pretty(&entry).expect("CacheEntry serializes to JSON");
+
+        // Write to a temp file and rename so readers never see a partial entry.
+        // The name is unique so concurrent runs don't clobber each other's.
+        self.create_dir()?;
+        let tmp = self.dir.join(format!(
+            "{key}.json.{}-{}.tmp",
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let written = Self::create_private(&tmp).and_then(|mut f| f.write_all(json.as_bytes()));
+        if let Err(e) = written.and_then(|()| fs::rename(&tmp, self.path(key))) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn create_dir(&self) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.dir, fs::Permissions::from_mode(0o700))?;
+        }
+        Ok(())
+    }
+
+    fn create_private(path: &Path) -> std::io::Result<fs::File> {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(path)
+    }
+
+    /// Remove all cached entries. Returns how many were removed.
+    pub fn clear(&self) -> Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry?.path();
+            let is_entry = path.file_name().and_then(|n| n.to_str()).is_some_and(|n| {
+                n.ends_with(".json") || (n.contains(".json.") && n.ends_with(".tmp"))
+            });
+            if is_entry {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
@@ -0,0 +1,252 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::services::git::GitService;
+use crate::services::history::{CommitRecord, HistoryService};
+
+/// Keep-a-Changelog sections in render order, with the commit types they collect.
+///
+/// Commits that don't follow the conventional format land in "Other".
+const SECTIONS: &[(&str, &[&str])] = &[
+    ("Added", &["feat"]),
+    ("Changed", &["refactor", "perf", "style", "revert"]),
+    ("Fixed", &["fix"]),
+    ("Documentation", &["docs"]),
+    ("Maintenance", &["build", "ci", "chore", "test"]),
+];
+
+const OTHER_SECTION: &str = "Other";
+
+/// Semantic version bump implied by a set of commits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BumpLevel {
+    /// Nothing user-facing changed (docs, chores, CI, ...).
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl BumpLevel {
+    /// Apply this bump to a `[v]MAJOR.MINOR.PATCH` version string.
+    ///
+    /// Pre-release and build suffixes are dropped. While the major version is
+    /// 0, breaking changes bump the minor version (Cargo semver rules).
+    /// Returns `None` if the version can't be parsed or nothing needs bumping.
+    #[must_use]
+    pub fn apply(self, version: &str) -> Option<String> {
+        let (prefix, core) = match version.strip_prefix('v') {
+            Some(rest) => ("v", rest),
+            None => ("", version),
+        };
+        let core = core.split(['-', '+']).next().unwrap_or(core);
+        let mut parts = core.split('.').map(str::parse::<u64>);
+        let (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return None;
+        };
+
+        let (major, minor, patch) = match self {
+            Self::None => return None,
+            Self::Major if major == 0 => (0, minor + 1, 0),
+            Self::Major => (major + 1, 0, 0),
+            Self::Minor => (major, minor + 1, 0),
+            Self::Patch => (major, minor, patch + 1),
+        };
+        Some(format!("{prefix}{major}.{minor}.{patch}"))
+    }
+}
+
+impl fmt::Display for BumpLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::None => "none",
+            Self::Patch => "patch",
+            Self::Minor => "minor",
+            Self::Major => "major",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A single commit as it appears in the changelog.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangelogEntry {
+    pub sha: String,
+    /// Conventional type, or `None` for non-conventional commits.
+    #[serde(rename = "type")]
+    pub commit_type: Option<String>,
+    pub scope: Option<String>,
+    pub description: String,
+    pub breaking: bool,
+}
+
+/// A breaking change note collected from a `BREAKING CHANGE:` footer or `!` marker.
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakingNote {
+    pub sha: String,
+    pub scope: Option<String>,
+    pub description: String,
+}
+
+/// Entries under one Keep-a-Changelog heading, ordered by scope.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangelogSection {
+    pub title: String,
+    pub entries: Vec<ChangelogEntry>,
+}
+
+/// Release notes for a commit range.
+#[derive(Debug, Clone, Serialize)]
+pub struct Changelog {
+    pub range: String,
+    /// Release version for the heading; `None` renders as `[Unreleased]`.
+    pub version: Option<String>,
+    /// Release date (`YYYY-MM-DD`), only shown alongside a version.
+    pub date: Option<String>,
+    pub bump: BumpLevel,
+    pub breaking_changes: Vec<BreakingNote>,
+    pub sections: Vec<ChangelogSection>,
+}
+
+pub struct ChangelogBuilder;
+
+impl ChangelogBuilder {
+    /// Group `commits` (oldest first) into changelog sections.
+    #[must_use]
+    pub fn build(range: &str, commits: &[CommitRecord]) -> Changelog {
+        let mut sections: Vec<ChangelogSection> = SECTIONS
+            .iter()
+            .map(|(title, _)| title)
+            .chain(std::iter::once(&OTHER_SECTION))
+            .map(|title| ChangelogSection {
+                title: (*title).to_string(),
+                entries: Vec::new(),
+            })
+            .collect();
+        let mut breaking_changes = Vec::new();
+        let mut bump = BumpLevel::None;
+
+        // Newest first, like every other changelog
+        for commit in commits.iter().rev() {
+            let sha = GitService::short_sha(&commit.sha).to_string();
+            let parsed = HistoryService::parse_conventional(&commit.subject);
+            let notes = commit.breaking_notes();
+            let breaking = !notes.is_empty();
+            let scope = parsed.as_ref().and_then(|p| p.scope.clone());
+
+            for note in notes {
+                breaking_changes.push(BreakingNote {
+                    sha: sha.clone(),
+                    scope: scope.clone(),
+                    description: note,
+                });
+            }
+
+            let commit_bump = match parsed.as_ref().map(|p| p.commit_type.as_str()) {
+                _ if breaking => BumpLevel::Major,
+                Some("feat") => BumpLevel::Minor,
+                Some("fix" | "perf" | "refactor" | "revert") => BumpLevel::Patch,
+                _ => BumpLevel::None,
+            };
+            bump = bump.max(commit_bump);
+
+            let index = parsed
+                .as_ref()
+                .and_then(|p| {
+                    SECTIONS
+                        .iter()
+                        .position(|(_, types)| types.contains(&p.commit_type.as_str()))
+                })
+                .unwrap_or(SECTIONS.len());
+
+            let entry = match parsed {
+                Some(p) => ChangelogEntry {
+                    sha,
+                    commit_type: Some(p.commit_type),
+                    scope: p.scope,
+                    description: p.subject_text,
+                    breaking,
+                },
+                None => ChangelogEntry {
+                    sha,
+                    commit_type: None,
+                    scope: None,
+                    description: commit.subject.clone(),
+                    breaking,
+                },
+            };
+            sections[index].entries.push(entry);
+        }
+
+        for section in &mut sections {
+            // Stable sort keeps newest-first order within a scope; unscoped entries go last
+            section.entries.sort_by(|a, b| match (&a.scope, &b.scope) {
+                (Some(x), Some(y)) => x.cmp(y),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            });
+        }
+        sections.retain(|s| !s.entries.is_empty());
+
+        Changelog {
+            range: range.to_string(),
+            version: None,
+            date: None,
+            bump,
+            breaking_changes,
+            sections,
+        }
+    }
+}
+
+impl Changelog {
+    /// Render as a Keep-a-Changelog release section.
+    #[must_use]
+    pub fn render_markdown(&self) -> String {
+        let mut out = match (&self.version, &self.date) {
+            (Some(version), Some(date)) => format!("## [{}] - {}\n", version, date),
+            (Some(version), None) => format!("## [{}]\n", version),
+            (None, _) => "## [Unreleased]\n".to_string(),
+        };
+
+        if !self.breaking_changes.is_empty() {
+            out.push_str("\n### Breaking Changes\n\n");
+            for note in &self.breaking_changes {
+                out.push_str(&Self::bullet(
+                    note.scope.as_deref(),
+                    &note.description,
+                    &note.sha,
+                ));
+            }
+        }
+
+        for section in &self.sections {
+            out.push_str(&format!("\n### {}\n\n", section.title));
+            for entry in &section.entries {
+                out.push_str(&Self::bullet(
+                    entry.scope.as_deref(),
+                    &entry.description,
+                    &entry.sha,
+                ));
+            }
+        }
+
+        out
+    }
+
+    fn bullet(scope: Option<&str>, description: &str, sha: &str) -> String {
+        match scope {
+            Some(scope) => format!("- **{}:** {} (`{}`)\n", scope, description, sha),
+            None => format!("- {} (`{}`)\n", description, sha),
+        }
+    }
+}
@@ -251,6 +251,7 @@ impl ContextBuilder {
             structured_changes: diffs.to_vec(),
             intents,
             has_unsafe_addition,
+            trailers: Vec::new(), // Set by App for the commits it creates
         }
     }
 
@@ -1216,29 +1217,27 @@ impl ContextBuilder {
                 };
 
                 match name {
-                    "Cargo.toml" => {
-                        // rust-version (MSRV) changed
-                        if content.contains("rust-version") && is_added {
-                            signals.push(format!("MSRV changed in Cargo.toml: {}", content.trim()));
-                        }
+                    // rust-version (MSRV) changed
+                    "Cargo.toml" if content.contains("rust-version") && is_added => {
+                        signals.push(format!("MSRV changed in Cargo.toml: {}", content.trim()));
                     }
-                    "package.json" => {
-                        // engines.node minimum raised
-                        if content.contains("\"node\"") && is_added && content.contains("engines") {
-                            signals.push(format!(
-                                "Node engine requirement changed: {}",
-                                content.trim()
-                            ));
-                        }
+                    // engines.node minimum raised
+                    "package.json"
+                        if content.contains("\"node\"")
+                            && is_added
+                            && content.contains("engines") =>
+                    {
+                        signals.push(format!(
+                            "Node engine requirement changed: {}",
+                            content.trim()
+                        ));
                     }
-                    "pyproject.toml" => {
-                        // requires-python minimum raised
-                        if content.contains("requires-python") && is_added {
-                            signals.push(format!(
-                                "Python version requirement changed: {}",
-                                content.trim()
-                            ));
-                        }
+                    // requires-python minimum raised
+                    "pyproject.toml" if content.contains("requires-python") && is_added => {
+                        signals.push(format!(
+                            "Python version requirement changed: {}",
+                            content.trim()
+                        ));
                     }
                     _ => {}
                 }
@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -10,6 +10,7 @@ use tokio::process::Command;
 
 use crate::domain::{ChangeStatus, DiffStats, FileCategory, FileChange, StagedChanges};
 use crate::error::{Error, Result};
+use crate::services::analyzer::DiffHunk;
 
 pub(crate) struct GitService {
     repo: gix::Repository,
@@ -18,7 +19,12 @@ pub(crate) struct GitService {
 
 impl GitService {
     pub fn discover() -> Result<Self> {
-        let repo = gix::discover(".").map_err(|_| Error::NotAGitRepo)?;
+        Self::discover_at(Path::new("."))
+    }
+
+    /// Discover the repository containing `path`.
+    pub fn discover_at(path: &Path) -> Result<Self> {
+        let repo = gix::discover(path).map_err(|_| Error::NotAGitRepo)?;
 
         let work_dir = repo
             .workdir()
@@ -42,6 +48,27 @@ impl GitService {
         Ok(())
     }
 
+    /// Whether a merge is waiting to be committed (`MERGE_HEAD` exists).
+    #[must_use]
+    pub fn merge_in_progress(&self) -> bool {
+        matches!(self.repo.state(), Some(gix::state::InProgress::Merge))
+    }
+
+    /// The commits being merged into `HEAD`, one per line of `MERGE_HEAD`
+    /// (several for an octopus merge).
+    #[must_use]
+    pub fn merge_heads(&self) -> Vec<String> {
+        self.read_git_file("MERGE_HEAD")
+            .map(|text| text.lines().map(|l| l.trim().to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Read a state file from the git directory, such as `SQUASH_MSG`.
+    #[must_use]
+    pub fn read_git_file(&self, name: &str) -> Option<String> {
+        std::fs::read_to_string(self.repo.git_dir().join(name)).ok()
+    }
+
     // ─── Async Git Helpers ───
 
     async fn run_git(&self, args: &[&str]) -> Result<String> {
@@ -59,6 +86,37 @@ impl GitService {
         Ok(String::from_utf8_lossy(&output.stdout).into_owned())
     }
 
+    /// Run git with extra environment variables and data piped to stdin.
+    async fn run_git_with_input(
+        &self,
+        args: &[&str],
+        envs: &[(&str, &str)],
+        input: &str,
+    ) -> Result<String> {
+        use tokio::io::AsyncWriteExt;
+
+        let mut child = Command::new("git")
+            .args(args)
+            .envs(envs.iter().copied())
+            .current_dir(&self.work_dir)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes()).await?;
+        }
+
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Git(stderr.to_string()));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
     // ─── Staged Changes (Single-Pass Diff) ───
 
     /// Returns `(StagedChanges, full_diff)` — the full diff is the raw unified
@@ -68,7 +126,16 @@ impl GitService {
         max_file_lines: usize,
         rename_threshold: u8,
     ) -> Result<(StagedChanges, String)> {
-        self.check_state()?;
+        // A merge can be committed once no path is left unmerged
+        if self.merge_in_progress()
+            && !self
+                .run_git(&["diff", "--name-only", "--diff-filter=U"])
+                .await?
+                .trim()
+                .is_empty()
+        {
+            return Err(Error::MergeConflicts);
+        }
 
         let use_renames = rename_threshold > 0;
         let rename_arg = format!("--find-renames={}%", rename_threshold);
@@ -90,7 +157,195 @@ impl GitService {
         let (status_output, diff_output) =
             tokio::try_join!(self.run_git(&status_args), self.run_git(&diff_args),)?;
 
-        let file_diffs = Self::split_unified_diff(&diff_output);
+        let changes = Self::parse_changes(&status_output, &diff_output, max_file_lines)?;
+        Ok((changes, diff_output))
+    }
+
+    /// Returns `(StagedChanges, full_diff)` for a single existing commit,
+    /// diffed against its first parent (or the empty tree for a root commit).
+    pub async fn get_commit_changes(
+        &self,
+        rev: &str,
+        max_file_lines: usize,
+        rename_threshold: u8,
+    ) -> Result<(StagedChanges, String)> {
+        let rename_arg = format!("--find-renames={}%", rename_threshold);
+        let rename_flag: &str = if rename_threshold > 0 {
+            &rename_arg
+        } else {
+            "--no-renames"
+        };
+        let status_args = [
+            "diff-tree",
+            "-r",
+            "-z",
+            "--root",
+            "--no-commit-id",
+            "--name-status",
+            rename_flag,
+            rev,
+        ];
+        let diff_args = [
+            "diff-tree",
+            "-r",
+            "-p",
+            "--root",
+            "--no-commit-id",
+            "--no-ext-diff",
+            "--unified=3",
+            rename_flag,
+            rev,
+        ];
+        let (status_output, diff_output) =
+            tokio::try_join!(self.run_git(&status_args), self.run_git(&diff_args),)?;
+
+        let changes = Self::parse_changes(&status_output, &diff_output, max_file_lines)?;
+        Ok((changes, diff_output))
+    }
+
+    /// Returns `(StagedChanges, full_diff)` for the net difference between two
+    /// revisions (`git diff <from> <to>`).
+    pub async fn get_range_changes(
+        &self,
+        from: &str,
+        to: &str,
+        max_file_lines: usize,
+        rename_threshold: u8,
+    ) -> Result<(StagedChanges, String)> {
+        let rename_arg = format!("--find-renames={}%", rename_threshold);
+        let rename_flag: &str = if rename_threshold > 0 {
+            &rename_arg
+        } else {
+            "--no-renames"
+        };
+        let status_args = ["diff", "-z", "--name-status", rename_flag, from, to];
+        let diff_args = [
+            "diff",
+            "--no-ext-diff",
+            "--unified=3",
+            rename_flag,
+            from,
+            to,
+        ];
+        let (status_output, diff_output) =
+            tokio::try_join!(self.run_git(&status_args), self.run_git(&diff_args),)?;
+
+        let changes = Self::parse_changes(&status_output, &diff_output, max_file_lines)?;
+        Ok((changes, diff_output))
+    }
+
+    /// Returns `(StagedChanges, full_diff)` for amending `HEAD`: everything
+    /// `HEAD` changed relative to its parent, plus whatever is staged now.
+    pub async fn get_amend_changes(
+        &self,
+        max_file_lines: usize,
+        rename_threshold: u8,
+    ) -> Result<(StagedChanges, String)> {
+        self.check_state()?;
+
+        let base = self.amend_base().await?;
+        let rename_arg = format!("--find-renames={}%", rename_threshold);
+        let rename_flag: &str = if rename_threshold > 0 {
+            &rename_arg
+        } else {
+            "--no-renames"
+        };
+        let status_args = [
+            "diff",
+            "-z",
+            "--cached",
+            "--name-status",
+            rename_flag,
+            &base,
+        ];
+        let diff_args = [
+            "diff",
+            "--cached",
+            "--no-ext-diff",
+            "--unified=3",
+            rename_flag,
+            &base,
+        ];
+        let (status_output, diff_output) =
+            tokio::try_join!(self.run_git(&status_args), self.run_git(&diff_args),)?;
+
+        let changes = Self::parse_changes(&status_output, &diff_output, max_file_lines)?;
+        Ok((changes, diff_output))
+    }
+
+    /// Changes between `rev` and the index, limited to `paths` and without
+    /// rename detection. Used to compare a merge resolution to each parent.
+    pub async fn get_resolution_changes(
+        &self,
+        rev: &str,
+        paths: &[PathBuf],
+        max_file_lines: usize,
+    ) -> Result<StagedChanges> {
+        let path_strs: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+        let mut status_args = vec![
+            "diff",
+            "-z",
+            "--cached",
+            "--name-status",
+            "--no-renames",
+            rev,
+            "--",
+        ];
+        let mut diff_args = vec![
+            "diff",
+            "--cached",
+            "--no-ext-diff",
+            "--unified=3",
+            "--no-renames",
+            rev,
+            "--",
+        ];
+        status_args.extend(path_strs.iter().map(String::as_str));
+        diff_args.extend(path_strs.iter().map(String::as_str));
+        let (status_output, diff_output) =
+            tokio::try_join!(self.run_git(&status_args), self.run_git(&diff_args),)?;
+
+        // A side the resolution matches exactly has no changes
+        match Self::parse_changes(&status_output, &diff_output, max_file_lines) {
+            Err(Error::NoStagedChanges) => Ok(StagedChanges {
+                files: Vec::new(),
+                stats: DiffStats::default(),
+            }),
+            other => other,
+        }
+    }
+
+    /// What an amended `HEAD` is diffed against: its first parent, or the
+    /// empty tree when `HEAD` is a root commit.
+    async fn amend_base(&self) -> Result<String> {
+        if self
+            .run_git(&["rev-parse", "--verify", "-q", "HEAD"])
+            .await
+            .is_err()
+        {
+            return Err(Error::Git("Nothing to amend: HEAD has no commits".into()));
+        }
+        match self
+            .run_git(&["rev-parse", "--verify", "-q", "HEAD^"])
+            .await
+        {
+            Ok(parent) => Ok(parent.trim().to_string()),
+            Err(_) => Ok(self
+                .run_git_with_input(&["mktree"], &[], "")
+                .await?
+                .trim()
+                .to_string()),
+        }
+    }
+
+    /// Build `StagedChanges` from NUL-delimited `--name-status` output and the
+    /// matching unified diff.
+    fn parse_changes(
+        status_output: &str,
+        diff_output: &str,
+        max_file_lines: usize,
+    ) -> Result<StagedChanges> {
+        let file_diffs = Self::split_unified_diff(diff_output);
 
         let mut files = Vec::new();
         let mut stats = DiffStats::default();
@@ -144,8 +399,9 @@ impl GitService {
                 file_diffs.get(diff_key.as_ref())
             };
 
-            let (additions, deletions) =
-                full_diff.map(|d| Self::count_changes(d)).unwrap_or((0, 0));
+            let (additions, deletions) = full_diff
+                .map(|d| DiffHunk::count_changes(d))
+                .unwrap_or((0, 0));
 
             // Truncate diff for prompt context (binary files get empty diff)
             let diff = full_diff
@@ -173,7 +429,7 @@ impl GitService {
             return Err(Error::NoStagedChanges);
         }
 
-        Ok((StagedChanges { files, stats }, diff_output))
+        Ok(StagedChanges { files, stats })
     }
 
     /// Split a unified diff into per-file sections keyed by file path.
@@ -227,6 +483,66 @@ impl GitService {
     pub async fn fetch_file_contents(
         &self,
         paths: &[PathBuf],
+    ) -> (HashMap<PathBuf, String>, HashMap<PathBuf, String>) {
+        self.fetch_contents_between(paths, ":0:", "HEAD:").await
+    }
+
+    /// Fetch file content at `rev` and at its first parent, in the same shape
+    /// as [`fetch_file_contents`](Self::fetch_file_contents).
+    pub async fn fetch_commit_file_contents(
+        &self,
+        rev: &str,
+        paths: &[PathBuf],
+    ) -> (HashMap<PathBuf, String>, HashMap<PathBuf, String>) {
+        self.fetch_contents_between(paths, &format!("{rev}:"), &format!("{rev}^:"))
+            .await
+    }
+
+    /// Fetch staged content and content at `HEAD^`, for amending `HEAD`, in
+    /// the same shape as [`fetch_file_contents`](Self::fetch_file_contents).
+    pub async fn fetch_amend_file_contents(
+        &self,
+        paths: &[PathBuf],
+    ) -> (HashMap<PathBuf, String>, HashMap<PathBuf, String>) {
+        self.fetch_contents_between(paths, ":0:", "HEAD^:").await
+    }
+
+    /// Fetch `(resolved, ours, theirs)` content for a merge: the index, `HEAD`
+    /// and `MERGE_HEAD`. Paths missing on a side are absent from its map.
+    pub async fn fetch_merge_file_contents(
+        &self,
+        paths: &[PathBuf],
+    ) -> (
+        HashMap<PathBuf, String>,
+        HashMap<PathBuf, String>,
+        HashMap<PathBuf, String>,
+    ) {
+        let ((resolved, ours), (theirs, _)) = tokio::join!(
+            self.fetch_contents_between(paths, ":0:", "HEAD:"),
+            self.fetch_contents_between(paths, "MERGE_HEAD:", "HEAD:"),
+        );
+        (resolved, ours, theirs)
+    }
+
+    /// Fetch file content at `to` and at `from`, in the same shape as
+    /// [`fetch_file_contents`](Self::fetch_file_contents).
+    pub async fn fetch_range_file_contents(
+        &self,
+        from: &str,
+        to: &str,
+        paths: &[PathBuf],
+    ) -> (HashMap<PathBuf, String>, HashMap<PathBuf, String>) {
+        self.fetch_contents_between(paths, &format!("{to}:"), &format!("{from}:"))
+            .await
+    }
+
+    /// Fetch `(new, old)` content maps where each side is addressed by a
+    /// `git show` object prefix such as `:0:` or `HEAD:`.
+    async fn fetch_contents_between(
+        &self,
+        paths: &[PathBuf],
+        new_prefix: &str,
+        old_prefix: &str,
     ) -> (HashMap<PathBuf, String>, HashMap<PathBuf, String>) {
         let mut set = tokio::task::JoinSet::new();
         let work_dir: Arc<PathBuf> = Arc::new(self.work_dir.clone());
@@ -234,11 +550,11 @@ impl GitService {
         for path in paths {
             let work_dir = Arc::clone(&work_dir);
             let path = path.clone();
+            let new_spec = format!("{}{}", new_prefix, path.display());
+            let old_spec = format!("{}{}", old_prefix, path.display());
             set.spawn(async move {
-                let staged =
-                    Self::fetch_git_show(&work_dir, &format!(":0:{}", path.display())).await;
-                let head =
-                    Self::fetch_git_show(&work_dir, &format!("HEAD:{}", path.display())).await;
+                let staged = Self::fetch_git_show(&work_dir, &new_spec).await;
+                let head = Self::fetch_git_show(&work_dir, &old_spec).await;
                 (path, staged, head)
             });
         }
@@ -277,21 +593,6 @@ impl GitService {
 
     // ─── Diff Parsing ───
 
-    fn count_changes(diff: &str) -> (usize, usize) {
-        let mut additions = 0;
-        let mut deletions = 0;
-
-        for line in diff.lines() {
-            if line.starts_with('+') && !line.starts_with("+++") {
-                additions += 1;
-            } else if line.starts_with('-') && !line.starts_with("---") {
-                deletions += 1;
-            }
-        }
-
-        (additions, deletions)
-    }
-
     fn is_binary_path(path: &Path) -> bool {
         let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
 
@@ -322,41 +623,105 @@ impl GitService {
         )
     }
 
-    // ─── Staging Operations ───
+    // ─── Split Commits ───
 
-    /// Check if any staged file also has unstaged modifications.
-    /// Returns the list of overlapping file paths.
-    pub async fn has_unstaged_overlap(&self) -> Result<Vec<PathBuf>> {
-        let (staged_output, unstaged_output) = tokio::try_join!(
-            self.run_git(&["diff", "--cached", "--name-only"]),
-            self.run_git(&["diff", "--name-only"]),
-        )?;
-
-        let staged: std::collections::HashSet<&str> =
-            staged_output.lines().filter(|l| !l.is_empty()).collect();
-        let unstaged: std::collections::HashSet<&str> =
-            unstaged_output.lines().filter(|l| !l.is_empty()).collect();
+    /// Raw staged diff of a single file, with the same hunk layout as
+    /// [`get_staged_changes`](Self::get_staged_changes).
+    pub async fn staged_file_diff(&self, path: &Path) -> Result<String> {
+        let path = path.display().to_string();
+        self.run_git(&[
+            "diff",
+            "--cached",
+            "--no-ext-diff",
+            "--unified=3",
+            "--no-renames",
+            "--",
+            &path,
+        ])
+        .await
+    }
 
-        Ok(staged.intersection(&unstaged).map(PathBuf::from).collect())
+    /// Write the current index as a tree object. Split commits are built from
+    /// this snapshot, so later changes to the index don't leak into them.
+    pub async fn snapshot_index(&self) -> Result<String> {
+        Ok(self.run_git(&["write-tree"]).await?.trim().to_string())
     }
 
-    /// Unstage all currently staged files (soft reset).
-    pub async fn unstage_all(&self) -> Result<()> {
-        self.run_git(&["reset", "HEAD"]).await?;
-        Ok(())
+    /// Commit part of an index snapshot on top of `HEAD`.
+    ///
+    /// `paths` are taken whole from `snapshot` (and removed if it doesn't have
+    /// them), then `patches` are applied on top. The commit is assembled in a
+    /// temporary index file, so neither the real index nor the working tree
+    /// changes. Hooks run as for a normal commit.
+    pub async fn commit_from_snapshot(
+        &self,
+        snapshot: &str,
+        paths: &[PathBuf],
+        patches: &[String],
+        message: &str,
+    ) -> Result<()> {
+        let index = self.work_dir.join(
+            self.run_git(&["rev-parse", "--git-path", "commitbee-split-index"])
+                .await?
+                .trim(),
+        );
+        let index_str = index.display().to_string();
+        let envs = [("GIT_INDEX_FILE", index_str.as_str())];
+
+        let result = self
+            .commit_in_index(&envs, snapshot, paths, patches, message)
+            .await;
+        let _ = tokio::fs::remove_file(&index).await;
+        result
     }
 
-    /// Stage specific files by path.
-    pub async fn stage_files(&self, paths: &[PathBuf]) -> Result<()> {
-        if paths.is_empty() {
-            return Ok(());
+    async fn commit_in_index(
+        &self,
+        envs: &[(&str, &str)],
+        snapshot: &str,
+        paths: &[PathBuf],
+        patches: &[String],
+        message: &str,
+    ) -> Result<()> {
+        let has_head = self
+            .run_git(&["rev-parse", "--verify", "-q", "HEAD"])
+            .await
+            .is_ok();
+        let base = if has_head { "HEAD" } else { "--empty" };
+        self.run_git_with_input(&["read-tree", base], envs, "")
+            .await?;
+
+        if !paths.is_empty() {
+            let path_strs: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+            let mut args = vec!["ls-tree", "-z", "--full-tree", snapshot, "--"];
+            args.extend(path_strs.iter().map(String::as_str));
+            let listing = self.run_git(&args).await?;
+
+            // `ls-tree` entries are valid `--index-info` input; paths missing
+            // from the snapshot were deleted and get a zero mode to remove them
+            let mut info = String::new();
+            let mut present = HashSet::new();
+            for entry in listing.split('\0').filter(|e| !e.is_empty()) {
+                if let Some((_, path)) = entry.split_once('\t') {
+                    present.insert(path.to_string());
+                }
+                info.push_str(entry);
+                info.push('\0');
+            }
+            for path in path_strs.iter().filter(|p| !present.contains(p.as_str())) {
+                info.push_str(&format!("0 {}\t{path}\0", "0".repeat(snapshot.len())));
+            }
+            self.run_git_with_input(&["update-index", "-z", "--index-info"], envs, &info)
+                .await?;
         }
 
-        let path_strs: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
-        let mut args: Vec<&str> = vec!["add", "--"];
-        args.extend(path_strs.iter().map(|s| s.as_str()));
+        for patch in patches {
+            self.run_git_with_input(&["apply", "--cached", "-"], envs, patch)
+                .await?;
+        }
 
-        self.run_git(&args).await?;
+        self.run_git_with_input(&["commit", "-q", "-F", "-"], envs, message)
+            .await?;
         Ok(())
     }
 
@@ -376,4 +741,623 @@ impl GitService {
 
         Ok(())
     }
+
+    /// Commit that the staged changes revert, if any.
+    ///
+    /// During `git revert --no-commit` or a revert stopped by conflicts this
+    /// is `REVERT_HEAD`. Otherwise the last `depth` commits touching `paths`
+    /// are searched for one whose inverse patch has the same `git patch-id`
+    /// as the staged diff, i.e. an exact manual revert.
+    pub async fn find_reverted_commit(
+        &self,
+        paths: &[PathBuf],
+        depth: usize,
+    ) -> Result<Option<String>> {
+        if let Some(sha) = self.read_git_file("REVERT_HEAD") {
+            return Ok(Some(sha.trim().to_string()));
+        }
+        if paths.is_empty() {
+            return Ok(None);
+        }
+
+        let staged = self
+            .run_git(&[
+                "diff",
+                "--cached",
+                "--no-ext-diff",
+                "--no-renames",
+                "--no-prefix",
+            ])
+            .await?;
+        let staged_id = self
+            .run_git_with_input(&["patch-id", "--stable"], &[], &staged)
+            .await?;
+        let Some(staged_id) = staged_id.split_whitespace().next() else {
+            return Ok(None);
+        };
+
+        let depth_arg = format!("-n{depth}");
+        let path_strs: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+        let mut args = vec![
+            "log",
+            &depth_arg,
+            "--no-merges",
+            "-p",
+            "-R",
+            "--full-diff",
+            "--no-ext-diff",
+            "--no-renames",
+            "--no-prefix",
+            "--format=commit %H",
+            "--",
+        ];
+        args.extend(path_strs.iter().map(String::as_str));
+        let Ok(log) = self.run_git(&args).await else {
+            // Unborn HEAD: nothing to revert
+            return Ok(None);
+        };
+        let ids = self
+            .run_git_with_input(&["patch-id", "--stable"], &[], &log)
+            .await?;
+
+        Ok(ids.lines().find_map(|line| {
+            let (id, sha) = line.split_once(' ')?;
+            (id == staged_id).then(|| sha.to_string())
+        }))
+    }
+
+    /// Replace `HEAD` with a commit of the current index and `message`.
+    pub async fn amend(&self, message: &str) -> Result<()> {
+        self.run_git(&["commit", "--amend", "-m", message]).await?;
+        Ok(())
+    }
+
+    // ─── History Rewriting ───
+
+    /// Resolve a single revision or a `<from>..<to>` range into commit ids,
+    /// oldest first.
+    pub async fn resolve_range(&self, range: &str) -> Result<Vec<String>> {
+        let output = if range.contains("..") {
+            self.run_git(&["rev-list", "--reverse", "--topo-order", range])
+                .await?
+        } else {
+            let spec = format!("{range}^{{commit}}");
+            self.run_git(&["rev-parse", "--verify", "--quiet", &spec])
+                .await
+                .map_err(|_| Error::Git(format!("Unknown revision '{range}'")))?
+        };
+
+        let commits: Vec<String> = output
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect();
+
+        if commits.is_empty() {
+            return Err(Error::Git(format!("No commits in range '{range}'")));
+        }
+
+        Ok(commits)
+    }
+
+    /// Best common ancestor of two revisions.
+    pub async fn merge_base(&self, a: &str, b: &str) -> Result<String> {
+        let output = self
+            .run_git(&["merge-base", a, b])
+            .await
+            .map_err(|_| Error::Git(format!("No common ancestor between '{a}' and '{b}'")))?;
+        Ok(output.trim().to_string())
+    }
+
+    /// Name of the checked-out branch, or `None` when `HEAD` is detached.
+    pub async fn current_branch(&self) -> Option<String> {
+        let output = self
+            .run_git(&["symbolic-ref", "--quiet", "--short", "HEAD"])
+            .await
+            .ok()?;
+        let name = output.trim();
+        (!name.is_empty()).then(|| name.to_string())
+    }
+
+    /// The committer as `Name <email>`, or `None` when git has no identity.
+    pub async fn committer_identity(&self) -> Option<String> {
+        let ident = self.run_git(&["var", "GIT_COMMITTER_IDENT"]).await.ok()?;
+        // `Name <email> <timestamp> <tz>`
+        let end = ident.rfind('>')?;
+        Some(ident[..=end].trim().to_string())
+    }
+
+    /// Committer date of `rev` as `YYYY-MM-DD`.
+    pub async fn commit_date(&self, rev: &str) -> Result<String> {
+        let output = self.run_git(&["log", "-1", "--format=%cs", rev]).await?;
+        Ok(output.trim().to_string())
+    }
+
+    /// Full commit message (subject and body) of `rev`.
+    pub async fn commit_message(&self, rev: &str) -> Result<String> {
+        self.run_git(&["log", "-1", "--format=%B", rev]).await
+    }
+
+    /// Commits that rewording `targets` would replay: the first-parent chain
+    /// from the oldest target up to `HEAD`, oldest first.
+    ///
+    /// Fails if a target isn't on that chain (e.g. it came in through a
+    /// merge's second parent) or a merge sits between it and `HEAD`.
+    pub async fn reword_chain(&self, targets: &[String]) -> Result<Vec<String>> {
+        let chain = self.first_parent_chain(targets).await?;
+        Ok(chain.into_iter().map(|(sha, _)| sha).collect())
+    }
+
+    /// The commits among `shas` that carry a GPG, SSH or X.509 signature.
+    pub async fn signed_commits(&self, shas: &[String]) -> Result<Vec<String>> {
+        let mut signed = Vec::new();
+        for sha in shas {
+            let raw = self.run_git(&["cat-file", "commit", sha]).await?;
+            let header = raw.split("\n\n").next().unwrap_or_default();
+            if header.lines().any(|l| l.starts_with("gpgsig")) {
+                signed.push(sha.clone());
+            }
+        }
+        Ok(signed)
+    }
+
+    /// `reword_chain` with each commit's parents.
+    async fn first_parent_chain(&self, targets: &[String]) -> Result<Vec<(String, Vec<String>)>> {
+        // Every rewritten commit must be reachable from HEAD
+        for sha in targets {
+            if self
+                .run_git(&["merge-base", "--is-ancestor", sha, "HEAD"])
+                .await
+                .is_err()
+            {
+                return Err(Error::Git(format!(
+                    "Commit {} is not an ancestor of HEAD",
+                    Self::short_sha(sha)
+                )));
+            }
+        }
+
+        // Linear history from HEAD back to the oldest rewritten commit
+        let mut pending: HashSet<&str> = targets.iter().map(String::as_str).collect();
+        let mut chain: Vec<(String, Vec<String>)> = Vec::new();
+        let log = self
+            .run_git(&["rev-list", "--parents", "--first-parent", "HEAD"])
+            .await?;
+        for line in log.lines() {
+            if pending.is_empty() {
+                break;
+            }
+            let mut ids = line.split_whitespace().map(String::from);
+            let Some(sha) = ids.next() else { continue };
+            let parents: Vec<String> = ids.collect();
+            if parents.len() > 1 {
+                return Err(Error::Git(format!(
+                    "Cannot reword across merge commit {}",
+                    Self::short_sha(&sha)
+                )));
+            }
+            pending.remove(sha.as_str());
+            chain.push((sha, parents));
+        }
+
+        // Replaying the whole history would change every commit back to the root
+        if let Some(sha) = pending.into_iter().next() {
+            return Err(Error::Git(format!(
+                "Commit {} is not on the first-parent history of HEAD",
+                Self::short_sha(sha)
+            )));
+        }
+
+        chain.reverse();
+        Ok(chain)
+    }
+
+    /// Rewrite the messages of the given commits and replay every descendant
+    /// up to `HEAD` with `git commit-tree`.
+    ///
+    /// Trees, authorship and untouched messages are preserved, so neither the
+    /// worktree nor the index changes — only the ref `HEAD` points at moves.
+    /// Returns the new `HEAD` commit id.
+    pub async fn rewrite_messages(&self, messages: &HashMap<String, String>) -> Result<String> {
+        let old_head = self
+            .run_git(&["rev-parse", "HEAD"])
+            .await?
+            .trim()
+            .to_string();
+
+        let targets: Vec<String> = messages.keys().cloned().collect();
+        let chain = self.first_parent_chain(&targets).await?;
+
+        let Some((_, base_parents)) = chain.first() else {
+            return Ok(old_head);
+        };
+        let mut parent = base_parents.first().cloned();
+
+        for (sha, _) in &chain {
+            let meta = self
+                .run_git(&["log", "-1", "--format=%an%x00%ae%x00%ad", "--date=raw", sha])
+                .await?;
+            let mut fields = meta.trim_end_matches('\n').split('\0');
+            let name = fields.next().unwrap_or_default().to_string();
+            let email = fields.next().unwrap_or_default().to_string();
+            let date = fields.next().unwrap_or_default().to_string();
+
+            let message = match messages.get(sha) {
+                Some(m) => m.clone(),
+                None => self.commit_message(sha).await?,
+            };
+
+            let tree = format!("{sha}^{{tree}}");
+            let mut args = vec!["commit-tree", tree.as_str()];
+            if let Some(ref p) = parent {
+                args.extend(["-p", p.as_str()]);
+            }
+            args.extend(["-F", "-"]);
+
+            let envs = [
+                ("GIT_AUTHOR_NAME", name.as_str()),
+                ("GIT_AUTHOR_EMAIL", email.as_str()),
+                ("GIT_AUTHOR_DATE", date.as_str()),
+            ];
+            let new_sha = self.run_git_with_input(&args, &envs, &message).await?;
+            parent = Some(new_sha.trim().to_string());
+        }
+
+        let new_head = parent.unwrap_or(old_head.clone());
+        self.run_git(&[
+            "update-ref",
+            "-m",
+            "commitbee: reword",
+            "HEAD",
+            &new_head,
+            &old_head,
+        ])
+        .await?;
+
+        Ok(new_head)
+    }
+
+    /// Abbreviate a commit id for display.
+    #[must_use]
+    pub fn short_sha(sha: &str) -> &str {
+        &sha[..sha.len().min(7)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::analyzer::DiffHunk;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Create a repo with one commit per `(file, content, subject)` entry.
+    fn init_repo(commits: &[(&str, &str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        git(path, &["init", "-q"]);
+        git(path, &["config", "user.email", "test@test.com"]);
+        git(path, &["config", "user.name", "Test"]);
+        for (file, content, subject) in commits {
+            std::fs::write(path.join(file), content).unwrap();
+            git(path, &["add", "."]);
+            git(path, &["commit", "-q", "-m", subject]);
+        }
+        dir
+    }
+
+    #[tokio::test]
+    async fn resolve_range_single_and_range() {
+        let dir = init_repo(&[
+            ("a.txt", "a", "first"),
+            ("b.txt", "b", "second"),
+            ("c.txt", "c", "third"),
+        ]);
+        let service = GitService::discover_at(dir.path()).unwrap();
+
+        let single = service.resolve_range("HEAD").await.unwrap();
+        assert_eq!(single, vec![git(dir.path(), &["rev-parse", "HEAD"])]);
+
+        let range = service.resolve_range("HEAD~2..HEAD").await.unwrap();
+        assert_eq!(
+            range,
+            vec![
+                git(dir.path(), &["rev-parse", "HEAD~1"]),
+                git(dir.path(), &["rev-parse", "HEAD"]),
+            ],
+            "range should be oldest first"
+        );
+
+        assert!(service.resolve_range("HEAD..HEAD").await.is_err());
+        assert!(service.resolve_range("no-such-rev").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn get_commit_changes_reads_commit_diff() {
+        let dir = init_repo(&[
+            ("a.txt", "one\n", "first"),
+            ("a.txt", "one\ntwo\n", "second"),
+        ]);
+        let service = GitService::discover_at(dir.path()).unwrap();
+
+        let (changes, full_diff) = service.get_commit_changes("HEAD", 100, 70).await.unwrap();
+        assert_eq!(changes.files.len(), 1);
+        assert_eq!(changes.files[0].status, ChangeStatus::Modified);
+        assert_eq!(changes.files[0].additions, 1);
+        assert!(full_diff.contains("+two"));
+
+        // Root commit diffs against the empty tree
+        let (root, _) = service.get_commit_changes("HEAD~1", 100, 70).await.unwrap();
+        assert_eq!(root.files[0].status, ChangeStatus::Added);
+    }
+
+    #[tokio::test]
+    async fn amend_covers_head_and_staged_changes() {
+        let dir = init_repo(&[("a.txt", "one\n", "first")]);
+        let path = dir.path();
+        let service = GitService::discover_at(path).unwrap();
+
+        // Root commit: diffed against the empty tree
+        let (root, _) = service.get_amend_changes(100, 70).await.unwrap();
+        assert_eq!(root.files[0].status, ChangeStatus::Added);
+
+        std::fs::write(path.join("a.txt"), "one\ntwo\n").unwrap();
+        git(path, &["commit", "-q", "-am", "second"]);
+        std::fs::write(path.join("b.txt"), "b\n").unwrap();
+        git(path, &["add", "b.txt"]);
+
+        let (changes, full_diff) = service.get_amend_changes(100, 70).await.unwrap();
+        assert_eq!(changes.files.len(), 2);
+        assert!(full_diff.contains("+two"));
+        assert!(full_diff.contains("+b"));
+
+        service.amend("feat: two and b").await.unwrap();
+        assert_eq!(git(path, &["rev-list", "--count", "HEAD"]), "2");
+        assert_eq!(git(path, &["log", "-1", "--format=%s"]), "feat: two and b");
+        assert_eq!(
+            git(path, &["show", "HEAD", "--format=", "--name-only"]),
+            "a.txt\nb.txt"
+        );
+    }
+
+    #[tokio::test]
+    async fn find_reverted_commit_matches_inverse_patch() {
+        let dir = init_repo(&[
+            ("a.txt", "one\n", "first"),
+            ("a.txt", "one\ntwo\n", "feat: add two"),
+            ("b.txt", "b\n", "docs: add b"),
+        ]);
+        let path = dir.path();
+        let service = GitService::discover_at(path).unwrap();
+        let target = git(path, &["rev-parse", "HEAD~1"]);
+        let a = [PathBuf::from("a.txt")];
+
+        // A partial undo isn't a revert
+        std::fs::write(path.join("a.txt"), "one\ntwo!\n").unwrap();
+        git(path, &["add", "a.txt"]);
+        assert_eq!(service.find_reverted_commit(&a, 50).await.unwrap(), None);
+
+        // Undoing the change by hand is
+        std::fs::write(path.join("a.txt"), "one\n").unwrap();
+        git(path, &["add", "a.txt"]);
+        assert_eq!(
+            service.find_reverted_commit(&a, 50).await.unwrap(),
+            Some(target.clone())
+        );
+
+        // `git revert --no-commit` leaves REVERT_HEAD behind
+        git(path, &["reset", "-q", "--hard"]);
+        git(path, &["revert", "--no-commit", "HEAD~1"]);
+        assert_eq!(
+            service.find_reverted_commit(&[], 50).await.unwrap(),
+            Some(target)
+        );
+    }
+
+    #[tokio::test]
+    async fn merge_resolution_is_committed_with_both_parents() {
+        let dir = init_repo(&[("a.txt", "base\n", "first")]);
+        let path = dir.path();
+        let a = [PathBuf::from("a.txt")];
+        git(path, &["checkout", "-q", "-b", "feature"]);
+        std::fs::write(path.join("a.txt"), "theirs\n").unwrap();
+        git(path, &["commit", "-q", "-am", "feat: theirs"]);
+        let feature = git(path, &["rev-parse", "HEAD"]);
+        git(path, &["checkout", "-q", "-"]);
+        std::fs::write(path.join("a.txt"), "ours\n").unwrap();
+        git(path, &["commit", "-q", "-am", "fix: ours"]);
+        git(path, &["merge", "-q", "feature"]);
+
+        let service = GitService::discover_at(path).unwrap();
+        assert!(service.merge_in_progress());
+        assert_eq!(service.merge_heads(), vec![feature]);
+        assert!(matches!(
+            service.get_staged_changes(100, 70).await,
+            Err(Error::MergeConflicts)
+        ));
+
+        // Taking the incoming side leaves nothing to diff against it
+        std::fs::write(path.join("a.txt"), "theirs\n").unwrap();
+        git(path, &["add", "a.txt"]);
+        let vs_theirs = service
+            .get_resolution_changes("MERGE_HEAD", &a, 100)
+            .await
+            .unwrap();
+        assert!(vs_theirs.files.is_empty());
+
+        std::fs::write(path.join("a.txt"), "ours\ntheirs\n").unwrap();
+        git(path, &["add", "a.txt"]);
+        let (changes, _) = service.get_staged_changes(100, 70).await.unwrap();
+        assert_eq!(changes.files.len(), 1);
+        let vs_theirs = service
+            .get_resolution_changes("MERGE_HEAD", &a, 100)
+            .await
+            .unwrap();
+        assert!(vs_theirs.files[0].diff.contains("+ours"));
+
+        let (resolved, ours, theirs) = service.fetch_merge_file_contents(&a).await;
+        assert_eq!(resolved[&a[0]], "ours\ntheirs\n");
+        assert_eq!(ours[&a[0]], "ours\n");
+        assert_eq!(theirs[&a[0]], "theirs\n");
+
+        service.commit("merge: feature").await.unwrap();
+        let parents = git(path, &["rev-list", "--parents", "-1", "HEAD"]);
+        assert_eq!(parents.split_whitespace().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn snapshot_commits_hunks_separately() {
+        let original: String = (1..=30).map(|n| format!("line {n}\n")).collect();
+        let dir = init_repo(&[("a.txt", &original, "first")]);
+        let path = dir.path();
+        let edited = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 28\n", "line 28\nline 28.5\n");
+        std::fs::write(path.join("a.txt"), &edited).unwrap();
+        git(path, &["add", "a.txt"]);
+        let service = GitService::discover_at(path).unwrap();
+
+        let snapshot = service.snapshot_index().await.unwrap();
+        let diff = service.staged_file_diff(Path::new("a.txt")).await.unwrap();
+        assert_eq!(DiffHunk::parse_from_diff(&diff).len(), 2);
+
+        // Later hunk first, so the earlier one has to apply on top of it
+        for hunk in [1, 0] {
+            service
+                .commit_from_snapshot(
+                    &snapshot,
+                    &[],
+                    &[DiffHunk::select(&diff, &[hunk])],
+                    &format!("hunk {hunk}"),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            git(path, &["show", "HEAD~1", "--format=", "--stat"])
+                .lines()
+                .count(),
+            2
+        );
+        assert!(git(path, &["show", "HEAD~1"]).contains("+line 28.5"));
+        assert!(git(path, &["show", "HEAD"]).contains("+line two"));
+        assert_eq!(git(path, &["status", "--porcelain"]), "");
+    }
+
+    #[tokio::test]
+    async fn snapshot_commits_keep_unstaged_edits() {
+        let dir = init_repo(&[
+            ("a.txt", "a\n", "add a"),
+            ("b.txt", "b\n", "add b"),
+            ("c.txt", "c\n", "add c"),
+        ]);
+        let path = dir.path();
+        std::fs::create_dir(path.join("src")).unwrap();
+        std::fs::write(path.join("src/new.rs"), "fn new() {}\n").unwrap();
+        std::fs::write(path.join("a.txt"), "a staged\n").unwrap();
+        git(path, &["add", "a.txt", "src/new.rs"]);
+        git(path, &["rm", "-q", "b.txt"]);
+        git(path, &["mv", "c.txt", "d.txt"]);
+        // Unstaged edits on top of staged ones
+        std::fs::write(path.join("a.txt"), "a staged\na unstaged\n").unwrap();
+        std::fs::write(path.join("src/new.rs"), "fn newer() {}\n").unwrap();
+        let service = GitService::discover_at(path).unwrap();
+        let snapshot = service.snapshot_index().await.unwrap();
+
+        let groups: [(&[&str], &str); 2] = [
+            (&["a.txt", "src/new.rs"], "first"),
+            // The rename's old path goes with it
+            (&["b.txt", "d.txt", "c.txt"], "second"),
+        ];
+        for (paths, message) in groups {
+            let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+            service
+                .commit_from_snapshot(&snapshot, &paths, &[], message)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            git(path, &["show", "HEAD~1", "--format=", "--name-only"]),
+            "a.txt\nsrc/new.rs"
+        );
+        assert_eq!(git(path, &["show", "HEAD~1:a.txt"]), "a staged");
+        assert_eq!(
+            git(path, &["show", "HEAD", "--format=", "--name-status", "-M"]),
+            "D\tb.txt\nR100\tc.txt\td.txt"
+        );
+        // Index matches HEAD and the working tree keeps its unstaged edits
+        assert_eq!(git(path, &["diff", "--cached", "--name-only"]), "");
+        assert_eq!(git(path, &["diff", "--name-only"]), "a.txt\nsrc/new.rs");
+        assert_eq!(
+            std::fs::read_to_string(path.join("a.txt")).unwrap(),
+            "a staged\na unstaged\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn rewrite_messages_preserves_trees_and_descendants() {
+        let dir = init_repo(&[
+            ("a.txt", "a", "wip"),
+            ("b.txt", "b", "more wip"),
+            ("c.txt", "c", "feat: keep me"),
+        ]);
+        let path = dir.path();
+        let service = GitService::discover_at(path).unwrap();
+        let old_tree = git(path, &["rev-parse", "HEAD^{tree}"]);
+        let target = git(path, &["rev-parse", "HEAD~1"]);
+
+        let mut messages = HashMap::new();
+        messages.insert(target, "feat: add b.txt\n".to_string());
+        let new_head = service.rewrite_messages(&messages).await.unwrap();
+
+        assert_eq!(git(path, &["rev-parse", "HEAD"]), new_head);
+        assert_eq!(git(path, &["rev-parse", "HEAD^{tree}"]), old_tree);
+        assert_eq!(
+            git(path, &["log", "--format=%s"]),
+            "feat: keep me\nfeat: add b.txt\nwip"
+        );
+    }
+
+    #[tokio::test]
+    async fn signed_commits_reads_the_commit_header() {
+        let dir = init_repo(&[("a.txt", "a", "plain")]);
+        let path = dir.path();
+        let service = GitService::discover_at(path).unwrap();
+        let plain = git(path, &["rev-parse", "HEAD"]);
+
+        // A commit object with a (fake) signature header
+        let raw = git(path, &["cat-file", "commit", "HEAD"]);
+        let (header, body) = raw.split_once("\n\n").unwrap();
+        let object = format!(
+            "{header}\ngpgsig -----BEGIN PGP SIGNATURE-----\n \n -----END PGP SIGNATURE-----\n\n{body}\n"
+        );
+        let object_path = path.join("signed-commit");
+        std::fs::write(&object_path, object).unwrap();
+        let signed = git(
+            path,
+            &[
+                "hash-object",
+                "-t",
+                "commit",
+                "-w",
+                object_path.to_str().unwrap(),
+            ],
+        );
+
+        let found = service
+            .signed_commits(&[plain, signed.clone()])
+            .await
+            .unwrap();
+        assert_eq!(found, vec![signed]);
+    }
 }
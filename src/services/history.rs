@@ -9,6 +9,8 @@ use std::path::Path;
 use tokio::process::Command;
 use tracing::debug;
 
+use crate::error::{Error, Result};
+
 /// Minimum number of commits required for meaningful style learning.
 const MIN_COMMITS_FOR_ANALYSIS: usize = 5;
 
@@ -101,6 +103,59 @@ impl fmt::Display for HistoryContext {
     }
 }
 
+/// A commit read from `git log`, with its full message split into parts.
+#[derive(Debug, Clone)]
+pub struct CommitRecord {
+    pub sha: String,
+    pub subject: String,
+    /// Everything after the subject line (body and trailers), trimmed.
+    pub body: String,
+}
+
+impl CommitRecord {
+    /// Breaking change notes declared by this commit: every `BREAKING CHANGE:`
+    /// (or `BREAKING-CHANGE:`) footer, or the subject itself when it only uses
+    /// the `!` marker.
+    #[must_use]
+    pub fn breaking_notes(&self) -> Vec<String> {
+        let mut notes: Vec<String> = Vec::new();
+        let mut current: Option<String> = None;
+
+        for line in self.body.lines() {
+            let footer = line
+                .strip_prefix("BREAKING CHANGE:")
+                .or_else(|| line.strip_prefix("BREAKING-CHANGE:"));
+            if let Some(text) = footer {
+                if let Some(done) = current.take() {
+                    notes.push(done);
+                }
+                current = Some(text.trim().to_string());
+            } else if let Some(ref mut note) = current {
+                // Continuation lines are indented; anything else ends the footer
+                if line.starts_with(' ') && !line.trim().is_empty() {
+                    note.push(' ');
+                    note.push_str(line.trim());
+                } else {
+                    notes.push(current.take().unwrap_or_default());
+                }
+            }
+        }
+        if let Some(done) = current {
+            notes.push(done);
+        }
+        notes.retain(|n| !n.is_empty());
+
+        if notes.is_empty()
+            && let Some(parsed) = HistoryService::parse_conventional(&self.subject)
+            && parsed.breaking
+        {
+            notes.push(parsed.subject_text);
+        }
+
+        notes
+    }
+}
+
 pub struct HistoryService;
 
 impl HistoryService {
@@ -148,6 +203,50 @@ impl HistoryService {
         Some(subjects)
     }
 
+    /// Fetch the non-merge commits in `range` (e.g., `main..HEAD`), oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Git` if `git log` fails (unknown revision, not a repo).
+    pub async fn fetch_commits(work_dir: &Path, range: &str) -> Result<Vec<CommitRecord>> {
+        let output: std::process::Output = Command::new("git")
+            .args([
+                "log",
+                "--reverse",
+                "--no-merges",
+                "--format=%H%x1f%B%x1e",
+                range,
+            ])
+            .current_dir(work_dir)
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Git(stderr.to_string()));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(Self::parse_log_records(&stdout))
+    }
+
+    /// Parse `%H%x1f%B%x1e`-formatted `git log` output into records.
+    fn parse_log_records(output: &str) -> Vec<CommitRecord> {
+        output
+            .split('\x1e')
+            .filter_map(|record| {
+                let (sha, message) = record.trim_start_matches('\n').split_once('\x1f')?;
+                let (subject, body) = message.split_once('\n').unwrap_or((message, ""));
+                Some(CommitRecord {
+                    sha: sha.trim().to_string(),
+                    subject: subject.trim().to_string(),
+                    body: body.trim().to_string(),
+                })
+            })
+            .filter(|r| !r.sha.is_empty())
+            .collect()
+    }
+
     /// Parse and analyze a list of commit subjects to extract patterns.
     ///
     /// Public for testing; this is a pure function that does not require git.
@@ -194,11 +293,7 @@ impl HistoryService {
         }
 
         let total = subjects.len();
-        let avg_subject_length = if total > 0 {
-            total_subject_len / total
-        } else {
-            0
-        };
+        let avg_subject_length = total_subject_len.checked_div(total).unwrap_or(0);
 
         // Lowercase threshold: 80%+ of commits use lowercase
         let uses_lowercase = total > 0 && (lowercase_count as f32 / total as f32) >= 0.8;
@@ -231,12 +326,14 @@ impl HistoryService {
     /// Parse a conventional commit subject line into its components.
     ///
     /// Format: `type(scope): subject` or `type: subject` or `type!: subject`
-    fn parse_conventional(subject: &str) -> Option<ParsedConventional> {
+    #[must_use]
+    pub fn parse_conventional(subject: &str) -> Option<ParsedConventional> {
         // Find the colon separator
         let colon_pos = subject.find(':')?;
         let prefix = &subject[..colon_pos];
 
         // Strip trailing `!` (breaking change indicator)
+        let breaking = prefix.ends_with('!');
         let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
 
         // Extract type and optional scope
@@ -265,14 +362,20 @@ impl HistoryService {
             commit_type: type_lower,
             scope: scope.map(|s| s.to_string()),
             subject_text: subject_text.to_string(),
+            breaking,
         })
     }
 }
 
-struct ParsedConventional {
-    commit_type: String,
-    scope: Option<String>,
-    subject_text: String,
+/// Components of a conventional commit subject line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedConventional {
+    /// Lowercased commit type (e.g., `feat`).
+    pub commit_type: String,
+    pub scope: Option<String>,
+    pub subject_text: String,
+    /// Whether the type carries the `!` breaking change marker.
+    pub breaking: bool,
 }
 
 fn is_conventional_type(s: &str) -> bool {
@@ -302,6 +405,7 @@ mod tests {
         assert_eq!(parsed.commit_type, "feat");
         assert!(parsed.scope.is_none());
         assert_eq!(parsed.subject_text, "add new feature");
+        assert!(!parsed.breaking);
     }
 
     #[test]
@@ -320,6 +424,7 @@ mod tests {
         assert_eq!(parsed.commit_type, "refactor");
         assert_eq!(parsed.scope.as_deref(), Some("api"));
         assert_eq!(parsed.subject_text, "remove deprecated endpoint");
+        assert!(parsed.breaking);
     }
 
     #[test]
@@ -334,6 +439,18 @@ mod tests {
         assert!(HistoryService::parse_conventional("yolo: something").is_none());
     }
 
+    #[test]
+    fn parse_log_records_splits_subject_and_body() {
+        let output = "abc\x1ffeat: one\n\nbody line\n\x1e\ndef\x1ffix: two\n\x1e\n";
+        let records = HistoryService::parse_log_records(output);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].sha, "abc");
+        assert_eq!(records[0].subject, "feat: one");
+        assert_eq!(records[0].body, "body line");
+        assert_eq!(records[1].subject, "fix: two");
+        assert!(records[1].body.is_empty());
+    }
+
     #[test]
     fn analyze_subjects_basic() {
         let subjects = vec![
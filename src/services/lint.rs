@@ -0,0 +1,109 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use crate::domain::{CommitType, PromptContext};
+use crate::error::Error;
+use crate::services::history::HistoryService;
+use crate::services::output::parse_message;
+use crate::services::sanitizer::{CommitSanitizer, CommitValidator};
+
+/// Git drops this line and everything below it (`commit --verbose`).
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// Messages written by git or by autosquash tooling; never linted.
+const EXEMPT_PREFIXES: &[&str] = &["Merge ", "Revert \"", "fixup! ", "squash! ", "amend! "];
+
+/// Checks human-written commit messages against Conventional Commits and,
+/// when available, the evidence computed from the commit's diff.
+pub struct CommitLinter;
+
+impl CommitLinter {
+    /// Reduce a commit message file to what git would commit with the
+    /// default `--cleanup=strip`: no `#` comments, nothing below the scissors
+    /// line, no leading or trailing blank lines.
+    #[must_use]
+    pub fn clean_message(raw: &str) -> String {
+        raw.lines()
+            .take_while(|line| *line != SCISSORS)
+            .filter(|line| !line.starts_with('#'))
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string()
+    }
+
+    /// Whether the message was generated by git (merges, reverts) or is an
+    /// autosquash marker that will be rewritten later.
+    #[must_use]
+    pub fn is_exempt(message: &str) -> bool {
+        EXEMPT_PREFIXES.iter().any(|p| message.starts_with(p))
+    }
+
+    /// Lint a cleaned message. Format rules always run; evidence rules run
+    /// when `context` is given. Returns human-readable issues, empty if the
+    /// message passes.
+    #[must_use]
+    pub fn lint(message: &str, context: Option<&PromptContext>) -> Vec<String> {
+        if message.trim().is_empty() {
+            return vec!["Message is empty.".to_string()];
+        }
+
+        match CommitSanitizer::validate_conventional(message) {
+            Ok(()) => {}
+            Err(Error::InvalidCommitMessage(reason)) => return vec![reason],
+            Err(e) => return vec![e.to_string()],
+        }
+
+        let header = message.lines().next().unwrap_or_default();
+        let (Some(parsed), Some(mut commit)) = (
+            HistoryService::parse_conventional(header),
+            parse_message(message),
+        ) else {
+            return vec![format!(
+                "Header must be \"type(scope): subject\". Got: '{header}'"
+            )];
+        };
+
+        let mut issues = Vec::new();
+        if commit.subject.is_empty() {
+            issues.push("Subject is empty.".to_string());
+        }
+        if message.lines().nth(1).is_some_and(|l| !l.trim().is_empty()) {
+            issues.push("Separate the header from the body with a blank line.".to_string());
+        }
+
+        if let Some(context) = context {
+            // `type!:` counts as breaking even without a footer
+            if parsed.breaking && commit.breaking_change.is_none() {
+                commit.breaking_change = Some(commit.subject.clone());
+            }
+            // A docs- or test-only diff can't be a feature or a fix
+            let declared = CommitType::parse(&parsed.commit_type);
+            if matches!(declared, Some(CommitType::Feat | CommitType::Fix))
+                && matches!(context.suggested_type, CommitType::Docs | CommitType::Test)
+            {
+                issues.push(format!(
+                    "Type is \"{}\" but the diff only changes {}. Use \"{}\" instead.",
+                    parsed.commit_type,
+                    match context.suggested_type {
+                        CommitType::Docs => "documentation",
+                        _ => "tests",
+                    },
+                    context.suggested_type.as_str(),
+                ));
+            }
+
+            issues.extend(CommitValidator::validate(
+                &commit,
+                context.has_bug_evidence,
+                context.is_mechanical,
+                context.public_api_removed_count,
+                context.is_dependency_only,
+            ));
+        }
+
+        issues
+    }
+}
@@ -2,41 +2,71 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
-use std::time::Duration;
+use std::time::Instant;
 
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio::sync::mpsc;
 use tokio_stream::StreamExt;
 use tokio_util::sync::CancellationToken;
 
 use secrecy::{ExposeSecret, SecretString};
 
-use crate::config::Config;
+use crate::config::{Auth, ResolvedProfile, RetryConfig};
 use crate::error::{Error, Result};
+use crate::services::usage::{TokenUsage, UsageLog, UsageMeter};
 
 use super::MAX_RESPONSE_BYTES;
+use super::http;
+use super::retry::{self, Failure};
 
 const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
 const API_VERSION: &str = "2023-06-01";
 
+/// Tool the model is made to call when output is schema-constrained; its
+/// input is the commit JSON.
+const COMMIT_TOOL: &str = "commit_message";
+
 pub struct AnthropicProvider {
     client: Client,
     base_url: String,
     model: String,
     api_key: SecretString,
+    auth: Auth,
     temperature: f32,
     max_tokens: u32,
+    structured_output: bool,
+    retry: RetryConfig,
+    meter: UsageMeter,
 }
 
 #[derive(Serialize)]
-struct MessagesRequest {
+struct MessagesRequest<'a> {
     model: String,
     system: String,
     messages: Vec<Message>,
     temperature: f32,
     max_tokens: u32,
     stream: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Tool<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<ToolChoice>,
+}
+
+#[derive(Serialize)]
+struct Tool<'a> {
+    name: &'static str,
+    description: &'static str,
+    input_schema: &'a Value,
+}
+
+#[derive(Serialize)]
+struct ToolChoice {
+    #[serde(rename = "type")]
+    choice_type: &'static str,
+    name: &'static str,
 }
 
 #[derive(Serialize)]
@@ -50,42 +80,80 @@ struct StreamEvent {
     #[serde(rename = "type")]
     event_type: String,
     delta: Option<ContentDelta>,
+    /// `message_start` carries the input token count
+    message: Option<MessageStart>,
+    /// `message_delta` carries the cumulative output token count
+    usage: Option<EventUsage>,
+}
+
+#[derive(Deserialize)]
+struct MessageStart {
+    usage: Option<EventUsage>,
+}
+
+#[derive(Deserialize)]
+struct EventUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
 }
 
 #[derive(Deserialize)]
 struct ContentDelta {
     text: Option<String>,
+    /// Tool input, streamed as JSON fragments
+    partial_json: Option<String>,
+}
+
+impl ContentDelta {
+    fn content(&self) -> Option<&String> {
+        self.text.as_ref().or(self.partial_json.as_ref())
+    }
 }
 
 impl AnthropicProvider {
-    pub fn new(config: &Config) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .build()
-            .map_err(|e| Error::Provider {
-                provider: "anthropic".into(),
-                message: format!("failed to build HTTP client: {e}"),
-            })?;
+    pub fn new(profile: &ResolvedProfile) -> Result<Self> {
+        let client = http::build_client("anthropic", profile)?;
 
         Ok(Self {
             client,
-            base_url: config
-                .anthropic_base_url
-                .clone()
-                .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+            base_url: profile
+                .base_url
+                .as_deref()
+                .unwrap_or(DEFAULT_BASE_URL)
                 .trim_end_matches('/')
                 .to_string(),
-            model: config.model.clone(),
-            api_key: config.api_key.clone().unwrap_or_default(),
-            temperature: config.temperature,
-            max_tokens: config.num_predict,
+            model: profile.model.clone(),
+            api_key: profile.api_key.clone().unwrap_or_default(),
+            auth: profile.auth.clone(),
+            temperature: profile.temperature,
+            max_tokens: profile.num_predict,
+            structured_output: profile.structured_output,
+            retry: profile.retry,
+            meter: UsageMeter::new(profile),
         })
     }
 
+    /// Attach the key per the profile's auth style (`x-api-key` by default).
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        let key = self.api_key.expose_secret();
+        match self.auth {
+            _ if key.is_empty() => request,
+            Auth::Bearer => request.header("Authorization", format!("Bearer {}", key)),
+            Auth::Header(ref name) => request.header(name.as_str(), key),
+            Auth::None => request,
+        }
+    }
+
     pub async fn verify_connection(&self) -> Result<()> {
         // Anthropic doesn't have a lightweight endpoint for verification,
         // so we just validate that the key looks plausible
-        if self.api_key.expose_secret().is_empty() {
+        if self.auth != Auth::None && self.api_key.expose_secret().is_empty() {
             return Err(Error::Provider {
                 provider: "anthropic".into(),
                 message: "API key not configured".into(),
@@ -94,19 +162,41 @@ impl AnthropicProvider {
         Ok(())
     }
 
+    /// Check that the endpoint is reachable through the configured proxy
+    /// and TLS settings.
+    pub async fn probe(&self) -> Result<()> {
+        http::probe(&self.client, "anthropic", &self.base_url).await
+    }
+
     pub async fn generate(
         &self,
         prompt: &str,
         system_prompt: &str,
+        schema: Option<&Value>,
         token_tx: mpsc::Sender<String>,
         cancel: CancellationToken,
     ) -> Result<String> {
+        retry::with_retry("anthropic", &self.retry, &cancel, || {
+            self.attempt(prompt, system_prompt, schema, &token_tx, &cancel)
+        })
+        .await
+    }
+
+    /// One request, streamed to completion.
+    async fn attempt(
+        &self,
+        prompt: &str,
+        system_prompt: &str,
+        schema: Option<&Value>,
+        token_tx: &mpsc::Sender<String>,
+        cancel: &CancellationToken,
+    ) -> std::result::Result<String, Failure> {
+        let start = Instant::now();
         let url = format!("{}/messages", self.base_url);
+        let schema = schema.filter(|_| self.structured_output);
 
         let response = self
-            .client
-            .post(&url)
-            .header("x-api-key", self.api_key.expose_secret())
+            .authorize(self.client.post(&url))
             .header("anthropic-version", API_VERSION)
             .header("content-type", "application/json")
             .json(&MessagesRequest {
@@ -119,50 +209,80 @@ impl AnthropicProvider {
                 temperature: self.temperature,
                 max_tokens: self.max_tokens,
                 stream: true,
+                tools: schema
+                    .map(|input_schema| Tool {
+                        name: COMMIT_TOOL,
+                        description: "Record the commit message",
+                        input_schema,
+                    })
+                    .into_iter()
+                    .collect(),
+                tool_choice: schema.map(|_| ToolChoice {
+                    choice_type: "tool",
+                    name: COMMIT_TOOL,
+                }),
             })
             .send()
             .await
             .map_err(|e| {
-                if e.is_timeout() {
-                    Error::Provider {
+                if let Some(message) = http::tls_failure(&e) {
+                    Error::Tls {
+                        provider: "anthropic".into(),
+                        message,
+                    }
+                    .into()
+                } else if e.is_timeout() {
+                    Error::ProviderUnreachable {
                         provider: "anthropic".into(),
                         message: "request timed out".into(),
                     }
-                } else {
-                    Error::Provider {
+                    .into()
+                } else if e.is_connect() {
+                    Error::ProviderUnreachable {
                         provider: "anthropic".into(),
                         message: e.without_url().to_string(),
                     }
+                    .into()
+                } else {
+                    Failure::transient(Error::Provider {
+                        provider: "anthropic".into(),
+                        message: e.without_url().to_string(),
+                    })
                 }
             })?;
+        let mut metered = self.meter.attempt(start);
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response
-                .text()
-                .await
-                .unwrap_or_else(|e| format!("(failed to read body: {e})"));
-            return Err(Error::Provider {
-                provider: "anthropic".into(),
-                message: format!("HTTP {status}: {body}"),
-            });
+            return Err(Failure::from_response("anthropic", response).await);
         }
 
         let mut stream = response.bytes_stream();
         let mut full_response = String::new();
+        // Whether any of the response reached the printer
+        let mut shown = false;
         let mut line_buffer = String::new();
 
         loop {
             tokio::select! {
                 _ = cancel.cancelled() => {
-                    return Err(Error::Cancelled);
+                    return Err(Error::Cancelled.into());
                 }
                 chunk = stream.next() => {
                     let Some(chunk) = chunk else { break };
 
-                    let chunk = chunk.map_err(|e| Error::Provider {
-                        provider: "anthropic".into(),
-                        message: e.without_url().to_string(),
+                    let chunk = chunk.map_err(|e| {
+                        let error = if e.is_timeout() {
+                            Error::ProviderUnreachable {
+                                provider: "anthropic".into(),
+                                message: "response stream timed out".into(),
+                            }
+                        } else {
+                            Error::Provider {
+                                provider: "anthropic".into(),
+                                message: e.without_url().to_string(),
+                            }
+                        };
+                        Failure::interrupted(error, shown)
                     })?;
 
                     line_buffer.push_str(&String::from_utf8_lossy(&chunk));
@@ -171,7 +291,8 @@ impl AnthropicProvider {
                         return Err(Error::Provider {
                             provider: "anthropic".into(),
                             message: "line buffer exceeded 1 MB limit".into(),
-                        });
+                        }
+                        .into());
                     }
 
                     while let Some(newline_pos) = line_buffer.find('\n') {
@@ -193,18 +314,35 @@ impl AnthropicProvider {
                             match event.event_type.as_str() {
                                 "content_block_delta" => {
                                     if let Some(delta) = &event.delta
-                                        && let Some(text) = &delta.text
+                                        && let Some(text) = delta.content()
                                     {
-                                        let _ = token_tx.send(text.clone()).await;
+                                        shown |= token_tx.send(text.clone()).await.is_ok();
                                         full_response.push_str(text);
                                     }
                                     if full_response.len() > MAX_RESPONSE_BYTES {
                                         return Err(Error::Provider {
                                             provider: "anthropic".into(),
                                             message: "response exceeded 1 MB limit".into(),
+                                        }
+                                        .into());
+                                    }
+                                }
+                                "message_start" => {
+                                    if let Some(u) = event.message.and_then(|m| m.usage) {
+                                        metered.tokens = Some(TokenUsage {
+                                            input_tokens: u.input_tokens
+                                                + u.cache_creation_input_tokens
+                                                + u.cache_read_input_tokens,
+                                            output_tokens: u.output_tokens,
                                         });
                                     }
                                 }
+                                "message_delta" => {
+                                    if let Some(u) = event.usage {
+                                        metered.tokens.get_or_insert_default().output_tokens =
+                                            u.output_tokens;
+                                    }
+                                }
                                 "message_stop" => {
                                     return Ok(full_response.trim().to_string());
                                 }
@@ -225,7 +363,7 @@ impl AnthropicProvider {
                 && let Ok(event) = serde_json::from_str::<StreamEvent>(data)
                 && event.event_type == "content_block_delta"
                 && let Some(delta) = &event.delta
-                && let Some(text) = &delta.text
+                && let Some(text) = delta.content()
             {
                 full_response.push_str(text);
             }
@@ -237,4 +375,15 @@ impl AnthropicProvider {
     pub fn name(&self) -> &str {
         "anthropic"
     }
+
+    /// Record requests in `log` instead of this provider's own.
+    #[must_use]
+    pub fn with_usage_log(mut self, log: UsageLog) -> Self {
+        self.meter.attach(log);
+        self
+    }
+
+    pub(crate) fn is_cloud(&self) -> bool {
+        self.meter.is_cloud()
+    }
 }
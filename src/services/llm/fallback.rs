@@ -0,0 +1,184 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::config::ResolvedProfile;
+use crate::error::{Error, Result};
+use crate::services::usage::UsageLog;
+
+use super::LlmBackend;
+
+/// One provider in a fallback chain.
+pub struct Hop {
+    pub profile: ResolvedProfile,
+    pub backend: LlmBackend,
+}
+
+/// Ordered list of providers, walked on connection errors, timeouts, and
+/// missing models (see [`Error::is_failover`]).
+///
+/// The first hop that verifies becomes active and stays active for the rest
+/// of the run. Once a diff with user-approved secrets is in play, only the
+/// first hop (the one the user approved) and local endpoints are eligible.
+pub struct FallbackChain {
+    hops: Vec<Hop>,
+    active: AtomicUsize,
+    sensitive: AtomicBool,
+}
+
+impl FallbackChain {
+    #[must_use]
+    pub fn new(hops: Vec<Hop>) -> Self {
+        Self {
+            hops,
+            active: AtomicUsize::new(0),
+            sensitive: AtomicBool::new(false),
+        }
+    }
+
+    /// Record every hop's requests in `log`.
+    #[must_use]
+    pub fn with_usage_log(self, log: UsageLog) -> Self {
+        let hops = self
+            .hops
+            .into_iter()
+            .map(|hop| Hop {
+                backend: hop.backend.with_usage_log(log.clone()),
+                profile: hop.profile,
+            })
+            .collect();
+        Self { hops, ..self }
+    }
+
+    /// The currently active hop.
+    #[must_use]
+    pub fn active(&self) -> &Hop {
+        &self.hops[self.active.load(Ordering::Relaxed)]
+    }
+
+    /// Whether the active hop is a fallback rather than the primary provider.
+    #[must_use]
+    pub fn is_fallback(&self) -> bool {
+        self.active.load(Ordering::Relaxed) > 0
+    }
+
+    /// Restrict failover to local endpoints from now on.
+    pub fn mark_sensitive(&self) {
+        self.sensitive.store(true, Ordering::Relaxed);
+    }
+
+    fn eligible(&self, index: usize) -> bool {
+        index == 0 || !self.sensitive.load(Ordering::Relaxed) || self.hops[index].profile.is_local()
+    }
+
+    /// Verify hops in order, starting at the active one, until one succeeds.
+    pub async fn verify(&self) -> Result<()> {
+        self.verify_from(self.active.load(Ordering::Relaxed)).await
+    }
+
+    async fn verify_from(&self, start: usize) -> Result<()> {
+        // Report the first failure: the primary's error is the most actionable
+        let mut first_err: Option<Error> = None;
+
+        for (index, hop) in self.hops.iter().enumerate().skip(start) {
+            if !self.eligible(index) {
+                warn!(
+                    provider = %hop.profile.name,
+                    "skipping remote fallback: diff contains potential secrets"
+                );
+                continue;
+            }
+
+            debug!(provider = %hop.profile.name, model = %hop.profile.model, "verifying provider");
+            match Box::pin(hop.backend.verify()).await {
+                Ok(()) => {
+                    self.active.store(index, Ordering::Relaxed);
+                    if index > 0 {
+                        info!(
+                            provider = %hop.profile.name,
+                            model = %hop.profile.model,
+                            "using fallback provider"
+                        );
+                    }
+                    return Ok(());
+                }
+                Err(e) if e.is_failover() => {
+                    warn!(provider = %hop.profile.name, error = %e, "provider unavailable");
+                    first_err.get_or_insert(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(first_err.unwrap_or_else(|| Error::Provider {
+            provider: self.hops[start.min(self.hops.len() - 1)]
+                .profile
+                .name
+                .clone(),
+            message: "no eligible provider in fallback chain".into(),
+        }))
+    }
+
+    /// Generate with the active hop, failing over to the next verified hop if
+    /// it becomes unreachable before any of its response was shown.
+    pub async fn generate(
+        &self,
+        prompt: &str,
+        system_prompt: &str,
+        schema: Option<&Value>,
+        token_tx: mpsc::Sender<String>,
+        cancel: CancellationToken,
+    ) -> Result<String> {
+        loop {
+            let index = self.active.load(Ordering::Relaxed);
+            let hop = &self.hops[index];
+            if !self.eligible(index) {
+                return Err(Error::Provider {
+                    provider: hop.profile.name.clone(),
+                    message: "refusing to send a diff with potential secrets to a remote fallback"
+                        .into(),
+                });
+            }
+
+            // Relay tokens to note whether any reached the printer. The next
+            // hop would stream its message from the start into the same one.
+            let (hop_tx, mut hop_rx) = mpsc::channel::<String>(token_tx.max_capacity());
+            let relay = async {
+                let mut shown = false;
+                while let Some(token) = hop_rx.recv().await {
+                    shown |= !token.is_empty() && token_tx.send(token).await.is_ok();
+                }
+                shown
+            };
+            let (result, shown) = tokio::join!(
+                Box::pin(hop.backend.generate(
+                    prompt,
+                    system_prompt,
+                    schema,
+                    hop_tx,
+                    cancel.clone(),
+                )),
+                relay
+            );
+
+            match result {
+                Err(e) if e.is_failover() && !shown && index + 1 < self.hops.len() => {
+                    warn!(provider = %hop.profile.name, error = %e, "provider failed, trying next");
+                    // The next hop that verifies becomes active; give up with
+                    // the original error if none does
+                    if self.verify_from(index + 1).await.is_err() {
+                        return Err(e);
+                    }
+                }
+                other => return other,
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::error::Error as _;
+use std::path::Path;
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::tls::{Certificate, Identity};
+use reqwest::{Client, NoProxy, Proxy};
+use secrecy::ExposeSecret;
+
+use crate::config::{Config, ResolvedProfile};
+use crate::error::{Error, Result};
+
+/// Fragments of rustls/webpki error messages that point at TLS rather than
+/// plain connectivity.
+const TLS_MARKERS: &[&str] = &[
+    "certificate",
+    "tls",
+    "handshake",
+    "unknownissuer",
+    "corrupt message",
+    "peer sent no certificates",
+];
+
+/// Build a provider's HTTP client from the profile's timeout and `[network]`
+/// settings: proxy, extra root certificates and client certificate, plus the
+/// profile's extra headers.
+pub(crate) fn build_client(provider: &str, profile: &ResolvedProfile) -> Result<Client> {
+    let network = &profile.network;
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(profile.timeout_secs))
+        .default_headers(extra_headers(profile)?);
+
+    if let Some(ref url) = network.proxy {
+        let proxy = Proxy::all(url)
+            .map_err(|e| Error::Config(format!("network.proxy is not a valid URL: {e}")))?
+            .no_proxy(network.no_proxy.as_deref().and_then(NoProxy::from_string));
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(ref path) = network.ca_bundle_path {
+        let pem = read_pem("network.ca_bundle_path", path)?;
+        let certs = Certificate::from_pem_bundle(&pem).map_err(|e| {
+            Error::Config(format!(
+                "network.ca_bundle_path: no valid certificates in {}: {e}",
+                path.display()
+            ))
+        })?;
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    if let Some(ref cert_path) = network.client_cert_path {
+        // The key may sit in the certificate file or in its own file
+        let mut pem = read_pem("network.client_cert_path", cert_path)?;
+        if let Some(ref key_path) = network.client_key_path {
+            pem.push(b'\n');
+            pem.extend(read_pem("network.client_key_path", key_path)?);
+        }
+        let identity = Identity::from_pem(&pem).map_err(|e| {
+            Error::Config(format!(
+                "network.client_cert_path: invalid client certificate or key: {e}"
+            ))
+        })?;
+        builder = builder.identity(identity);
+    }
+
+    builder.build().map_err(|e| Error::Provider {
+        provider: provider.into(),
+        message: format!("failed to build HTTP client: {e}"),
+    })
+}
+
+/// Resolve the profile's `headers` table. Values are marked sensitive so
+/// they stay out of debug output.
+fn extra_headers(profile: &ResolvedProfile) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for (name, spec) in &profile.headers {
+        let key = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| Error::Config(format!("'{name}' is not a valid header name")))?;
+        let resolved = Config::resolve_header_value(name, spec)?;
+        let mut value = HeaderValue::from_str(resolved.expose_secret()).map_err(|_| {
+            Error::Config(format!("header {name}: value is not a valid header value"))
+        })?;
+        value.set_sensitive(true);
+        headers.insert(key, value);
+    }
+    Ok(headers)
+}
+
+fn read_pem(key: &str, path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path)
+        .map_err(|e| Error::Config(format!("{key}: cannot read {}: {e}", path.display())))
+}
+
+/// The TLS failure behind a request error, if that's what it is: an unknown
+/// issuer, an expired certificate, a rejected client certificate.
+pub(crate) fn tls_failure(e: &reqwest::Error) -> Option<String> {
+    let mut source = e.source();
+    while let Some(err) = source {
+        let message = err.to_string();
+        let lower = message.to_lowercase();
+        if TLS_MARKERS.iter().any(|m| lower.contains(m)) {
+            return Some(message);
+        }
+        source = err.source();
+    }
+    None
+}
+
+/// Send a bare request to `url`. Any HTTP response, even an error status,
+/// shows the proxy and TLS setup work.
+pub(crate) async fn probe(client: &Client, provider: &str, url: &str) -> Result<()> {
+    match client.get(url).send().await {
+        Ok(_) => Ok(()),
+        Err(e) => Err(if let Some(message) = tls_failure(&e) {
+            Error::Tls {
+                provider: provider.into(),
+                message,
+            }
+        } else if e.is_connect() || e.is_timeout() {
+            Error::ProviderUnreachable {
+                provider: provider.into(),
+                message: source_chain(&e),
+            }
+        } else {
+            Error::Provider {
+                provider: provider.into(),
+                message: e.without_url().to_string(),
+            }
+        }),
+    }
+}
+
+/// The innermost error message, which names the actual cause (refused, DNS,
+/// proxy) rather than reqwest's generic "error sending request".
+fn source_chain(e: &reqwest::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(err) = source {
+        message = err.to_string();
+        source = err.source();
+    }
+    message
+}
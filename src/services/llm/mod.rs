@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
+use serde_json::Value;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
@@ -50,32 +51,54 @@ Respond with ONLY the JSON object as specified in the user prompt.
 "#;
 
 pub mod anthropic;
+pub mod fallback;
+mod http;
 pub mod ollama;
 pub mod openai;
+mod retry;
 
-use crate::config::{Config, Provider};
+use crate::config::{Config, Provider, ResolvedProfile};
 use crate::error::Result;
+use crate::services::usage::UsageLog;
 
 /// Enum dispatch for LLM providers — avoids async-trait / dyn overhead.
 pub enum LlmBackend {
     Ollama(ollama::OllamaProvider),
     OpenAi(openai::OpenAiProvider),
     Anthropic(anthropic::AnthropicProvider),
+    /// Ordered providers walked on connection errors, timeouts, and missing models
+    Fallback(fallback::FallbackChain),
 }
 
 impl LlmBackend {
-    /// Generate with streaming tokens and cancellation support
+    /// Generate with streaming tokens and cancellation support. With a
+    /// `schema`, output is constrained to it through the provider's native
+    /// structured output, unless the profile disables that.
     pub async fn generate(
         &self,
         prompt: &str,
         system_prompt: &str,
+        schema: Option<&Value>,
         token_tx: mpsc::Sender<String>,
         cancel: CancellationToken,
     ) -> Result<String> {
         match self {
-            Self::Ollama(p) => p.generate(prompt, system_prompt, token_tx, cancel).await,
-            Self::OpenAi(p) => p.generate(prompt, system_prompt, token_tx, cancel).await,
-            Self::Anthropic(p) => p.generate(prompt, system_prompt, token_tx, cancel).await,
+            Self::Ollama(p) => {
+                p.generate(prompt, system_prompt, schema, token_tx, cancel)
+                    .await
+            }
+            Self::OpenAi(p) => {
+                p.generate(prompt, system_prompt, schema, token_tx, cancel)
+                    .await
+            }
+            Self::Anthropic(p) => {
+                p.generate(prompt, system_prompt, schema, token_tx, cancel)
+                    .await
+            }
+            Self::Fallback(c) => {
+                c.generate(prompt, system_prompt, schema, token_tx, cancel)
+                    .await
+            }
         }
     }
 
@@ -84,6 +107,7 @@ impl LlmBackend {
             Self::Ollama(p) => p.name(),
             Self::OpenAi(p) => p.name(),
             Self::Anthropic(p) => p.name(),
+            Self::Fallback(c) => &c.active().profile.name,
         }
     }
 
@@ -93,16 +117,93 @@ impl LlmBackend {
             Self::Ollama(p) => p.verify_model().await,
             Self::OpenAi(p) => p.verify_connection().await,
             Self::Anthropic(p) => p.verify_connection().await,
+            Self::Fallback(c) => c.verify().await,
+        }
+    }
+
+    /// Connect to the endpoint without generating, to diagnose proxy and TLS
+    /// problems. A fallback chain probes its active provider.
+    pub async fn probe(&self) -> Result<()> {
+        match self {
+            Self::Ollama(p) => p.probe().await,
+            Self::OpenAi(p) => p.probe().await,
+            Self::Anthropic(p) => p.probe().await,
+            Self::Fallback(c) => Box::pin(c.active().backend.probe()).await,
+        }
+    }
+
+    /// `name (model)` of the provider serving requests, if a fallback took over
+    /// from the primary.
+    #[must_use]
+    pub fn fallback_label(&self) -> Option<String> {
+        match self {
+            Self::Fallback(c) if c.is_fallback() => {
+                let profile = &c.active().profile;
+                Some(format!("{} ({})", profile.name, profile.model))
+            }
+            _ => None,
+        }
+    }
+
+    /// Record completed requests in `log`, shared with the caller.
+    #[must_use]
+    pub fn with_usage_log(self, log: UsageLog) -> Self {
+        match self {
+            Self::Ollama(p) => Self::Ollama(p.with_usage_log(log)),
+            Self::OpenAi(p) => Self::OpenAi(p.with_usage_log(log)),
+            Self::Anthropic(p) => Self::Anthropic(p.with_usage_log(log)),
+            Self::Fallback(c) => Self::Fallback(c.with_usage_log(log)),
+        }
+    }
+
+    /// Whether requests go to a billed endpoint (the active hop, for a
+    /// fallback chain).
+    #[must_use]
+    pub fn is_cloud(&self) -> bool {
+        match self {
+            Self::Ollama(_) => false,
+            Self::OpenAi(p) => p.is_cloud(),
+            Self::Anthropic(p) => p.is_cloud(),
+            Self::Fallback(c) => c.active().profile.is_cloud(),
+        }
+    }
+
+    /// Keep a diff with user-approved secrets away from remote fallbacks.
+    /// No-op without a fallback chain.
+    pub fn mark_sensitive(&self) {
+        if let Self::Fallback(c) = self {
+            c.mark_sensitive();
         }
     }
 }
 
+/// Build the backend for the active provider profile, wrapped in a fallback
+/// chain when `fallback` profiles are configured.
 pub fn create_provider(config: &Config) -> Result<LlmBackend> {
-    match config.provider {
-        Provider::Ollama => Ok(LlmBackend::Ollama(ollama::OllamaProvider::new(config)?)),
-        Provider::OpenAI => Ok(LlmBackend::OpenAi(openai::OpenAiProvider::new(config)?)),
+    if config.fallback.is_empty() {
+        return create_backend(&config.active_profile());
+    }
+
+    let hops = config
+        .provider_chain()
+        .into_iter()
+        .map(|profile| {
+            Ok(fallback::Hop {
+                backend: create_backend(&profile)?,
+                profile,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(LlmBackend::Fallback(fallback::FallbackChain::new(hops)))
+}
+
+/// Build a single-provider backend from resolved profile settings.
+pub fn create_backend(profile: &ResolvedProfile) -> Result<LlmBackend> {
+    match profile.kind {
+        Provider::Ollama => Ok(LlmBackend::Ollama(ollama::OllamaProvider::new(profile)?)),
+        Provider::OpenAI => Ok(LlmBackend::OpenAi(openai::OpenAiProvider::new(profile)?)),
         Provider::Anthropic => Ok(LlmBackend::Anthropic(anthropic::AnthropicProvider::new(
-            config,
+            profile,
         )?)),
     }
 }
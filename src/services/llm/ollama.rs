@@ -2,18 +2,24 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
-use std::time::Duration;
+use std::time::Instant;
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio::sync::mpsc;
 use tokio_stream::StreamExt;
 use tokio_util::sync::CancellationToken;
 
-use crate::config::Config;
+use crate::config::{ResolvedProfile, RetryConfig};
 use crate::error::{Error, Result};
+use crate::services::usage::{TokenUsage, UsageLog, UsageMeter};
 
 use super::MAX_RESPONSE_BYTES;
+use super::http;
+use super::retry::{self, Failure};
+
+const DEFAULT_HOST: &str = "http://localhost:11434";
 
 pub struct OllamaProvider {
     client: Client,
@@ -22,15 +28,21 @@ pub struct OllamaProvider {
     temperature: f32,
     num_predict: u32,
     think: bool,
+    structured_output: bool,
+    retry: RetryConfig,
+    meter: UsageMeter,
 }
 
 #[derive(Serialize)]
-struct GenerateRequest {
+struct GenerateRequest<'a> {
     model: String,
     prompt: String,
     system: String,
     stream: bool,
     think: bool,
+    /// JSON schema the response must follow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a Value>,
     options: OllamaOptions,
 }
 
@@ -45,6 +57,22 @@ struct GenerateResponse {
     #[serde(default)]
     response: String,
     done: bool,
+    /// Prompt tokens, on the final chunk
+    prompt_eval_count: Option<u64>,
+    /// Generated tokens, on the final chunk
+    eval_count: Option<u64>,
+}
+
+impl GenerateResponse {
+    fn usage(&self) -> Option<TokenUsage> {
+        if self.prompt_eval_count.is_none() && self.eval_count.is_none() {
+            return None;
+        }
+        Some(TokenUsage {
+            input_tokens: self.prompt_eval_count.unwrap_or(0),
+            output_tokens: self.eval_count.unwrap_or(0),
+        })
+    }
 }
 
 #[derive(Deserialize)]
@@ -58,23 +86,25 @@ struct ModelInfo {
 }
 
 impl OllamaProvider {
-    pub fn new(config: &Config) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .build()
-            .map_err(|e| Error::Provider {
-                provider: "ollama".into(),
-                message: format!("failed to build HTTP client: {e}"),
-            })?;
+    pub fn new(profile: &ResolvedProfile) -> Result<Self> {
+        let client = http::build_client("ollama", profile)?;
 
         Ok(Self {
             client,
             // Sanitize: remove trailing slashes to avoid //api/generate
-            host: config.ollama_host.trim_end_matches('/').to_string(),
-            model: config.model.clone(),
-            temperature: config.temperature,
-            num_predict: config.num_predict,
-            think: config.think,
+            host: profile
+                .base_url
+                .as_deref()
+                .unwrap_or(DEFAULT_HOST)
+                .trim_end_matches('/')
+                .to_string(),
+            model: profile.model.clone(),
+            temperature: profile.temperature,
+            num_predict: profile.num_predict,
+            think: profile.think,
+            structured_output: profile.structured_output,
+            retry: profile.retry,
+            meter: UsageMeter::new(profile),
         })
     }
 
@@ -83,10 +113,20 @@ impl OllamaProvider {
         let url = format!("{}/api/tags", self.host);
 
         let response = self.client.get(&url).send().await.map_err(|e| {
-            if e.is_connect() {
+            if let Some(message) = http::tls_failure(&e) {
+                Error::Tls {
+                    provider: "ollama".into(),
+                    message,
+                }
+            } else if e.is_connect() {
                 Error::OllamaNotRunning {
                     host: self.host.clone(),
                 }
+            } else if e.is_timeout() {
+                Error::ProviderUnreachable {
+                    provider: "ollama".into(),
+                    message: "request timed out".into(),
+                }
             } else {
                 Error::Provider {
                     provider: "ollama".into(),
@@ -124,13 +164,36 @@ impl OllamaProvider {
         Ok(())
     }
 
+    /// Check that the endpoint is reachable through the configured proxy
+    /// and TLS settings.
+    pub async fn probe(&self) -> Result<()> {
+        http::probe(&self.client, "ollama", &self.host).await
+    }
+
     pub async fn generate(
         &self,
         prompt: &str,
         system_prompt: &str,
+        schema: Option<&Value>,
         token_tx: mpsc::Sender<String>,
         cancel: CancellationToken,
     ) -> Result<String> {
+        retry::with_retry("ollama", &self.retry, &cancel, || {
+            self.attempt(prompt, system_prompt, schema, &token_tx, &cancel)
+        })
+        .await
+    }
+
+    /// One request, streamed to completion.
+    async fn attempt(
+        &self,
+        prompt: &str,
+        system_prompt: &str,
+        schema: Option<&Value>,
+        token_tx: &mpsc::Sender<String>,
+        cancel: &CancellationToken,
+    ) -> std::result::Result<String, Failure> {
+        let start = Instant::now();
         let url = format!("{}/api/generate", self.host);
 
         let response = self
@@ -142,6 +205,7 @@ impl OllamaProvider {
                 system: system_prompt.to_string(),
                 stream: true,
                 think: self.think,
+                format: schema.filter(|_| self.structured_output),
                 options: OllamaOptions {
                     temperature: self.temperature,
                     num_predict: self.num_predict,
@@ -150,37 +214,40 @@ impl OllamaProvider {
             .send()
             .await
             .map_err(|e| {
-                if e.is_connect() {
+                if let Some(message) = http::tls_failure(&e) {
+                    Error::Tls {
+                        provider: "ollama".into(),
+                        message,
+                    }
+                    .into()
+                } else if e.is_connect() {
                     Error::OllamaNotRunning {
                         host: self.host.clone(),
                     }
+                    .into()
                 } else if e.is_timeout() {
-                    Error::Provider {
+                    Error::ProviderUnreachable {
                         provider: "ollama".into(),
                         message: "request timed out".into(),
                     }
+                    .into()
                 } else {
-                    Error::Provider {
+                    Failure::transient(Error::Provider {
                         provider: "ollama".into(),
                         message: e.without_url().to_string(),
-                    }
+                    })
                 }
             })?;
+        let mut metered = self.meter.attempt(start);
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response
-                .text()
-                .await
-                .unwrap_or_else(|e| format!("(failed to read body: {e})"));
-            return Err(Error::Provider {
-                provider: "ollama".into(),
-                message: format!("HTTP {}: {}", status, body),
-            });
+            return Err(Failure::from_response("ollama", response).await);
         }
 
         let mut stream = response.bytes_stream();
         let mut full_response = String::new();
+        // Whether any of the response reached the printer
+        let mut shown = false;
 
         // CRITICAL: Buffer for handling chunk boundaries
         // Chunks from bytes_stream() are NOT aligned to newlines!
@@ -188,16 +255,26 @@ impl OllamaProvider {
         loop {
             tokio::select! {
                 _ = cancel.cancelled() => {
-                    return Err(Error::Cancelled);
+                    return Err(Error::Cancelled.into());
                 }
                 chunk = stream.next() => {
                     let Some(chunk) = chunk else {
                         break; // Stream ended
                     };
 
-                    let chunk = chunk.map_err(|e| Error::Provider {
-                        provider: "ollama".into(),
-                        message: e.without_url().to_string(),
+                    let chunk = chunk.map_err(|e| {
+                        let error = if e.is_timeout() {
+                            Error::ProviderUnreachable {
+                                provider: "ollama".into(),
+                                message: "response stream timed out".into(),
+                            }
+                        } else {
+                            Error::Provider {
+                                provider: "ollama".into(),
+                                message: e.without_url().to_string(),
+                            }
+                        };
+                        Failure::interrupted(error, shown)
                     })?;
 
                     // Append chunk to buffer
@@ -209,7 +286,8 @@ impl OllamaProvider {
                         return Err(Error::Provider {
                             provider: "ollama".into(),
                             message: "line buffer exceeded 1 MB limit".into(),
-                        });
+                        }
+                        .into());
                     }
 
                     // Process complete lines (newline-delimited JSON)
@@ -227,17 +305,20 @@ impl OllamaProvider {
                         line_buffer.drain(..=newline_pos);
 
                         if let Some(resp) = result {
-                            let _ = token_tx.send(resp.response.clone()).await;
+                            shown |= token_tx.send(resp.response.clone()).await.is_ok()
+                                && !resp.response.is_empty();
                             full_response.push_str(&resp.response);
 
                             if full_response.len() > MAX_RESPONSE_BYTES {
                                 return Err(Error::Provider {
                                     provider: "ollama".into(),
                                     message: "response exceeded 1 MB limit".into(),
-                                });
+                                }
+                                .into());
                             }
 
                             if resp.done {
+                                metered.tokens = resp.usage();
                                 return Ok(full_response.trim().to_string());
                             }
                         }
@@ -251,6 +332,7 @@ impl OllamaProvider {
             && let Ok(resp) = serde_json::from_str::<GenerateResponse>(&line_buffer)
         {
             full_response.push_str(&resp.response);
+            metered.tokens = resp.usage();
         }
 
         Ok(full_response.trim().to_string())
@@ -259,4 +341,11 @@ impl OllamaProvider {
     pub fn name(&self) -> &str {
         "ollama"
     }
+
+    /// Record requests in `log` instead of this provider's own.
+    #[must_use]
+    pub fn with_usage_log(mut self, log: UsageLog) -> Self {
+        self.meter.attach(log);
+        self
+    }
 }
@@ -2,20 +2,24 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
-use std::time::Duration;
+use std::time::Instant;
 
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio::sync::mpsc;
 use tokio_stream::StreamExt;
 use tokio_util::sync::CancellationToken;
 
 use secrecy::{ExposeSecret, SecretString};
 
-use crate::config::Config;
+use crate::config::{Auth, ResolvedProfile, RetryConfig};
 use crate::error::{Error, Result};
+use crate::services::usage::{TokenUsage, UsageLog, UsageMeter};
 
 use super::MAX_RESPONSE_BYTES;
+use super::http;
+use super::retry::{self, Failure};
 
 const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
 
@@ -24,17 +28,47 @@ pub struct OpenAiProvider {
     base_url: String,
     model: String,
     api_key: SecretString,
+    auth: Auth,
     temperature: f32,
     max_tokens: u32,
+    structured_output: bool,
+    stream_usage: bool,
+    retry: RetryConfig,
+    meter: UsageMeter,
 }
 
 #[derive(Serialize)]
-struct ChatRequest {
+struct ChatRequest<'a> {
     model: String,
     messages: Vec<Message>,
     temperature: f32,
     max_tokens: u32,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+/// Asks for a final chunk carrying the request's `usage`.
+#[derive(Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+/// `response_format: {"type": "json_schema", ...}`
+#[derive(Serialize)]
+struct ResponseFormat<'a> {
+    #[serde(rename = "type")]
+    format_type: &'static str,
+    json_schema: JsonSchema<'a>,
+}
+
+#[derive(Serialize)]
+struct JsonSchema<'a> {
+    name: &'static str,
+    strict: bool,
+    schema: &'a Value,
 }
 
 #[derive(Serialize)]
@@ -45,7 +79,24 @@ struct Message {
 
 #[derive(Deserialize)]
 struct ChatChunk {
+    #[serde(default)]
     choices: Vec<ChunkChoice>,
+    usage: Option<ChunkUsage>,
+}
+
+#[derive(Deserialize)]
+struct ChunkUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+impl From<ChunkUsage> for TokenUsage {
+    fn from(u: ChunkUsage) -> Self {
+        Self {
+            input_tokens: u.prompt_tokens,
+            output_tokens: u.completion_tokens,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -60,45 +111,65 @@ struct Delta {
 }
 
 impl OpenAiProvider {
-    pub fn new(config: &Config) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .build()
-            .map_err(|e| Error::Provider {
-                provider: "openai".into(),
-                message: format!("failed to build HTTP client: {e}"),
-            })?;
+    pub fn new(profile: &ResolvedProfile) -> Result<Self> {
+        let client = http::build_client("openai", profile)?;
 
         Ok(Self {
             client,
-            base_url: config
-                .openai_base_url
-                .clone()
-                .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+            base_url: profile
+                .base_url
+                .as_deref()
+                .unwrap_or(DEFAULT_BASE_URL)
                 .trim_end_matches('/')
                 .to_string(),
-            model: config.model.clone(),
-            api_key: config.api_key.clone().unwrap_or_default(),
-            temperature: config.temperature,
-            max_tokens: config.num_predict,
+            model: profile.model.clone(),
+            api_key: profile.api_key.clone().unwrap_or_default(),
+            auth: profile.auth.clone(),
+            temperature: profile.temperature,
+            max_tokens: profile.num_predict,
+            structured_output: profile.structured_output,
+            stream_usage: profile.stream_usage,
+            retry: profile.retry,
+            meter: UsageMeter::new(profile),
         })
     }
 
+    /// Attach the key per the profile's auth style. Keyless self-hosted
+    /// servers get no header.
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        let key = self.api_key.expose_secret();
+        match self.auth {
+            _ if key.is_empty() => request,
+            Auth::Bearer => request.header("Authorization", format!("Bearer {}", key)),
+            Auth::Header(ref name) => request.header(name.as_str(), key),
+            Auth::None => request,
+        }
+    }
+
     pub async fn verify_connection(&self) -> Result<()> {
         let url = format!("{}/models", self.base_url);
 
         let response = self
-            .client
-            .get(&url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.api_key.expose_secret()),
-            )
+            .authorize(self.client.get(&url))
             .send()
             .await
-            .map_err(|e| Error::Provider {
-                provider: "openai".into(),
-                message: e.without_url().to_string(),
+            .map_err(|e| {
+                if let Some(message) = http::tls_failure(&e) {
+                    Error::Tls {
+                        provider: "openai".into(),
+                        message,
+                    }
+                } else if e.is_connect() || e.is_timeout() {
+                    Error::ProviderUnreachable {
+                        provider: "openai".into(),
+                        message: e.without_url().to_string(),
+                    }
+                } else {
+                    Error::Provider {
+                        provider: "openai".into(),
+                        message: e.without_url().to_string(),
+                    }
+                }
             })?;
 
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
@@ -111,22 +182,40 @@ impl OpenAiProvider {
         Ok(())
     }
 
+    /// Check that the endpoint is reachable through the configured proxy
+    /// and TLS settings.
+    pub async fn probe(&self) -> Result<()> {
+        http::probe(&self.client, "openai", &self.base_url).await
+    }
+
     pub async fn generate(
         &self,
         prompt: &str,
         system_prompt: &str,
+        schema: Option<&Value>,
         token_tx: mpsc::Sender<String>,
         cancel: CancellationToken,
     ) -> Result<String> {
+        retry::with_retry("openai", &self.retry, &cancel, || {
+            self.attempt(prompt, system_prompt, schema, &token_tx, &cancel)
+        })
+        .await
+    }
+
+    /// One request, streamed to completion.
+    async fn attempt(
+        &self,
+        prompt: &str,
+        system_prompt: &str,
+        schema: Option<&Value>,
+        token_tx: &mpsc::Sender<String>,
+        cancel: &CancellationToken,
+    ) -> std::result::Result<String, Failure> {
+        let start = Instant::now();
         let url = format!("{}/chat/completions", self.base_url);
 
         let response = self
-            .client
-            .post(&url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.api_key.expose_secret()),
-            )
+            .authorize(self.client.post(&url))
             .json(&ChatRequest {
                 model: self.model.clone(),
                 messages: vec![
@@ -142,50 +231,82 @@ impl OpenAiProvider {
                 temperature: self.temperature,
                 max_tokens: self.max_tokens,
                 stream: true,
+                response_format: schema.filter(|_| self.structured_output).map(|schema| {
+                    ResponseFormat {
+                        format_type: "json_schema",
+                        json_schema: JsonSchema {
+                            name: "commit_message",
+                            strict: true,
+                            schema,
+                        },
+                    }
+                }),
+                stream_options: self.stream_usage.then_some(StreamOptions {
+                    include_usage: true,
+                }),
             })
             .send()
             .await
             .map_err(|e| {
-                if e.is_timeout() {
-                    Error::Provider {
+                if let Some(message) = http::tls_failure(&e) {
+                    Error::Tls {
+                        provider: "openai".into(),
+                        message,
+                    }
+                    .into()
+                } else if e.is_timeout() {
+                    Error::ProviderUnreachable {
                         provider: "openai".into(),
                         message: "request timed out".into(),
                     }
-                } else {
-                    Error::Provider {
+                    .into()
+                } else if e.is_connect() {
+                    Error::ProviderUnreachable {
                         provider: "openai".into(),
                         message: e.without_url().to_string(),
                     }
+                    .into()
+                } else {
+                    Failure::transient(Error::Provider {
+                        provider: "openai".into(),
+                        message: e.without_url().to_string(),
+                    })
                 }
             })?;
+        let mut metered = self.meter.attempt(start);
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response
-                .text()
-                .await
-                .unwrap_or_else(|e| format!("(failed to read body: {e})"));
-            return Err(Error::Provider {
-                provider: "openai".into(),
-                message: format!("HTTP {status}: {body}"),
-            });
+            return Err(Failure::from_response("openai", response).await);
         }
 
         let mut stream = response.bytes_stream();
         let mut full_response = String::new();
+        // Whether any of the response reached the printer
+        let mut shown = false;
         let mut line_buffer = String::new();
+        let mut finished = false;
 
         loop {
             tokio::select! {
                 _ = cancel.cancelled() => {
-                    return Err(Error::Cancelled);
+                    return Err(Error::Cancelled.into());
                 }
                 chunk = stream.next() => {
                     let Some(chunk) = chunk else { break };
 
-                    let chunk = chunk.map_err(|e| Error::Provider {
-                        provider: "openai".into(),
-                        message: e.without_url().to_string(),
+                    let chunk = chunk.map_err(|e| {
+                        let error = if e.is_timeout() {
+                            Error::ProviderUnreachable {
+                                provider: "openai".into(),
+                                message: "response stream timed out".into(),
+                            }
+                        } else {
+                            Error::Provider {
+                                provider: "openai".into(),
+                                message: e.without_url().to_string(),
+                            }
+                        };
+                        Failure::interrupted(error, shown)
                     })?;
 
                     line_buffer.push_str(&String::from_utf8_lossy(&chunk));
@@ -194,40 +315,54 @@ impl OpenAiProvider {
                         return Err(Error::Provider {
                             provider: "openai".into(),
                             message: "line buffer exceeded 1 MB limit".into(),
-                        });
+                        }
+                        .into());
                     }
 
                     while let Some(newline_pos) = line_buffer.find('\n') {
                         // Parse from slice to avoid allocating a String per line
-                        let result = {
+                        let (result, done) = {
                             let line = line_buffer[..newline_pos].trim();
-                            if line.is_empty() || line == "data: [DONE]" {
-                                None
+                            if line == "data: [DONE]" {
+                                (None, true)
                             } else if let Some(data) = line.strip_prefix("data: ") {
-                                serde_json::from_str::<ChatChunk>(data).ok()
+                                (serde_json::from_str::<ChatChunk>(data).ok(), false)
                             } else {
-                                None
+                                (None, false)
                             }
                         };
                         // Shift buffer in-place (no allocation)
                         line_buffer.drain(..=newline_pos);
 
+                        if done {
+                            return Ok(full_response.trim().to_string());
+                        }
+
                         if let Some(chunk) = result {
                             for choice in &chunk.choices {
                                 if let Some(ref content) = choice.delta.content {
-                                    let _ = token_tx.send(content.clone()).await;
+                                    shown |= token_tx.send(content.clone()).await.is_ok();
                                     full_response.push_str(content);
                                 }
                                 if full_response.len() > MAX_RESPONSE_BYTES {
                                     return Err(Error::Provider {
                                         provider: "openai".into(),
                                         message: "response exceeded 1 MB limit".into(),
-                                    });
+                                    }
+                                    .into());
                                 }
                                 if choice.finish_reason.is_some() {
-                                    return Ok(full_response.trim().to_string());
+                                    finished = true;
                                 }
                             }
+                            if let Some(u) = chunk.usage {
+                                metered.tokens = Some(u.into());
+                            }
+                            // Usage comes with or after the chunk carrying
+                            // finish_reason; without it, wait for [DONE]
+                            if finished && metered.tokens.is_some() {
+                                return Ok(full_response.trim().to_string());
+                            }
                         }
                     }
                 }
@@ -247,6 +382,9 @@ impl OpenAiProvider {
                         full_response.push_str(content);
                     }
                 }
+                if let Some(u) = chunk.usage {
+                    metered.tokens = Some(u.into());
+                }
             }
         }
 
@@ -256,4 +394,15 @@ impl OpenAiProvider {
     pub fn name(&self) -> &str {
         "openai"
     }
+
+    /// Record requests in `log` instead of this provider's own.
+    #[must_use]
+    pub fn with_usage_log(mut self, log: UsageLog) -> Self {
+        self.meter.attach(log);
+        self
+    }
+
+    pub(crate) fn is_cloud(&self) -> bool {
+        self.meter.is_cloud()
+    }
 }
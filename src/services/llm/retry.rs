@@ -0,0 +1,168 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant, SystemTime};
+
+use reqwest::{Response, StatusCode};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::config::RetryConfig;
+use crate::error::{Error, Result};
+
+/// Delay before the first retry; doubled for each one after that.
+const BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Upper bound for the computed backoff (not for `Retry-After`).
+const MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// A failed request attempt: the error to report, and whether another
+/// attempt may succeed.
+pub(crate) struct Failure {
+    error: Error,
+    retryable: bool,
+    retry_after: Option<Duration>,
+}
+
+impl Failure {
+    /// A failure worth retrying, e.g. a request that failed before any
+    /// response arrived.
+    pub(crate) fn transient(error: Error) -> Self {
+        Self {
+            error,
+            retryable: true,
+            retry_after: None,
+        }
+    }
+
+    /// A stream that broke partway through. A retry streams into the same
+    /// token channel from the start, so it's only retried if none of the
+    /// response was `shown` yet.
+    pub(crate) fn interrupted(error: Error, shown: bool) -> Self {
+        Self {
+            error,
+            retryable: !shown,
+            retry_after: None,
+        }
+    }
+
+    /// Build the failure for a non-2xx response. Throttling and server
+    /// errors are retried, honoring `Retry-After`; anything else is final.
+    pub(crate) async fn from_response(provider: &str, response: Response) -> Self {
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
+        let body = response
+            .text()
+            .await
+            .unwrap_or_else(|e| format!("(failed to read body: {e})"));
+
+        Self {
+            error: Error::Provider {
+                provider: provider.into(),
+                message: format!("HTTP {status}: {body}"),
+            },
+            retryable: is_retryable(status),
+            retry_after,
+        }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(error: Error) -> Self {
+        Self {
+            error,
+            retryable: false,
+            retry_after: None,
+        }
+    }
+}
+
+/// A `Retry-After` value: delay seconds or an HTTP date (RFC 9110). Delays
+/// too long to represent saturate, so the deadline check gives up on them.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        if secs.is_nan() || secs < 0.0 {
+            return None;
+        }
+        return Some(Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    // A date in the past means "now"
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// 408, 429, 5xx gateway/availability errors and Anthropic's 529 (overloaded).
+fn is_retryable(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 408 | 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+/// Exponential backoff for retry `n` (1-based) with jitter in the upper half,
+/// so concurrent candidates don't retry in lockstep.
+fn backoff(n: u32) -> Duration {
+    let exp = BASE_DELAY
+        .saturating_mul(1 << n.saturating_sub(1).min(16))
+        .min(MAX_DELAY);
+    let jitter = (RandomState::new().hash_one(n) % 1_000) as f64 / 1_000.0;
+    exp.mul_f64(0.5 + jitter / 2.0)
+}
+
+/// Run `attempt` until it succeeds, fails for good, or the policy's attempts
+/// or deadline run out. The last error is returned in that case.
+///
+/// The deadline only decides whether another attempt starts; a running
+/// attempt is bounded by the client's `timeout_secs`, not by the deadline.
+pub(crate) async fn with_retry<T, F, Fut>(
+    provider: &str,
+    policy: &RetryConfig,
+    cancel: &CancellationToken,
+    mut attempt: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, Failure>>,
+{
+    let start = Instant::now();
+    let deadline = Duration::from_secs(policy.deadline_secs);
+
+    let mut n = 0;
+    loop {
+        n += 1;
+        let failure = match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(failure) => failure,
+        };
+        if !failure.retryable || n >= policy.max_attempts {
+            return Err(failure.error);
+        }
+
+        let delay = failure.retry_after.unwrap_or_else(|| backoff(n));
+        // Never wait past the deadline
+        if delay > deadline.saturating_sub(start.elapsed()) {
+            return Err(failure.error);
+        }
+
+        warn!(
+            provider,
+            attempt = n,
+            delay_ms = delay.as_millis() as u64,
+            error = %failure.error,
+            "request failed, retrying"
+        );
+        tokio::select! {
+            _ = cancel.cancelled() => return Err(Error::Cancelled),
+            _ = tokio::time::sleep(delay) => {}
+        }
+    }
+}
@@ -0,0 +1,197 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::domain::CodeSymbol;
+use crate::services::history::CommitRecord;
+
+/// Maximum number of incoming commit subjects listed in the prompt.
+const MAX_PROMPT_COMMITS: usize = 50;
+
+/// Maximum number of symbols listed per side of a conflicted file.
+const MAX_RESOLUTION_SYMBOLS: usize = 8;
+
+/// How a conflicted file was resolved, judged by comparing the resolved
+/// index entry to both parents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Kept the current branch's version.
+    Ours,
+    /// Took the incoming branch's version.
+    Theirs,
+    /// Combined both sides, or rewrote the conflicting part.
+    Combined,
+    /// Deleted the file.
+    Deleted,
+}
+
+impl Resolution {
+    #[must_use]
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Ours => "kept the current branch's version",
+            Self::Theirs => "took the incoming branch's version",
+            Self::Combined => "combined both sides",
+            Self::Deleted => "deleted the file",
+        }
+    }
+}
+
+/// A file that needed conflict resolution.
+#[derive(Debug, Clone)]
+pub struct ConflictResolution {
+    pub path: PathBuf,
+    pub resolution: Resolution,
+    /// Symbols where the result differs from the current branch, i.e. what
+    /// the incoming side contributed.
+    pub from_theirs: Vec<String>,
+    /// Symbols where the result differs from the incoming branch, i.e. what
+    /// the current side kept.
+    pub from_ours: Vec<String>,
+}
+
+/// The merge being committed.
+#[derive(Debug, Clone, Default)]
+pub struct MergeInfo {
+    /// First line of `MERGE_MSG`, e.g. `Merge branch 'feature'`.
+    pub title: Option<String>,
+    /// Commits brought in by the merge, oldest first.
+    pub commits: Vec<CommitRecord>,
+    pub conflicts: Vec<ConflictResolution>,
+}
+
+/// Describes merge commits: the incoming commits and how conflicts were resolved.
+pub struct MergeBuilder;
+
+impl MergeBuilder {
+    /// The first line of `MERGE_MSG`, which git fills with the merge title.
+    #[must_use]
+    pub fn merge_title(merge_msg: &str) -> Option<String> {
+        merge_msg
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(String::from)
+    }
+
+    /// Paths listed under `Conflicts:` in `MERGE_MSG`. Git writes them as
+    /// comments (`#\tpath`), or uncommented with older `commit.cleanup` modes.
+    #[must_use]
+    pub fn parse_conflicts(merge_msg: &str) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        let mut in_list = false;
+
+        for line in merge_msg.lines() {
+            let line = line.strip_prefix('#').unwrap_or(line);
+            if line.trim() == "Conflicts:" {
+                in_list = true;
+                continue;
+            }
+            if !in_list {
+                continue;
+            }
+            if let Some(path) = line.strip_prefix('\t') {
+                paths.push(PathBuf::from(path.trim()));
+            } else if !line.trim().is_empty() {
+                in_list = false;
+            }
+        }
+        paths
+    }
+
+    /// Classify a resolution from the resolved, current and incoming content
+    /// of a file (`None` where the file doesn't exist).
+    #[must_use]
+    pub fn classify(
+        resolved: Option<&str>,
+        ours: Option<&str>,
+        theirs: Option<&str>,
+    ) -> Resolution {
+        match resolved {
+            None => Resolution::Deleted,
+            Some(_) if resolved == ours => Resolution::Ours,
+            Some(_) if resolved == theirs => Resolution::Theirs,
+            Some(_) => Resolution::Combined,
+        }
+    }
+
+    /// Distinct symbol names, qualified by their parent scope, in order.
+    #[must_use]
+    pub fn symbol_names(symbols: &[CodeSymbol]) -> Vec<String> {
+        let mut seen = HashSet::new();
+        symbols
+            .iter()
+            .map(|s| match s.parent_scope {
+                Some(ref scope) => format!("{scope}::{}", s.name),
+                None => s.name.clone(),
+            })
+            .filter(|name| seen.insert(name.clone()))
+            .collect()
+    }
+
+    /// Append the merge title, incoming commits and conflict resolutions to
+    /// the generation prompt.
+    #[must_use]
+    pub fn build_prompt(original_prompt: &str, info: &MergeInfo) -> String {
+        let mut section = String::from("---\nThese changes complete a merge");
+        if let Some(ref title) = info.title {
+            section.push_str(&format!(" (\"{title}\")"));
+        }
+        section.push('.');
+
+        if !info.commits.is_empty() {
+            section.push_str("\n\nINCOMING COMMITS:\n");
+            let mut subjects: Vec<String> = info
+                .commits
+                .iter()
+                .take(MAX_PROMPT_COMMITS)
+                .map(|c| format!("  - {}", c.subject))
+                .collect();
+            let omitted = info.commits.len().saturating_sub(MAX_PROMPT_COMMITS);
+            if omitted > 0 {
+                subjects.push(format!("  ... and {omitted} more"));
+            }
+            section.push_str(&subjects.join("\n"));
+        }
+
+        if !info.conflicts.is_empty() {
+            section.push_str("\n\nCONFLICTS RESOLVED:");
+            for conflict in &info.conflicts {
+                section.push_str(&format!(
+                    "\n  - {}: {}",
+                    conflict.path.display(),
+                    conflict.resolution.describe()
+                ));
+                if conflict.resolution != Resolution::Combined {
+                    continue;
+                }
+                for (label, names) in [
+                    ("from incoming", &conflict.from_theirs),
+                    ("kept from current", &conflict.from_ours),
+                ] {
+                    if !names.is_empty() {
+                        let listed: Vec<&str> = names
+                            .iter()
+                            .take(MAX_RESOLUTION_SYMBOLS)
+                            .map(String::as_str)
+                            .collect();
+                        section.push_str(&format!("\n      {label}: {}", listed.join(", ")));
+                    }
+                }
+            }
+        }
+
+        section
+            .push_str("\n\nSummarize what the merge brings in. Don't list the commits one by one.");
+        if !info.conflicts.is_empty() {
+            section.push_str(
+                " End the body with a paragraph describing which files had conflicts and how each was resolved.",
+            );
+        }
+
+        format!("{original_prompt}\n\n{section}\n\nRespond with ONLY the JSON object.")
+    }
+}
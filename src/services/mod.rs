@@ -3,13 +3,24 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
 pub mod analyzer;
+pub mod cache;
+pub mod changelog;
 pub mod context;
 pub mod differ;
 pub mod git;
 pub mod history;
+pub mod lint;
 pub mod llm;
+pub mod merge;
+pub mod output;
+pub mod pr;
 pub mod progress;
+pub mod revert;
 pub mod safety;
 pub mod sanitizer;
+pub mod split_editor;
 pub mod splitter;
+pub mod squash;
 pub mod template;
+pub mod trailers;
+pub mod usage;
@@ -0,0 +1,225 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::domain::{PromptContext, Trailer};
+use crate::services::history::HistoryService;
+use crate::services::sanitizer::StructuredCommit;
+use crate::services::splitter::CommitGroup;
+
+/// Version of the `--output json` schema. Fields may be added within a
+/// version; renaming or removing one bumps it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+const BREAKING_FOOTER: &str = "BREAKING CHANGE:";
+
+/// Result of a commit message generation run.
+#[derive(Debug, Serialize)]
+pub struct CommitOutput {
+    pub schema_version: u32,
+    /// Final sanitized commit message, exactly as it would be committed
+    pub message: String,
+    /// `message` split into its conventional commit parts; `None` if the
+    /// message isn't conventional (e.g. after a manual edit)
+    pub commit: Option<StructuredCommit>,
+    /// Whether the message is marked breaking (`!` or a `BREAKING CHANGE:` footer)
+    pub breaking: bool,
+    /// All candidates after deduplication, `message` first
+    pub candidates: Vec<String>,
+    pub suggested_type: String,
+    pub suggested_scope: Option<String>,
+    pub evidence: Evidence,
+    pub split: SplitOutput,
+    /// Whether the message was committed (`--yes` without `--dry-run`)
+    pub committed: bool,
+}
+
+/// Evidence flags computed from code analysis, as given to the LLM.
+#[derive(Debug, Serialize)]
+pub struct Evidence {
+    pub is_mechanical: bool,
+    pub has_bug_evidence: bool,
+    pub public_api_removed_count: usize,
+    pub has_new_public_api: bool,
+    pub is_dependency_only: bool,
+    pub has_unsafe_addition: bool,
+    pub breaking_signals: Vec<String>,
+}
+
+/// Output of `--split plan`: the suggested grouping, without messages.
+#[derive(Debug, Serialize)]
+pub struct SplitPlan {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub split: SplitOutput,
+}
+
+impl SplitPlan {
+    #[must_use]
+    pub fn new(groups: Option<&[CommitGroup]>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            split: SplitOutput::from_groups(groups),
+        }
+    }
+
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("SplitPlan serializes to JSON")
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SplitOutput {
+    /// Whether the staged changes look like several independent commits
+    pub suggested: bool,
+    pub groups: Vec<SplitGroup>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SplitGroup {
+    #[serde(rename = "type")]
+    pub commit_type: String,
+    pub scope: Option<String>,
+    pub files: Vec<String>,
+    /// Files split across groups, mapped to the 0-based indices of the hunks
+    /// in their staged diff that belong to this group
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub hunks: BTreeMap<String, Vec<usize>>,
+}
+
+impl CommitOutput {
+    /// Assemble the output for a generated message. `split_groups` is `None`
+    /// when no split was suggested (or detection was disabled).
+    #[must_use]
+    pub fn build(
+        message: &str,
+        candidates: Vec<String>,
+        context: &PromptContext,
+        split_groups: Option<&[CommitGroup]>,
+    ) -> Self {
+        let commit = parse_message(message);
+        let breaking = commit.as_ref().is_some_and(|c| c.breaking_change.is_some())
+            || message
+                .lines()
+                .next()
+                .and_then(HistoryService::parse_conventional)
+                .is_some_and(|p| p.breaking);
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            message: message.to_string(),
+            commit,
+            breaking,
+            candidates,
+            suggested_type: context.suggested_type.as_str().to_string(),
+            suggested_scope: context.suggested_scope.clone(),
+            evidence: Evidence::from_context(context),
+            split: SplitOutput::from_groups(split_groups),
+            committed: false,
+        }
+    }
+
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("CommitOutput serializes to JSON")
+    }
+}
+
+impl Evidence {
+    #[must_use]
+    pub fn from_context(context: &PromptContext) -> Self {
+        Self {
+            is_mechanical: context.is_mechanical,
+            has_bug_evidence: context.has_bug_evidence,
+            public_api_removed_count: context.public_api_removed_count,
+            has_new_public_api: context.has_new_public_api,
+            is_dependency_only: context.is_dependency_only,
+            has_unsafe_addition: context.has_unsafe_addition,
+            breaking_signals: context.metadata_breaking_signals.clone(),
+        }
+    }
+}
+
+impl SplitOutput {
+    #[must_use]
+    pub fn from_groups(groups: Option<&[CommitGroup]>) -> Self {
+        let groups: Vec<SplitGroup> = groups
+            .unwrap_or_default()
+            .iter()
+            .map(|g| SplitGroup {
+                commit_type: g.commit_type.as_str().to_string(),
+                scope: g.scope.clone(),
+                files: g.files.iter().map(|p| p.display().to_string()).collect(),
+                hunks: g
+                    .hunks
+                    .iter()
+                    .map(|(p, h)| (p.display().to_string(), h.clone()))
+                    .collect(),
+            })
+            .collect();
+        Self {
+            suggested: !groups.is_empty(),
+            groups,
+        }
+    }
+}
+
+/// Split a sanitized commit message back into its structured parts.
+///
+/// The `BREAKING CHANGE:` footer (with its indented continuation lines) is
+/// pulled out of the body and unwrapped into `breaking_change`. Trailers are
+/// taken from the final paragraph when every line of it is a footer.
+#[must_use]
+pub fn parse_message(message: &str) -> Option<StructuredCommit> {
+    let mut lines = message.lines();
+    let header = HistoryService::parse_conventional(lines.next()?)?;
+    let lines: Vec<&str> = lines.collect();
+
+    let footer_start = lines
+        .iter()
+        .rposition(|l| l.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    let has_trailers = lines[footer_start..].iter().all(|l| {
+        l.starts_with(BREAKING_FOOTER) || l.starts_with("  ") || Trailer::parse(l).is_some()
+    });
+
+    let mut body: Vec<&str> = Vec::new();
+    let mut breaking: Option<String> = None;
+    let mut trailers = Vec::new();
+    let mut in_footer = false;
+    for (i, line) in lines.into_iter().enumerate() {
+        if let Some(rest) = line.strip_prefix(BREAKING_FOOTER) {
+            breaking = Some(rest.trim().to_string());
+            in_footer = true;
+        } else if in_footer && line.starts_with("  ") {
+            if let Some(ref mut desc) = breaking {
+                desc.push(' ');
+                desc.push_str(line.trim());
+            }
+        } else if has_trailers
+            && i >= footer_start
+            && let Some(trailer) = Trailer::parse(line)
+        {
+            in_footer = false;
+            trailers.push(trailer);
+        } else {
+            in_footer = false;
+            body.push(line);
+        }
+    }
+
+    let body = body.join("\n").trim().to_string();
+    Some(StructuredCommit {
+        commit_type: header.commit_type,
+        scope: header.scope,
+        subject: header.subject_text,
+        body: (!body.is_empty()).then_some(body),
+        breaking_change: breaking,
+        trailers,
+    })
+}
@@ -0,0 +1,306 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::CommitFormat;
+use crate::domain::{CommitType, FileCategory, PromptContext, StagedChanges};
+use crate::services::history::CommitRecord;
+use crate::services::sanitizer::CommitSanitizer;
+
+/// Maximum number of commit subjects listed in the prompt.
+const MAX_PROMPT_COMMITS: usize = 50;
+
+// SYNC: commit type list must match CommitType::ALL in src/domain/commit.rs
+pub const PR_SYSTEM_PROMPT: &str = r#"You write pull request titles and descriptions from a branch's commits and its net git diff.
+
+Use exactly one type for the title:
+feat, fix, refactor, chore, docs, test, style, perf, build, ci, revert
+
+Rules:
+- Title: imperative, specific, lowercase start, no trailing period. Describe the branch as a whole, not the last commit.
+- Summary: 2-4 sentences on WHAT the branch changes and WHY. Plain prose, no headings.
+- Changes: 2-6 short bullet items naming concrete entities (functions, types, modules, commands) from the diff.
+- Breaking changes: only if existing users or dependents must change their code, config, or scripts. Otherwise an empty list.
+- Test notes: how the change is covered or how a reviewer can verify it, based on test files in the diff. null if nothing to say.
+- Never copy labels, field names, or evidence tags from the prompt into your output.
+
+Respond with ONLY the JSON object as specified in the user prompt.
+"#;
+
+/// Pull request description returned by the LLM.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PrDescription {
+    #[serde(rename = "type")]
+    pub pr_type: String,
+    pub scope: Option<String>,
+    pub title: String,
+    pub summary: String,
+    #[serde(default)]
+    pub changes: Vec<String>,
+    #[serde(default)]
+    pub breaking_changes: Vec<String>,
+    #[serde(default)]
+    pub test_notes: Option<String>,
+}
+
+pub struct PrBuilder;
+
+impl PrBuilder {
+    /// Build the user prompt for a branch from its combined context and commits.
+    #[must_use]
+    pub fn build_prompt(
+        context: &PromptContext,
+        branch: &str,
+        base: &str,
+        commits: &[CommitRecord],
+    ) -> String {
+        let commit_lines: Vec<String> = commits
+            .iter()
+            .take(MAX_PROMPT_COMMITS)
+            .map(|c| format!("  - {}", c.subject))
+            .collect();
+        let omitted = commits.len().saturating_sub(MAX_PROMPT_COMMITS);
+        let omitted_line = if omitted > 0 {
+            format!("\n  ... and {} more", omitted)
+        } else {
+            String::new()
+        };
+
+        let declared_breaking: Vec<String> = commits
+            .iter()
+            .flat_map(CommitRecord::breaking_notes)
+            .map(|n| format!("- {}", n))
+            .collect();
+        let declared_breaking_section = if declared_breaking.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "\nBREAKING CHANGES DECLARED IN COMMITS:\n{}\n",
+                declared_breaking.join("\n")
+            )
+        };
+
+        let metadata_breaking_section = if context.metadata_breaking_signals.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "\nWARNING: METADATA BREAKING CHANGES DETECTED:\n{}\n",
+                context
+                    .metadata_breaking_signals
+                    .iter()
+                    .map(|s| format!("- {}", s))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        };
+
+        let related_section = if context.test_correlations.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "\nRELATED FILES:\n{}\n",
+                context
+                    .test_correlations
+                    .iter()
+                    .map(|c| format!("  {}", c))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        };
+
+        let locale_instruction = context
+            .locale
+            .as_ref()
+            .map(|lang| {
+                format!(
+                    "\nLANGUAGE: Write the title, summary, changes, and notes in {lang}. \
+                     The type, scope, and JSON keys must remain in English.\n"
+                )
+            })
+            .unwrap_or_default();
+
+        let history_section = context
+            .history_context
+            .as_ref()
+            .map(|h| format!("\n{}\n", h))
+            .unwrap_or_default();
+
+        format!(
+            r#"Write a pull request title and description for branch "{branch}" targeting "{base}".
+
+SUMMARY: {summary}
+FILES: {files}
+SUGGESTED TYPE: {commit_type}{scope}
+
+COMMITS ({count}):
+{commits}{omitted}
+{symbols}{breaking}{declared_breaking}{metadata_breaking}{related}
+DIFF:
+{diff}
+{locale}{history}
+Respond with ONLY this JSON:
+{{"type": "<type>", "scope": {scope_json}, "title": "<title>", "summary": "<summary>", "changes": ["<change>"], "breaking_changes": [], "test_notes": null}}"#,
+            branch = branch,
+            base = base,
+            summary = context.change_summary,
+            files = context.file_breakdown.trim(),
+            commit_type = context.suggested_type.as_str(),
+            scope = context
+                .suggested_scope
+                .as_ref()
+                .map(|s| format!("\nSCOPE: {}", s))
+                .unwrap_or_default(),
+            count = commits.len(),
+            commits = commit_lines.join("\n"),
+            omitted = omitted_line,
+            symbols = context.format_symbols_section(),
+            breaking = context.format_breaking_warning(),
+            declared_breaking = declared_breaking_section,
+            metadata_breaking = metadata_breaking_section,
+            related = related_section,
+            diff = context.truncated_diff,
+            locale = locale_instruction,
+            history = history_section,
+            scope_json = context
+                .suggested_scope
+                .as_ref()
+                .map(|s| format!("\"{}\"", s))
+                .unwrap_or_else(|| "null".to_string()),
+        )
+    }
+
+    /// Parse raw LLM output into a PR description.
+    #[must_use]
+    pub fn parse(raw: &str) -> Option<PrDescription> {
+        CommitSanitizer::parse_json_object::<PrDescription>(raw)
+            .filter(|pr| !pr.title.trim().is_empty())
+    }
+
+    /// Format the title as a conventional header: `type(scope)!: title`.
+    ///
+    /// Falls back to `fallback_type` when the LLM returned an unknown type.
+    #[must_use]
+    pub fn format_title(
+        pr: &PrDescription,
+        fallback_type: CommitType,
+        breaking: bool,
+        format: &CommitFormat,
+    ) -> String {
+        let pr_type = pr.pr_type.to_lowercase();
+        let pr_type = if CommitType::ALL.contains(&pr_type.as_str()) {
+            pr_type
+        } else {
+            fallback_type.as_str().to_string()
+        };
+
+        let scope = pr
+            .scope
+            .as_deref()
+            .map(|s| s.trim().to_lowercase().replace(' ', "-"))
+            .filter(|s| format.include_scope && !s.is_empty());
+
+        let title = pr.title.trim().trim_end_matches('.');
+        let title = if format.lowercase_subject {
+            let mut chars = title.chars();
+            match chars.next() {
+                Some(first) => first.to_lowercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        } else {
+            title.to_string()
+        };
+
+        let bang = if breaking { "!" } else { "" };
+        match scope {
+            Some(sc) => format!("{}({}){}: {}", pr_type, sc, bang, title),
+            None => format!("{}{}: {}", pr_type, bang, title),
+        }
+    }
+
+    /// All breaking changes for the branch: the LLM's list merged with notes
+    /// declared in commit footers, deduplicated case-insensitively.
+    #[must_use]
+    pub fn collect_breaking(pr: &PrDescription, commits: &[CommitRecord]) -> Vec<String> {
+        let mut seen = std::collections::HashSet::new();
+        pr.breaking_changes
+            .iter()
+            .cloned()
+            .chain(commits.iter().flat_map(CommitRecord::breaking_notes))
+            .map(|b| b.trim().to_string())
+            .filter(|b| !b.is_empty() && !b.eq_ignore_ascii_case("null"))
+            .filter(|b| seen.insert(b.to_lowercase()))
+            .collect()
+    }
+
+    /// Render the Markdown body: summary, changes, breaking changes, testing
+    /// notes and the commit list.
+    #[must_use]
+    pub fn render_markdown(
+        pr: &PrDescription,
+        breaking: &[String],
+        commits: &[CommitRecord],
+        changes: &StagedChanges,
+    ) -> String {
+        let mut out = format!("## Summary\n\n{}\n", pr.summary.trim());
+
+        let change_items: Vec<&str> = pr
+            .changes
+            .iter()
+            .map(|c| c.trim())
+            .filter(|c| !c.is_empty())
+            .collect();
+        if !change_items.is_empty() {
+            out.push_str("\n## Changes\n\n");
+            for item in change_items {
+                out.push_str(&format!("- {}\n", item));
+            }
+        }
+
+        if !breaking.is_empty() {
+            out.push_str("\n## Breaking Changes\n\n");
+            for item in breaking {
+                out.push_str(&format!("- {}\n", item));
+            }
+        }
+
+        let test_files: Vec<String> = changes
+            .files
+            .iter()
+            .filter(|f| f.category == FileCategory::Test)
+            .map(|f| format!("`{}`", f.path.display()))
+            .collect();
+        let test_notes = pr
+            .test_notes
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty() && !n.eq_ignore_ascii_case("null"));
+        if test_notes.is_some() || !test_files.is_empty() {
+            out.push_str("\n## Testing\n\n");
+            if let Some(notes) = test_notes {
+                out.push_str(notes);
+                out.push('\n');
+            }
+            if !test_files.is_empty() {
+                if test_notes.is_some() {
+                    out.push('\n');
+                }
+                out.push_str(&format!("Test files changed: {}\n", test_files.join(", ")));
+            }
+        }
+
+        if !commits.is_empty() {
+            out.push_str("\n## Commits\n\n");
+            for c in commits {
+                out.push_str(&format!(
+                    "- `{}` {}\n",
+                    &c.sha[..c.sha.len().min(7)],
+                    c.subject
+                ));
+            }
+        }
+
+        out
+    }
+}
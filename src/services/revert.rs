@@ -0,0 +1,74 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use crate::services::git::GitService;
+use crate::services::history::CommitRecord;
+use crate::services::sanitizer::CommitSanitizer;
+
+/// How many recent commits are searched for one the staged diff reverts.
+pub const REVERT_SEARCH_DEPTH: usize = 50;
+
+/// Staged changes larger than this are only matched against `REVERT_HEAD`;
+/// searching history for them costs a patch of every recent commit.
+pub const REVERT_SEARCH_MAX_LINES: usize = 400;
+
+/// Longest first line the sanitizer accepts.
+const MAX_FIRST_LINE: usize = 72;
+
+/// Writes `revert:` messages that reference the reverted commit.
+pub struct RevertBuilder;
+
+impl RevertBuilder {
+    /// Append the reverted commit to the generation prompt, so the body can
+    /// explain what is being undone and why.
+    #[must_use]
+    pub fn build_prompt(original_prompt: &str, reverted: &CommitRecord) -> String {
+        let body = if reverted.body.is_empty() {
+            String::new()
+        } else {
+            format!("\n{}", reverted.body)
+        };
+        format!(
+            "{original_prompt}\n\n---\nThese changes revert commit {} whose message was:\n\"{}{body}\"\n\nUse type revert. In the body, explain what is being undone and why, based on the diff and the original message. Don't repeat the commit id.\n\nRespond with ONLY the JSON object.",
+            GitService::short_sha(&reverted.sha),
+            reverted.subject,
+        )
+    }
+
+    /// Rewrite a generated message as `revert: <original subject>`, with a
+    /// `This reverts commit <sha>.` line followed by the generated body.
+    ///
+    /// Reverting a `revert: <subject>` commit reapplies `<subject>`, so that
+    /// becomes the subject. The generated subject is kept when the result
+    /// wouldn't fit in 72 characters or isn't a conventional header.
+    #[must_use]
+    pub fn finalize(message: &str, reverted: &CommitRecord) -> String {
+        let mut lines = message.lines();
+        let generated = lines.next().unwrap_or_default().trim();
+        let explanation: Vec<&str> = lines
+            .filter(|l| !l.starts_with("This reverts commit"))
+            .collect();
+        let explanation = explanation.join("\n");
+        let explanation = explanation.trim();
+
+        let subject = match reverted.subject.strip_prefix("revert: ") {
+            Some(original) => original.trim().to_string(),
+            None => format!("revert: {}", reverted.subject),
+        };
+        let subject = if subject.chars().count() <= MAX_FIRST_LINE
+            && CommitSanitizer::validate_conventional(&subject).is_ok()
+        {
+            subject.as_str()
+        } else {
+            generated
+        };
+
+        let mut out = format!("{subject}\n\nThis reverts commit {}.", reverted.sha);
+        if !explanation.is_empty() {
+            out.push_str("\n\n");
+            out.push_str(explanation);
+        }
+        out
+    }
+}
@@ -2,13 +2,16 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
+use std::collections::HashSet;
 use std::sync::LazyLock;
 
 use regex::Regex;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 
 use crate::config::CommitFormat;
-use crate::domain::CommitType;
+use crate::domain::{CommitType, Trailer};
 use crate::error::{Error, Result};
 
 /// Structured commit message from LLM (preferred format)
@@ -20,6 +23,37 @@ pub struct StructuredCommit {
     pub subject: String,
     pub body: Option<String>,
     pub breaking_change: Option<String>, // null or omitted = non-breaking
+    /// Trailers after the footer (`Refs`, `Co-authored-by`, `Signed-off-by`).
+    /// Set by commitbee, never read from LLM output.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub trailers: Vec<Trailer>,
+}
+
+/// JSON schema for [`StructuredCommit`], sent to backends that support
+/// constrained output. `type` is limited to [`CommitType::ALL`].
+static COMMIT_SCHEMA: LazyLock<Value> = LazyLock::new(|| {
+    json!({
+        "type": "object",
+        "properties": {
+            "type": { "type": "string", "enum": CommitType::ALL },
+            "scope": { "type": ["string", "null"] },
+            "subject": { "type": "string" },
+            "body": { "type": ["string", "null"] },
+            "breaking_change": { "type": ["string", "null"] },
+        },
+        // OpenAI's strict mode requires every property to be listed
+        "required": ["type", "scope", "subject", "body", "breaking_change"],
+        "additionalProperties": false,
+    })
+});
+
+impl StructuredCommit {
+    /// The schema LLM output must follow. Trailers are set by commitbee and
+    /// not part of it.
+    #[must_use]
+    pub fn json_schema() -> &'static Value {
+        &COMMIT_SCHEMA
+    }
 }
 
 static SCOPE_REGEX: LazyLock<Regex> =
@@ -38,6 +72,12 @@ static VALID_TYPE_START_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(&format!(r"(?m)(?:^|\s)({})(?:\(|!|:)", types)).unwrap()
 });
 
+/// Issue references written as `#123`.
+static HASH_ISSUE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\B#\d+\b").unwrap());
+
+/// Word-set similarity at which two candidates count as duplicates.
+pub const DEDUP_SIMILARITY: f64 = 0.9;
+
 static PREAMBLE_PATTERNS: &[&str] = &[
     "here's the commit message",
     "here is the commit message",
@@ -114,9 +154,21 @@ impl CommitSanitizer {
     }
 
     /// Parse and validate commit message from LLM output
+    #[allow(dead_code)]
     pub fn sanitize(raw: &str, format: &CommitFormat) -> Result<String> {
+        Self::sanitize_with_trailers(raw, format, &[])
+    }
+
+    /// [`sanitize`](Self::sanitize), then end the message with `trailers`,
+    /// skipping any it already carries.
+    pub fn sanitize_with_trailers(
+        raw: &str,
+        format: &CommitFormat,
+        trailers: &[Trailer],
+    ) -> Result<String> {
         // Step 1: Try to parse as JSON (structured output)
-        if let Ok(structured) = Self::try_parse_json(raw) {
+        if let Ok(mut structured) = Self::try_parse_json(raw) {
+            structured.trailers = trailers.to_vec();
             let msg = Self::format_structured(&structured, format)?;
             return Ok(Self::strip_control_chars(&msg));
         }
@@ -137,7 +189,33 @@ impl CommitSanitizer {
             )));
         }
 
-        Ok(Self::strip_control_chars(&cleaned))
+        let message = Self::append_trailers(&cleaned, trailers);
+        Ok(Self::strip_control_chars(&message))
+    }
+
+    /// Add the trailers `message` doesn't already contain, joining its footer
+    /// paragraph if it ends with one.
+    #[must_use]
+    pub fn append_trailers(message: &str, trailers: &[Trailer]) -> String {
+        let message = message.trim_end();
+        let missing: Vec<String> = trailers
+            .iter()
+            .map(Trailer::to_string)
+            .filter(|t| !message.lines().any(|l| l.trim() == t))
+            .collect();
+        if missing.is_empty() {
+            return message.to_string();
+        }
+
+        let last = message.rsplit("\n\n").next().unwrap_or_default();
+        let ends_with_footer = message.contains("\n\n")
+            && last.lines().all(|l| {
+                Trailer::parse(l).is_some()
+                    || l.starts_with("BREAKING CHANGE:")
+                    || l.starts_with("  ")
+            });
+        let separator = if ends_with_footer { "\n" } else { "\n\n" };
+        format!("{message}{separator}{}", missing.join("\n"))
     }
 
     /// Remove control characters (null bytes, escape sequences, carriage returns)
@@ -150,6 +228,14 @@ impl CommitSanitizer {
     }
 
     fn try_parse_json(raw: &str) -> std::result::Result<StructuredCommit, ()> {
+        Self::parse_json_object(raw).ok_or(())
+    }
+
+    /// Dig a JSON object out of free-form LLM output and deserialize it.
+    ///
+    /// Strips thought blocks, then prefers the object enclosing the first
+    /// `"type"` key before falling back to the first `{`.
+    pub(crate) fn parse_json_object<T: DeserializeOwned>(raw: &str) -> Option<T> {
         // Strip thought blocks first to avoid picking up braces inside thoughts
         let stripped = THOUGHT_BLOCK_REGEX.replace_all(raw, "");
         let stripped = UNCLOSED_THOUGHT_REGEX.replace(&stripped, "");
@@ -164,8 +250,8 @@ impl CommitSanitizer {
                 // Find the last '}' to handle trailing text
                 if let Some(end_brace) = json_candidate.rfind('}') {
                     let json = &json_candidate[..=end_brace];
-                    if let Ok(structured) = serde_json::from_str::<StructuredCommit>(json) {
-                        return Ok(structured);
+                    if let Ok(parsed) = serde_json::from_str::<T>(json) {
+                        return Some(parsed);
                     }
                 }
             }
@@ -176,13 +262,13 @@ impl CommitSanitizer {
             let json_candidate = &trimmed[start_brace..];
             if let Some(end_brace) = json_candidate.rfind('}') {
                 let json = &json_candidate[..=end_brace];
-                if let Ok(structured) = serde_json::from_str::<StructuredCommit>(json) {
-                    return Ok(structured);
+                if let Ok(parsed) = serde_json::from_str::<T>(json) {
+                    return Some(parsed);
                 }
             }
         }
 
-        Err(())
+        None
     }
 
     fn format_structured(s: &StructuredCommit, format: &CommitFormat) -> Result<String> {
@@ -271,8 +357,14 @@ impl CommitSanitizer {
             None
         };
 
-        let footer_section: Option<String> =
-            breaking_change.as_deref().map(Self::format_breaking_footer);
+        // Breaking change footer first, then trailers, as one paragraph
+        let footer: Vec<String> = breaking_change
+            .as_deref()
+            .map(Self::format_breaking_footer)
+            .into_iter()
+            .chain(s.trailers.iter().map(Trailer::to_string))
+            .collect();
+        let footer_section = (!footer.is_empty()).then(|| footer.join("\n"));
 
         let message = match (body_section, footer_section) {
             (Some(body), Some(footer)) => format!("{}\n\n{}\n\n{}", first_line, body, footer),
@@ -332,7 +424,8 @@ impl CommitSanitizer {
         cleaned
     }
 
-    fn validate_conventional(message: &str) -> Result<()> {
+    /// Check that the first line starts with a known conventional type.
+    pub fn validate_conventional(message: &str) -> Result<()> {
         let first_line = message.lines().next().unwrap_or("");
 
         // Check for type prefix
@@ -358,6 +451,43 @@ impl CommitSanitizer {
     pub fn parse_structured(raw: &str) -> Option<StructuredCommit> {
         Self::try_parse_json(raw).ok()
     }
+
+    /// Drop candidates that are near-identical to an earlier one, keeping the
+    /// original order.
+    ///
+    /// Messages are compared as sets of lowercase words (punctuation and
+    /// whitespace ignored); two count as duplicates when their Jaccard
+    /// similarity reaches [`DEDUP_SIMILARITY`].
+    #[must_use]
+    pub fn dedup_candidates(candidates: Vec<String>) -> Vec<String> {
+        let mut kept: Vec<(String, HashSet<String>)> = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            let words = Self::word_set(&candidate);
+            let duplicate = kept
+                .iter()
+                .any(|(_, other)| Self::jaccard(&words, other) >= DEDUP_SIMILARITY);
+            if !duplicate {
+                kept.push((candidate, words));
+            }
+        }
+        kept.into_iter().map(|(candidate, _)| candidate).collect()
+    }
+
+    fn word_set(message: &str) -> HashSet<String> {
+        message
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    }
+
+    fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+        let union = a.union(b).count();
+        if union == 0 {
+            return 1.0;
+        }
+        a.intersection(b).count() as f64 / union as f64
+    }
 }
 
 /// Post-generation evidence-based validation.
@@ -465,6 +595,52 @@ impl CommitValidator {
         violations
     }
 
+    /// Flag issue references the LLM made up: any `#123`, or a `KEY-123`
+    /// sharing a project key with `known_refs`, that is neither in
+    /// `known_refs` (the branch's issue) nor quoted in the `prompt`.
+    #[must_use]
+    pub fn validate_issue_refs(
+        commit: &StructuredCommit,
+        known_refs: &[String],
+        prompt: &str,
+    ) -> Vec<String> {
+        let text = [
+            Some(commit.subject.as_str()),
+            commit.body.as_deref(),
+            commit.breaking_change.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        let mut mentioned: Vec<String> = HASH_ISSUE_REGEX
+            .find_iter(&text)
+            .map(|m| m.as_str().to_string())
+            .collect();
+        for key in known_refs.iter().filter_map(|r| r.rsplit_once('-')) {
+            if let Ok(re) = Regex::new(&format!(r"\b{}-\d+\b", regex::escape(key.0))) {
+                mentioned.extend(re.find_iter(&text).map(|m| m.as_str().to_string()));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let invented: Vec<String> = mentioned
+            .into_iter()
+            .filter(|r| !known_refs.contains(r) && !prompt.contains(r.as_str()))
+            .filter(|r| seen.insert(r.clone()))
+            .collect();
+        if invented.is_empty() {
+            return Vec::new();
+        }
+        vec![format!(
+            "The message mentions {} but the branch doesn't reference {}. \
+             Don't invent issue numbers: remove them from the subject and body.",
+            invented.join(", "),
+            if invented.len() == 1 { "it" } else { "them" },
+        )]
+    }
+
     /// Check if a subject is too generic (vague verb + vague noun without specifics).
     ///
     /// Flags subjects like "update code", "improve things", "change functionality"
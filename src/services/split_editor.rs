@@ -0,0 +1,226 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::domain::{ChangeStatus, CommitType, StagedChanges};
+use crate::error::{Error, Result};
+use crate::services::analyzer::DiffHunk;
+use crate::services::splitter::CommitGroup;
+
+const HELP: &str = "\
+# Commits are created from top to bottom. Each `group` line starts a commit
+# and the file lines below it belong to that commit.
+#
+# Commands:
+#   group <type>[(<scope>)]  commit these files with this type and scope
+#   drop <type>[(<scope>)]   leave these files out of the split
+#
+# Move a file line to move the file to another group. Delete a `group` line
+# to merge its files into the group above. Reorder groups to reorder commits.
+# `path #1,3` holds only those hunks of a file.
+#
+# Files left out stay staged after the split commits.
+# An empty plan cancels the split.
+";
+
+/// Renders split groups as an editable plan file, in the spirit of
+/// `git rebase -i`, and parses the edited plan back into groups.
+pub struct SplitEditor;
+
+impl SplitEditor {
+    /// Plan file text for `groups`, followed by a commented help block.
+    #[must_use]
+    pub fn render(groups: &[CommitGroup]) -> String {
+        let mut out = String::new();
+        for group in groups {
+            let _ = write!(out, "group {}", group.commit_type.as_str());
+            if let Some(scope) = &group.scope {
+                let _ = write!(out, "({scope})");
+            }
+            out.push('\n');
+            for path in &group.files {
+                out.push_str(&path.display().to_string());
+                if let Some(indices) = group.hunks.get(path) {
+                    let list: Vec<String> = indices.iter().map(|i| (i + 1).to_string()).collect();
+                    let _ = write!(out, " #{}", list.join(","));
+                }
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+        out.push_str(HELP);
+        out
+    }
+
+    /// Parse an edited plan against the staged changes it was rendered from.
+    ///
+    /// Every file line must name a staged file, and no file or hunk may be
+    /// listed twice. Groups without files are skipped. A file whose hunks
+    /// all end up in one group is committed whole. Hunks can't be selected
+    /// in a file whose diff was truncated.
+    pub fn parse(plan: &str, changes: &StagedChanges) -> Result<Vec<CommitGroup>> {
+        // (group, whether it's dropped)
+        let mut groups: Vec<(CommitGroup, bool)> = Vec::new();
+        // Hunks claimed so far per file; `None` means the whole file
+        let mut claimed: BTreeMap<PathBuf, Option<Vec<usize>>> = BTreeMap::new();
+
+        for (n, raw) in plan.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |msg: String| Error::SplitPlan(format!("line {}: {msg}", n + 1));
+
+            let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            if matches!(command, "group" | "drop") {
+                let (commit_type, scope) = Self::parse_header(rest.trim()).map_err(err)?;
+                groups.push((
+                    CommitGroup {
+                        files: Vec::new(),
+                        commit_type,
+                        scope,
+                        hunks: BTreeMap::new(),
+                    },
+                    command == "drop",
+                ));
+                continue;
+            }
+
+            let Some((group, _)) = groups.last_mut() else {
+                return Err(err(format!("'{line}' is not inside a group")));
+            };
+            let (path, indices) = Self::parse_file_line(line).map_err(err)?;
+            let Some(file) = changes.files.iter().find(|f| f.path == path) else {
+                return Err(err(format!("'{}' is not staged", path.display())));
+            };
+
+            let hunk_count = DiffHunk::parse_from_diff(&file.diff).len();
+            if let Some(indices) = &indices {
+                if file.status != ChangeStatus::Modified || file.is_binary || hunk_count < 2 {
+                    return Err(err(format!("'{}' can't be split by hunk", path.display())));
+                }
+                // The diff was cut at `max_file_lines`, so hunks past the cut
+                // can't be numbered
+                if DiffHunk::count_changes(&file.diff) != (file.additions, file.deletions) {
+                    return Err(err(format!(
+                        "'{}' is too large to split by hunk",
+                        path.display()
+                    )));
+                }
+                if let Some(&i) = indices.iter().find(|&&i| i >= hunk_count) {
+                    return Err(err(format!(
+                        "'{}' has no hunk {} (it has {hunk_count})",
+                        path.display(),
+                        i + 1
+                    )));
+                }
+            }
+
+            let taken = claimed.entry(path.clone()).or_insert(Some(Vec::new()));
+            let overlaps = match (&*taken, &indices) {
+                (None, _) => true,
+                (Some(prev), None) => !prev.is_empty(),
+                (Some(prev), Some(new)) => new.iter().any(|i| prev.contains(i)),
+            };
+            if overlaps {
+                return Err(err(format!(
+                    "'{}' is listed more than once",
+                    path.display()
+                )));
+            }
+            match (taken, indices) {
+                (Some(prev), Some(new)) => {
+                    prev.extend(&new);
+                    group.hunks.entry(path.clone()).or_default().extend(new);
+                }
+                (taken, _) => *taken = None,
+            }
+            if !group.files.contains(&path) {
+                group.files.push(path);
+            }
+        }
+
+        Ok(groups
+            .into_iter()
+            .filter(|(group, dropped)| !dropped && !group.files.is_empty())
+            .map(|(mut group, _)| {
+                group.hunks.retain(|path, indices| {
+                    indices.sort_unstable();
+                    indices.dedup();
+                    indices.len() < Self::hunk_count(changes, path)
+                });
+                group
+            })
+            .collect())
+    }
+
+    /// Staged files that a plan leaves out of every group, in full or in part.
+    #[must_use]
+    pub fn left_out(groups: &[CommitGroup], changes: &StagedChanges) -> Vec<PathBuf> {
+        changes
+            .files
+            .iter()
+            .filter(|f| {
+                let listed: Vec<&CommitGroup> = groups
+                    .iter()
+                    .filter(|g| g.files.contains(&f.path))
+                    .collect();
+                if listed.iter().any(|g| !g.hunks.contains_key(&f.path)) {
+                    return false;
+                }
+                let hunks: usize = listed.iter().map(|g| g.hunks[&f.path].len()).sum();
+                listed.is_empty() || hunks < Self::hunk_count(changes, &f.path)
+            })
+            .map(|f| f.path.clone())
+            .collect()
+    }
+
+    /// `feat(scope)` or `feat` from a `group`/`drop` line.
+    fn parse_header(header: &str) -> std::result::Result<(CommitType, Option<String>), String> {
+        let (ty, scope) = match header.split_once('(') {
+            Some((ty, rest)) => {
+                let scope = rest
+                    .strip_suffix(')')
+                    .ok_or_else(|| format!("missing ')' in '{header}'"))?
+                    .trim();
+                (ty, (!scope.is_empty()).then(|| scope.to_string()))
+            }
+            None => (header, None),
+        };
+        let ty = ty.trim();
+        let commit_type = CommitType::parse(ty).ok_or_else(|| {
+            format!(
+                "unknown commit type '{ty}' (expected one of: {})",
+                CommitType::ALL.join(", ")
+            )
+        })?;
+        Ok((commit_type, scope))
+    }
+
+    /// A path, optionally followed by ` #1,3` to select 1-based hunks.
+    fn parse_file_line(line: &str) -> std::result::Result<(PathBuf, Option<Vec<usize>>), String> {
+        let Some((path, spec)) = line.rsplit_once(" #") else {
+            return Ok((PathBuf::from(line), None));
+        };
+        let indices = spec
+            .split(',')
+            .map(|s| match s.trim().parse::<usize>() {
+                Ok(n) if n > 0 => Ok(n - 1),
+                _ => Err(format!("invalid hunk number '{}'", s.trim())),
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok((PathBuf::from(path.trim_end()), Some(indices)))
+    }
+
+    fn hunk_count(changes: &StagedChanges, path: &Path) -> usize {
+        changes
+            .files
+            .iter()
+            .find(|f| f.path == path)
+            .map_or(0, |f| DiffHunk::parse_from_diff(&f.diff).len())
+    }
+}
@@ -2,10 +2,14 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::domain::{CodeSymbol, CommitType, FileCategory, FileChange, StagedChanges};
+use crate::domain::{
+    ChangeStatus, CodeSymbol, CommitType, FileCategory, FileChange, StagedChanges,
+};
+use crate::services::analyzer::DiffHunk;
 use crate::services::context::ContextBuilder;
 
 /// A logical group of files that belong in a single commit.
@@ -14,6 +18,26 @@ pub struct CommitGroup {
     pub files: Vec<PathBuf>,
     pub commit_type: CommitType,
     pub scope: Option<String>,
+    /// Files that contribute only some of their hunks to this group, mapped
+    /// to 0-based hunk indices in their staged diff. Files not listed here
+    /// are committed whole.
+    pub hunks: BTreeMap<PathBuf, Vec<usize>>,
+}
+
+impl CommitGroup {
+    /// Display label for one of the group's files, noting which hunks are
+    /// included (1-based) when the file is split across groups.
+    #[must_use]
+    pub fn file_label(&self, path: &Path) -> String {
+        match self.hunks.get(path) {
+            Some(indices) => {
+                let list: Vec<String> = indices.iter().map(|i| (i + 1).to_string()).collect();
+                let noun = if indices.len() == 1 { "hunk" } else { "hunks" };
+                format!("{} ({noun} {})", path.display(), list.join(", "))
+            }
+            None => path.display().to_string(),
+        }
+    }
 }
 
 /// Result of analyzing staged changes for potential splitting.
@@ -58,19 +82,34 @@ impl CommitSplitter {
     /// Analyze staged changes and determine if they should be split.
     ///
     /// Strategy:
-    /// 1. Separate files by category (source, test, docs, config/build)
-    /// 2. Group source files by diff-shape similarity (cross-file pattern detection)
-    /// 3. Merge groups connected by symbol dependencies
-    /// 4. Attach test files to matching source groups
-    /// 5. Keep docs and config/build as their own groups when mixed with source
+    /// 1. Break source files that mix unrelated hunks into per-concern units
+    /// 2. Separate units by category (source, test, docs, config/build)
+    /// 3. Group source units by diff-shape similarity (cross-file pattern detection)
+    /// 4. Merge groups connected by symbol dependencies
+    /// 5. Attach test files to matching source groups
+    /// 6. Keep docs and config/build as their own groups when mixed with source
     pub fn analyze(changes: &StagedChanges, symbols: &[CodeSymbol]) -> SplitSuggestion {
-        // Classify files by category
+        // Split files into hunk-level units. Paths that produced several units
+        // are tracked so symbols can be matched to the unit that touches them.
+        let units: Vec<FileChange> = changes
+            .files
+            .iter()
+            .flat_map(|f| Self::split_hunks(f, symbols))
+            .collect();
+        let mut seen = HashSet::new();
+        let split_paths: HashSet<PathBuf> = units
+            .iter()
+            .filter(|u| !seen.insert(u.path.as_path()))
+            .map(|u| u.path.clone())
+            .collect();
+
+        // Classify units by category
         let mut source_files: Vec<&FileChange> = Vec::new();
         let mut test_files: Vec<&FileChange> = Vec::new();
         let mut doc_files: Vec<&FileChange> = Vec::new();
         let mut config_build_files: Vec<&FileChange> = Vec::new();
 
-        for file in &changes.files {
+        for file in &units {
             match file.category {
                 FileCategory::Source => source_files.push(file),
                 FileCategory::Test => test_files.push(file),
@@ -92,7 +131,7 @@ impl CommitSplitter {
         // (files in the same shape group stay together even if in different modules)
 
         // Step 3: Merge groups connected by symbol dependencies
-        Self::merge_by_symbol_deps(&mut source_groups, symbols);
+        Self::merge_by_symbol_deps(&mut source_groups, symbols, &split_paths);
 
         // Step 4: Attach test files to matching source groups
         Self::attach_test_files(&mut source_groups, &test_files);
@@ -112,16 +151,35 @@ impl CommitSplitter {
         }
 
         // Step 6: Build CommitGroups with type/scope inference
-        let mut groups: Vec<CommitGroup> = Vec::new();
+        let mut sized: Vec<(usize, CommitGroup)> = Vec::new();
 
         for files in &all_groups {
-            let paths: Vec<PathBuf> = files.iter().map(|f| f.path.clone()).collect();
-            let sub_changes = changes.subset(&paths);
-            let sub_symbols: Vec<CodeSymbol> = symbols
-                .iter()
-                .filter(|s| paths.contains(&s.file))
-                .cloned()
-                .collect();
+            let mut paths: Vec<PathBuf> = Vec::new();
+            let mut hunks: BTreeMap<PathBuf, Vec<usize>> = BTreeMap::new();
+            for unit in files {
+                if !paths.contains(&unit.path) {
+                    paths.push(unit.path.clone());
+                }
+                if split_paths.contains(&unit.path)
+                    && let Some(original) = changes.files.iter().find(|f| f.path == unit.path)
+                {
+                    hunks
+                        .entry(unit.path.clone())
+                        .or_default()
+                        .extend(Self::hunk_indices(original, unit));
+                }
+            }
+            // A split file whose hunks all landed in this group is committed whole
+            hunks.retain(|path, indices| {
+                indices.sort_unstable();
+                changes
+                    .files
+                    .iter()
+                    .find(|f| &f.path == path)
+                    .is_some_and(|f| indices.len() < DiffHunk::parse_from_diff(&f.diff).len())
+            });
+
+            let (sub_changes, sub_symbols) = Self::subset(changes, symbols, &paths, &hunks);
 
             // Whitespace classification requires full build(); pass false here
             // since sub_symbols are not yet classified via classify_span_change.
@@ -129,20 +187,24 @@ impl CommitSplitter {
                 ContextBuilder::infer_commit_type(&sub_changes, &sub_symbols, false, false);
             let scope = ContextBuilder::infer_scope(&sub_changes);
 
-            groups.push(CommitGroup {
-                files: paths,
-                commit_type,
-                scope,
-            });
+            sized.push((
+                sub_changes.stats.insertions + sub_changes.stats.deletions,
+                CommitGroup {
+                    files: paths,
+                    commit_type,
+                    scope,
+                    hunks,
+                },
+            ));
         }
 
         // Step 7: Check if groups are actually different
-        if groups.len() >= 2 {
-            let first_type = groups[0].commit_type;
-            let first_scope = &groups[0].scope;
-            let all_same = groups
+        if sized.len() >= 2 {
+            let first_type = sized[0].1.commit_type;
+            let first_scope = &sized[0].1.scope;
+            let all_same = sized
                 .iter()
-                .all(|g| g.commit_type == first_type && &g.scope == first_scope);
+                .all(|(_, g)| g.commit_type == first_type && &g.scope == first_scope);
 
             if all_same {
                 return SplitSuggestion::SingleCommit;
@@ -150,13 +212,237 @@ impl CommitSplitter {
         }
 
         // Sort groups by total change size (largest first)
-        groups.sort_by(|a, b| {
-            let a_size = Self::group_change_size(a, changes);
-            let b_size = Self::group_change_size(b, changes);
-            b_size.cmp(&a_size)
-        });
+        sized.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+
+        SplitSuggestion::SuggestSplit(sized.into_iter().map(|(_, g)| g).collect())
+    }
+
+    /// The part of the staged changes and symbols that belongs to `group`.
+    /// Partially included files are reduced to the group's hunks.
+    #[must_use]
+    pub fn group_context(
+        group: &CommitGroup,
+        changes: &StagedChanges,
+        symbols: &[CodeSymbol],
+    ) -> (StagedChanges, Vec<CodeSymbol>) {
+        Self::subset(changes, symbols, &group.files, &group.hunks)
+    }
+
+    fn subset(
+        changes: &StagedChanges,
+        symbols: &[CodeSymbol],
+        paths: &[PathBuf],
+        hunks: &BTreeMap<PathBuf, Vec<usize>>,
+    ) -> (StagedChanges, Vec<CodeSymbol>) {
+        let mut sub = changes.subset(paths);
+        for file in &mut sub.files {
+            if let Some(indices) = hunks.get(&file.path) {
+                *file = Self::select_hunks(file, indices);
+            }
+        }
+        sub.stats.insertions = sub.files.iter().map(|f| f.additions).sum();
+        sub.stats.deletions = sub.files.iter().map(|f| f.deletions).sum();
+
+        let sub_symbols = symbols
+            .iter()
+            .filter(|s| {
+                sub.files.iter().any(|f| {
+                    f.path == s.file && (!hunks.contains_key(&f.path) || Self::touches(f, s))
+                })
+            })
+            .cloned()
+            .collect();
+
+        (sub, sub_symbols)
+    }
+
+    // ─── Hunk-level splitting ───
+
+    /// Break a modified source file into units of related hunks.
+    ///
+    /// Hunks are related when they touch the same symbol, when one mentions a
+    /// symbol the other touches, or when they share a diff shape and
+    /// vocabulary. Hunks outside any symbol (imports, constants) join the
+    /// unit they share the most vocabulary with. Returns the file unchanged
+    /// when everything is related, or when its diff was truncated.
+    fn split_hunks(file: &FileChange, symbols: &[CodeSymbol]) -> Vec<FileChange> {
+        let whole = vec![file.clone()];
+        if file.category != FileCategory::Source
+            || file.status != ChangeStatus::Modified
+            || file.is_binary
+        {
+            return whole;
+        }
+        let hunk_count = DiffHunk::parse_from_diff(&file.diff).len();
+        if hunk_count < 2 || DiffHunk::count_changes(&file.diff) != (file.additions, file.deletions)
+        {
+            return whole;
+        }
+
+        let hunks: Vec<FileChange> = (0..hunk_count)
+            .map(|i| Self::select_hunks(file, &[i]))
+            .collect();
+        let touched: Vec<HashSet<&str>> = hunks
+            .iter()
+            .map(|h| {
+                symbols
+                    .iter()
+                    .filter(|s| Self::touches(h, s))
+                    .map(|s| s.name.as_str())
+                    .collect()
+            })
+            .collect();
+        let tokens: Vec<HashSet<String>> =
+            hunks.iter().map(|h| Self::tokenize_diff(&h.diff)).collect();
+        let shapes: Vec<DiffFingerprint> = hunks.iter().map(Self::diff_fingerprint).collect();
+
+        let anchored: Vec<usize> = (0..hunk_count)
+            .filter(|&i| !touched[i].is_empty())
+            .collect();
+        if anchored.is_empty() {
+            return whole;
+        }
+
+        // Union-find over hunks, seeded by the ones that touch symbols
+        let mut root: Vec<usize> = (0..hunk_count).collect();
+        fn find(root: &mut [usize], mut i: usize) -> usize {
+            while root[i] != i {
+                root[i] = root[root[i]];
+                i = root[i];
+            }
+            i
+        }
+
+        for (n, &i) in anchored.iter().enumerate() {
+            for &j in &anchored[n + 1..] {
+                let mentions = |a: usize, b: usize| {
+                    touched[a]
+                        .iter()
+                        .any(|name| Self::changed_lines_mention(&hunks[b].diff, name))
+                };
+                let related = !touched[i].is_disjoint(&touched[j])
+                    || mentions(i, j)
+                    || mentions(j, i)
+                    || (shapes[i].is_similar(&shapes[j])
+                        && Self::jaccard_index(&tokens[i], &tokens[j]) > 0.4);
+                if related {
+                    let (a, b) = (find(&mut root, i), find(&mut root, j));
+                    root[b] = a;
+                }
+            }
+        }
+
+        for i in (0..hunk_count).filter(|i| touched[*i].is_empty()) {
+            let best = anchored
+                .iter()
+                .copied()
+                .max_by(|&a, &b| {
+                    Self::jaccard_index(&tokens[i], &tokens[a])
+                        .total_cmp(&Self::jaccard_index(&tokens[i], &tokens[b]))
+                        // Ties go to the earlier hunk
+                        .then(b.cmp(&a))
+                })
+                .unwrap_or(anchored[0]);
+            root[i] = find(&mut root, best);
+        }
+
+        let mut clusters: Vec<(usize, Vec<usize>)> = Vec::new();
+        for i in 0..hunk_count {
+            let r = find(&mut root, i);
+            match clusters.iter_mut().find(|(cr, _)| *cr == r) {
+                Some((_, members)) => members.push(i),
+                None => clusters.push((r, vec![i])),
+            }
+        }
+
+        if clusters.len() < 2 {
+            return whole;
+        }
+        clusters
+            .iter()
+            .map(|(_, indices)| Self::select_hunks(file, indices))
+            .collect()
+    }
 
-        SplitSuggestion::SuggestSplit(groups)
+    /// A copy of `file` reduced to the hunks at `indices`.
+    fn select_hunks(file: &FileChange, indices: &[usize]) -> FileChange {
+        let diff = DiffHunk::select(&file.diff, indices);
+        let (additions, deletions) = DiffHunk::count_changes(&diff);
+        FileChange {
+            diff: Arc::from(diff),
+            additions,
+            deletions,
+            ..file.clone()
+        }
+    }
+
+    /// Indices of `unit`'s hunks within the diff of the file it came from.
+    fn hunk_indices(original: &FileChange, unit: &FileChange) -> Vec<usize> {
+        let all = DiffHunk::parse_from_diff(&original.diff);
+        DiffHunk::parse_from_diff(&unit.diff)
+            .iter()
+            .filter_map(|h| {
+                all.iter()
+                    .position(|a| a.old_start == h.old_start && a.new_start == h.new_start)
+            })
+            .collect()
+    }
+
+    /// Whether the symbol's span covers one of the unit's added lines (for
+    /// added symbols) or removed lines (for removed ones). Context lines
+    /// don't count, so an untouched neighbour doesn't tie hunks together.
+    fn touches(unit: &FileChange, symbol: &CodeSymbol) -> bool {
+        if unit.path != symbol.file {
+            return false;
+        }
+        let within = |n: usize| (symbol.line..=symbol.end_line).contains(&n);
+        let (mut old, mut new) = (0, 0);
+        let mut in_hunk = false;
+
+        for line in unit.diff.lines() {
+            if let Some(hunk) = DiffHunk::parse_hunk_header(line) {
+                (old, new) = (hunk.old_start, hunk.new_start);
+                in_hunk = true;
+                continue;
+            }
+            if !in_hunk {
+                continue;
+            }
+            match line.as_bytes().first() {
+                Some(b'+') => {
+                    if symbol.is_added && within(new) {
+                        return true;
+                    }
+                    new += 1;
+                }
+                Some(b'-') => {
+                    if !symbol.is_added && within(old) {
+                        return true;
+                    }
+                    old += 1;
+                }
+                Some(b'\\') => {}
+                _ => {
+                    old += 1;
+                    new += 1;
+                }
+            }
+        }
+        false
+    }
+
+    /// Whether an added or removed line of `diff` contains `name` as a word.
+    /// Everything before the first hunk is file header; inside hunks,
+    /// `+++`/`---` lines are content.
+    fn changed_lines_mention(diff: &str, name: &str) -> bool {
+        diff.lines()
+            .skip_while(|l| !l.starts_with("@@"))
+            .filter(|l| l.starts_with('+') || l.starts_with('-'))
+            .any(|l| {
+                l[1..]
+                    .split(|c: char| !c.is_alphanumeric() && c != '_')
+                    .any(|word| word == name)
+            })
     }
 
     /// Compute a structural fingerprint of a diff for similarity comparison.
@@ -382,18 +668,25 @@ impl CommitSplitter {
     ///
     /// If file A removes a public symbol and file B adds a symbol with the same name,
     /// or if file A's diff references symbols from file B's group, merge them.
-    fn merge_by_symbol_deps(groups: &mut Vec<Vec<&FileChange>>, symbols: &[CodeSymbol]) {
+    fn merge_by_symbol_deps(
+        groups: &mut Vec<Vec<&FileChange>>,
+        symbols: &[CodeSymbol],
+        split_paths: &HashSet<PathBuf>,
+    ) {
         if groups.len() <= 1 {
             return;
         }
 
-        // Build a map: file path → group index
-        let mut file_to_group: HashMap<&Path, usize> = HashMap::new();
-        for (idx, group) in groups.iter().enumerate() {
-            for file in group {
-                file_to_group.insert(file.path.as_path(), idx);
-            }
-        }
+        // Group owning a symbol: the one holding its file, or for files split
+        // into hunk units, the one holding the unit that touches the symbol
+        let group_of = |symbol: &CodeSymbol| {
+            groups.iter().position(|group| {
+                group.iter().any(|f| {
+                    f.path == symbol.file
+                        && (!split_paths.contains(&f.path) || Self::touches(f, symbol))
+                })
+            })
+        };
 
         // Pre-index added symbols by name for fast lookup
         let added: Vec<_> = symbols.iter().filter(|s| s.is_added).collect();
@@ -410,10 +703,7 @@ impl CommitSplitter {
                 for add in matches {
                     if rem.kind == add.kind
                         && rem.file != add.file
-                        && let (Some(&g1), Some(&g2)) = (
-                            file_to_group.get(rem.file.as_path()),
-                            file_to_group.get(add.file.as_path()),
-                        )
+                        && let (Some(g1), Some(g2)) = (group_of(rem), group_of(add))
                         && g1 != g2
                     {
                         merge_pairs.push((g1.min(g2), g1.max(g2)));
@@ -451,7 +741,7 @@ impl CommitSplitter {
                 for call in &potential_calls {
                     if let Some(matches) = added_by_name.get(call.as_str()) {
                         for sym in matches {
-                            if let Some(&sym_group) = file_to_group.get(sym.file.as_path())
+                            if let Some(sym_group) = group_of(sym)
                                 && sym_group != idx
                             {
                                 merge_pairs.push((idx.min(sym_group), idx.max(sym_group)));
@@ -630,17 +920,6 @@ impl CommitSplitter {
             groups.push(standalone);
         }
     }
-
-    /// Calculate total change size for a group (for sorting).
-    fn group_change_size(group: &CommitGroup, changes: &StagedChanges) -> usize {
-        let group_files: HashSet<&PathBuf> = group.files.iter().collect();
-        changes
-            .files
-            .iter()
-            .filter(|f| group_files.contains(&f.path))
-            .map(|f| f.additions + f.deletions)
-            .sum()
-    }
 }
 
 /// Structural fingerprint of a diff for similarity comparison.
@@ -0,0 +1,157 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::services::history::CommitRecord;
+
+/// Maximum number of original subjects listed in the prompt.
+const MAX_PROMPT_COMMITS: usize = 50;
+
+/// A git trailer line such as `Co-authored-by: Name <mail>` or `Refs: #12`.
+static TRAILER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9-]*: \S").unwrap());
+
+/// Synthesizes one conventional commit from a set of squashed commits.
+pub struct SquashBuilder;
+
+impl SquashBuilder {
+    /// Parse the `SQUASH_MSG` file written by `git merge --squash` into the
+    /// squashed commits, in the order git lists them.
+    #[must_use]
+    pub fn parse_squash_msg(text: &str) -> Vec<CommitRecord> {
+        let mut commits: Vec<(String, Vec<&str>)> = Vec::new();
+        let mut in_message = false;
+
+        for line in text.lines() {
+            if let Some(sha) = line.strip_prefix("commit ")
+                && !sha.is_empty()
+                && sha.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                commits.push((sha.to_string(), Vec::new()));
+                in_message = false;
+                continue;
+            }
+            let Some((_, message)) = commits.last_mut() else {
+                continue;
+            };
+            // `Author:`/`Date:` headers end at the first blank line
+            if !in_message {
+                in_message = line.is_empty();
+                continue;
+            }
+            if line.is_empty() {
+                message.push("");
+            } else if let Some(text) = line.strip_prefix("    ") {
+                message.push(text);
+            }
+        }
+
+        commits
+            .into_iter()
+            .filter_map(|(sha, lines)| {
+                let message = lines.join("\n");
+                let message = message.trim();
+                let (subject, body) = message.split_once('\n').unwrap_or((message, ""));
+                (!subject.is_empty()).then(|| CommitRecord {
+                    sha,
+                    subject: subject.trim().to_string(),
+                    body: body.trim().to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Append the squashed commits to the generation prompt, so the message
+    /// describes their combined effect instead of listing them.
+    #[must_use]
+    pub fn build_prompt(original_prompt: &str, commits: &[CommitRecord]) -> String {
+        let mut subjects: Vec<String> = commits
+            .iter()
+            .take(MAX_PROMPT_COMMITS)
+            .map(|c| format!("  - {}", c.subject))
+            .collect();
+        let omitted = commits.len().saturating_sub(MAX_PROMPT_COMMITS);
+        if omitted > 0 {
+            subjects.push(format!("  ... and {omitted} more"));
+        }
+
+        let breaking: Vec<String> = commits
+            .iter()
+            .flat_map(CommitRecord::breaking_notes)
+            .map(|n| format!("- {n}"))
+            .collect();
+        let breaking_section = if breaking.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "\n\nBREAKING CHANGES DECLARED IN COMMITS:\n{}",
+                breaking.join("\n")
+            )
+        };
+
+        format!(
+            "{original_prompt}\n\n---\nThese changes squash the following commits:\n{}{breaking_section}\n\nWrite ONE message for their combined effect. Don't list the commits or repeat work-in-progress subjects.\n\nRespond with ONLY the JSON object.",
+            subjects.join("\n")
+        )
+    }
+
+    /// Trailers from the final paragraph of each commit message, such as
+    /// `Co-authored-by` and `Refs`, deduplicated in order. Breaking change
+    /// footers are left to [`CommitRecord::breaking_notes`].
+    #[must_use]
+    pub fn collect_trailers(commits: &[CommitRecord]) -> Vec<String> {
+        let mut seen = HashSet::new();
+        commits
+            .iter()
+            .filter_map(|c| c.body.rsplit("\n\n").next())
+            .flat_map(|paragraph| {
+                let lines: Vec<&str> = paragraph.lines().map(str::trim_end).collect();
+                if lines.is_empty() || !lines.iter().all(|l| Self::is_footer_line(l)) {
+                    return Vec::new();
+                }
+                lines
+            })
+            .filter(|l| TRAILER_REGEX.is_match(l) && !Self::is_breaking_footer(l))
+            .filter(|l| seen.insert(l.to_lowercase()))
+            .map(String::from)
+            .collect()
+    }
+
+    /// Add the originals' breaking change footers and trailers that the
+    /// generated message doesn't already carry, as its final paragraph.
+    #[must_use]
+    pub fn finalize(message: &str, commits: &[CommitRecord]) -> String {
+        let message = message.trim_end();
+        let mut seen = HashSet::new();
+        let footers: Vec<String> = commits
+            .iter()
+            .flat_map(CommitRecord::breaking_notes)
+            .map(|n| format!("BREAKING CHANGE: {n}"))
+            .chain(Self::collect_trailers(commits))
+            .filter(|f| !message.contains(f.as_str()) && seen.insert(f.clone()))
+            .collect();
+        if footers.is_empty() {
+            return message.to_string();
+        }
+
+        // Join an existing footer paragraph rather than starting a new one
+        let last = message.rsplit("\n\n").next().unwrap_or_default();
+        let ends_with_footers = message.contains("\n\n") && last.lines().all(Self::is_footer_line);
+        let separator = if ends_with_footers { "\n" } else { "\n\n" };
+        format!("{message}{separator}{}", footers.join("\n"))
+    }
+
+    /// A trailer, a breaking change footer, or an indented continuation of one.
+    fn is_footer_line(line: &str) -> bool {
+        TRAILER_REGEX.is_match(line) || Self::is_breaking_footer(line) || line.starts_with(' ')
+    }
+
+    fn is_breaking_footer(line: &str) -> bool {
+        line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+    }
+}
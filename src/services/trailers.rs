@@ -0,0 +1,75 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use regex::Regex;
+
+use crate::config::TrailerConfig;
+use crate::domain::Trailer;
+
+/// Builds the trailers added to generated messages from the branch name,
+/// the pair roster and the git identity.
+pub struct TrailerBuilder;
+
+impl TrailerBuilder {
+    /// The issue a branch name refers to, using `pattern`'s first capture
+    /// group (or its whole match). Bare numbers are returned as `#123`.
+    #[must_use]
+    pub fn issue_ref(branch: &str, pattern: &str) -> Option<String> {
+        if pattern.is_empty() {
+            return None;
+        }
+        let caps = Regex::new(pattern).ok()?.captures(branch)?;
+        let found = caps.get(1).or_else(|| caps.get(0))?.as_str();
+        if found.is_empty() {
+            None
+        } else if found.chars().all(|c| c.is_ascii_digit()) {
+            Some(format!("#{found}"))
+        } else {
+            Some(found.to_string())
+        }
+    }
+
+    /// Issue, co-author and sign-off trailers, in that order. `identity` is
+    /// the committer as `Name <email>`, used when `sign_off` is set.
+    #[must_use]
+    pub fn build(
+        config: &TrailerConfig,
+        branch: Option<&str>,
+        identity: Option<&str>,
+    ) -> Vec<Trailer> {
+        let mut trailers = Vec::new();
+
+        if let Some(issue) = branch.and_then(|b| Self::issue_ref(b, &config.issue_pattern)) {
+            trailers.push(Trailer::new(&config.issue_trailer, issue));
+        }
+
+        for handle in &config.pair {
+            if let Some(person) = config.roster.get(handle) {
+                let trailer = Trailer::new("Co-authored-by", person);
+                if !trailers.contains(&trailer) {
+                    trailers.push(trailer);
+                }
+            }
+        }
+
+        if config.sign_off
+            && let Some(identity) = identity
+        {
+            trailers.push(Trailer::new("Signed-off-by", identity));
+        }
+
+        trailers
+    }
+
+    /// The issue references among `trailers` (`Refs`, `Closes` and the like),
+    /// which are the only ones a generated message may mention.
+    #[must_use]
+    pub fn issue_refs(trailers: &[Trailer], config: &TrailerConfig) -> Vec<String> {
+        trailers
+            .iter()
+            .filter(|t| t.key == config.issue_trailer)
+            .map(|t| t.value.clone())
+            .collect()
+    }
+}
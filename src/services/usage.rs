@@ -0,0 +1,377 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::config::{Provider, ResolvedProfile, UsageConfig};
+use crate::error::Result;
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Token counts a provider reported for one request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// One LLM request attempt, whether it completed or not.
+#[derive(Debug, Clone)]
+pub struct RequestUsage {
+    /// Profile name, or the provider kind for the top-level settings
+    pub profile: String,
+    pub kind: Provider,
+    pub model: String,
+    pub cloud: bool,
+    /// `None` when the server didn't report usage
+    pub tokens: Option<TokenUsage>,
+    /// Wall time of this attempt
+    pub elapsed: Duration,
+}
+
+#[derive(Default)]
+struct Log {
+    requests: Vec<RequestUsage>,
+    /// How many of `requests` were handed out by `take_unflushed`
+    flushed: usize,
+}
+
+/// Requests completed during this run, shared by every provider built for
+/// it. Clones share the same log.
+#[derive(Clone, Default)]
+pub struct UsageLog {
+    inner: Arc<Mutex<Log>>,
+}
+
+impl UsageLog {
+    pub fn record(&self, usage: RequestUsage) {
+        self.lock().requests.push(usage);
+    }
+
+    /// Every request recorded so far.
+    #[must_use]
+    pub fn requests(&self) -> Vec<RequestUsage> {
+        self.lock().requests.clone()
+    }
+
+    /// Requests recorded since the last call, for appending to the ledger.
+    #[must_use]
+    pub fn take_unflushed(&self) -> Vec<RequestUsage> {
+        let mut log = self.lock();
+        let pending = log.requests[log.flushed..].to_vec();
+        log.flushed = log.requests.len();
+        pending
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Log> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A provider's handle on the usage log, labelled with its profile.
+pub(crate) struct UsageMeter {
+    log: UsageLog,
+    profile: String,
+    kind: Provider,
+    model: String,
+    cloud: bool,
+}
+
+impl UsageMeter {
+    /// A meter with a log of its own, until `attach` replaces it.
+    pub(crate) fn new(profile: &ResolvedProfile) -> Self {
+        Self {
+            log: UsageLog::default(),
+            profile: profile.name.clone(),
+            kind: profile.kind,
+            model: profile.model.clone(),
+            cloud: profile.is_cloud(),
+        }
+    }
+
+    pub(crate) fn attach(&mut self, log: UsageLog) {
+        self.log = log;
+    }
+
+    pub(crate) fn is_cloud(&self) -> bool {
+        self.cloud
+    }
+
+    /// Meter one attempt that began at `start`. It's recorded when the
+    /// returned guard drops, so failed and interrupted attempts are logged
+    /// with whatever usage the server reported before they ended.
+    pub(crate) fn attempt(&self, start: Instant) -> MeteredAttempt<'_> {
+        MeteredAttempt {
+            meter: self,
+            start,
+            tokens: None,
+        }
+    }
+
+    fn record(&self, tokens: Option<TokenUsage>, elapsed: Duration) {
+        match tokens {
+            Some(t) => debug!(
+                profile = %self.profile,
+                model = %self.model,
+                input_tokens = t.input_tokens,
+                output_tokens = t.output_tokens,
+                elapsed_ms = elapsed.as_millis() as u64,
+                "request usage"
+            ),
+            None => debug!(
+                profile = %self.profile,
+                model = %self.model,
+                elapsed_ms = elapsed.as_millis() as u64,
+                "server reported no token usage"
+            ),
+        }
+        self.log.record(RequestUsage {
+            profile: self.profile.clone(),
+            kind: self.kind,
+            model: self.model.clone(),
+            cloud: self.cloud,
+            tokens,
+            elapsed,
+        });
+    }
+}
+
+/// Usage of one request attempt, recorded on drop.
+pub(crate) struct MeteredAttempt<'a> {
+    meter: &'a UsageMeter,
+    start: Instant,
+    pub(crate) tokens: Option<TokenUsage>,
+}
+
+impl Drop for MeteredAttempt<'_> {
+    fn drop(&mut self) {
+        self.meter.record(self.tokens, self.start.elapsed());
+    }
+}
+
+/// One line of the usage ledger.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// Unix seconds
+    pub timestamp: u64,
+    pub profile: String,
+    pub provider: String,
+    pub model: String,
+    pub cloud: bool,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// `None` when the model has no entry in `[usage.prices]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+impl LedgerEntry {
+    #[must_use]
+    pub fn new(usage: &RequestUsage, config: &UsageConfig, timestamp: u64) -> Self {
+        let tokens = usage.tokens.unwrap_or_default();
+        Self {
+            timestamp,
+            profile: usage.profile.clone(),
+            provider: usage.kind.to_string(),
+            model: usage.model.clone(),
+            cloud: usage.cloud,
+            input_tokens: tokens.input_tokens,
+            output_tokens: tokens.output_tokens,
+            cost: config.cost(&usage.model, tokens.input_tokens, tokens.output_tokens),
+        }
+    }
+}
+
+/// Append-only JSON Lines log of every request's token counts and cost.
+///
+/// Unreadable lines are skipped, so a truncated write never hides the rest
+/// of the history.
+pub struct UsageLedger {
+    path: PathBuf,
+}
+
+impl UsageLedger {
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, entries: &[LedgerEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(entry).expect("LedgerEntry serializes to JSON"));
+            lines.push('\n');
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // One write per flush keeps concurrent runs from interleaving lines
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(lines.as_bytes())?;
+        Ok(())
+    }
+
+    /// All entries, oldest first. Empty if the ledger doesn't exist yet.
+    #[must_use]
+    pub fn entries(&self) -> Vec<LedgerEntry> {
+        fs::read_to_string(&self.path)
+            .map(|s| {
+                s.lines()
+                    .filter_map(|l| serde_json::from_str(l).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Summed usage over a set of ledger entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost: f64,
+    /// Requests to models without a configured price
+    pub unpriced: u64,
+}
+
+impl UsageTotals {
+    #[must_use]
+    pub fn sum<'a>(entries: impl IntoIterator<Item = &'a LedgerEntry>) -> Self {
+        let mut totals = Self::default();
+        for entry in entries {
+            totals.requests += 1;
+            totals.input_tokens += entry.input_tokens;
+            totals.output_tokens += entry.output_tokens;
+            match entry.cost {
+                Some(cost) => totals.cost += cost,
+                None => totals.unpriced += 1,
+            }
+        }
+        totals
+    }
+
+    #[must_use]
+    pub fn tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// The first budget in `config` that cloud usage in `entries` has reached,
+/// described for the user. Days and months are UTC.
+#[must_use]
+pub fn budget_exceeded(config: &UsageConfig, entries: &[LedgerEntry], now: u64) -> Option<String> {
+    let cloud_since =
+        |start: u64| UsageTotals::sum(entries.iter().filter(|e| e.cloud && e.timestamp >= start));
+    let today = cloud_since(day_start(now));
+    let month = cloud_since(month_start(now));
+
+    if let Some(limit) = config.daily_tokens
+        && today.tokens() >= limit
+    {
+        return Some(format!(
+            "daily token budget of {limit} reached ({} used today)",
+            today.tokens()
+        ));
+    }
+    if let Some(limit) = config.monthly_tokens
+        && month.tokens() >= limit
+    {
+        return Some(format!(
+            "monthly token budget of {limit} reached ({} used this month)",
+            month.tokens()
+        ));
+    }
+    if let Some(limit) = config.daily_cost
+        && today.cost >= limit
+    {
+        return Some(format!(
+            "daily cost budget of {limit:.2} reached ({:.2} spent today)",
+            today.cost
+        ));
+    }
+    if let Some(limit) = config.monthly_cost
+        && month.cost >= limit
+    {
+        return Some(format!(
+            "monthly cost budget of {limit:.2} reached ({:.2} spent this month)",
+            month.cost
+        ));
+    }
+    None
+}
+
+/// Current time in Unix seconds.
+#[must_use]
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Start of the UTC day containing `timestamp`.
+#[must_use]
+pub fn day_start(timestamp: u64) -> u64 {
+    timestamp - timestamp % SECS_PER_DAY
+}
+
+/// Start of the UTC month containing `timestamp`.
+#[must_use]
+pub fn month_start(timestamp: u64) -> u64 {
+    let (year, month, _) = civil_from_days(timestamp / SECS_PER_DAY);
+    days_from_civil(year, month, 1) * SECS_PER_DAY
+}
+
+/// `YYYY-MM-DD` of the UTC day containing `timestamp`.
+#[must_use]
+pub fn format_date(timestamp: u64) -> String {
+    let (year, month, day) = civil_from_days(timestamp / SECS_PER_DAY);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// Gregorian calendar conversions for days since 1970-01-01, after Howard
+// Hinnant's `civil_from_days` / `days_from_civil`. Dates before the epoch
+// never occur here, so everything stays unsigned.
+
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -197,6 +197,33 @@ diff --git a/src/lib.rs b/src/lib.rs
     assert_eq!(hunks[2].new_count, 6);
 }
 
+#[test]
+fn select_keeps_header_and_chosen_hunks() {
+    let diff = "diff --git a/src/lib.rs b/src/lib.rs\r\n--- a/src/lib.rs\r\n+++ b/src/lib.rs\r\n\
+                @@ -1,1 +1,2 @@\r\n a\r\n+b\r\n\
+                @@ -20,1 +21,2 @@\r\n c\r\n+d\r\n\
+                @@ -40,1 +42,2 @@\r\n e\r\n+f";
+    let patch = DiffHunk::select(diff, &[0, 2]);
+
+    assert!(patch.starts_with("diff --git a/src/lib.rs b/src/lib.rs\r\n--- a/src/lib.rs"));
+    assert!(patch.contains("@@ -1,1 +1,2 @@\r\n a\r\n+b\r\n@@ -40,1"));
+    assert!(!patch.contains("+d"));
+    assert!(patch.ends_with("+f\n"), "patch must end with a newline");
+    assert_eq!(DiffHunk::parse_from_diff(&patch).len(), 2);
+}
+
+#[test]
+fn count_changes_treats_header_lookalikes_in_hunks_as_content() {
+    let diff = "diff --git a/schema.sql b/schema.sql\n--- a/schema.sql\n+++ b/schema.sql\n\
+                @@ -1,3 +1,3 @@\n--- drop the old table\n+++counter\n CREATE TABLE t;\n\
+                -x\n+y\n\
+                diff --git a/b.txt b/b.txt\n--- a/b.txt\n+++ b/b.txt\n\
+                @@ -1 +1 @@\n-b\n+c\n";
+
+    assert_eq!(DiffHunk::count_changes(diff), (3, 3));
+    assert_eq!(DiffHunk::count_changes(""), (0, 0));
+}
+
 // ─── DiffHunk intersection tests ────────────────────────────────────────────
 
 #[test]
@@ -0,0 +1,160 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use commitbee::config::{CommitFormat, Config, ResolvedProfile};
+use commitbee::domain::Trailer;
+use commitbee::services::cache::ResponseCache;
+
+fn profile() -> ResolvedProfile {
+    Config::default().active_profile()
+}
+
+fn key(profile: &ResolvedProfile, prompt: &str) -> String {
+    ResponseCache::key(profile, &CommitFormat::default(), &[], "system", prompt)
+}
+
+// ─── Key derivation ──────────────────────────────────────────────────────────
+
+#[test]
+fn key_is_stable_for_identical_requests() {
+    assert_eq!(key(&profile(), "diff"), key(&profile(), "diff"));
+    assert_eq!(key(&profile(), "diff").len(), 64);
+}
+
+#[test]
+fn key_changes_with_each_input() {
+    let base = key(&profile(), "diff");
+
+    assert_ne!(base, key(&profile(), "other diff"));
+
+    let mut model = profile();
+    model.model = "llama3:8b".into();
+    assert_ne!(base, key(&model, "diff"));
+
+    let mut temperature = profile();
+    temperature.temperature = 0.7;
+    assert_ne!(base, key(&temperature, "diff"));
+
+    let mut num_predict = profile();
+    num_predict.num_predict = 8192;
+    assert_ne!(base, key(&num_predict, "diff"));
+
+    let mut think = profile();
+    think.think = true;
+    assert_ne!(base, key(&think, "diff"));
+
+    let mut host = profile();
+    host.base_url = Some("http://gpu-box:11434".into());
+    assert_ne!(base, key(&host, "diff"));
+
+    assert_ne!(
+        base,
+        ResponseCache::key(
+            &profile(),
+            &CommitFormat::default(),
+            &[Trailer::new("Signed-off-by", "A <a@example.com>")],
+            "system",
+            "diff"
+        )
+    );
+
+    let format = CommitFormat {
+        include_scope: false,
+        ..CommitFormat::default()
+    };
+    assert_ne!(
+        base,
+        ResponseCache::key(&profile(), &format, &[], "system", "diff")
+    );
+    assert_ne!(
+        base,
+        ResponseCache::key(&profile(), &CommitFormat::default(), &[], "other", "diff")
+    );
+}
+
+#[test]
+fn key_fields_do_not_run_together() {
+    let format = CommitFormat::default();
+    assert_ne!(
+        ResponseCache::key(&profile(), &format, &[], "ab", "c"),
+        ResponseCache::key(&profile(), &format, &[], "a", "bc")
+    );
+}
+
+// ─── Storage ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn extend_appends_without_duplicates() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = ResponseCache::new(dir.path().join("responses"));
+    let key = key(&profile(), "diff");
+
+    assert!(cache.get(&key).is_empty());
+
+    cache.extend(&key, &["feat: a".into()]).unwrap();
+    cache
+        .extend(&key, &["feat: a".into(), "fix: b".into()])
+        .unwrap();
+    assert_eq!(cache.get(&key), ["feat: a", "fix: b"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn entries_are_private_to_the_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let cache_dir = dir.path().join("responses");
+    let cache = ResponseCache::new(&cache_dir);
+    let key = key(&profile(), "diff");
+    cache.extend(&key, &["feat: a".into()]).unwrap();
+
+    let mode = |p: &std::path::Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&cache_dir), 0o700);
+    assert_eq!(mode(&cache_dir.join(format!("{key}.json"))), 0o600);
+
+    // No temp files are left behind
+    let names: Vec<_> = std::fs::read_dir(&cache_dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(names.len(), 1);
+}
+
+#[test]
+fn corrupt_entry_is_a_miss() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = ResponseCache::new(dir.path());
+    let key = key(&profile(), "diff");
+
+    std::fs::write(dir.path().join(format!("{key}.json")), "not json").unwrap();
+    assert!(cache.get(&key).is_empty());
+
+    // A write replaces the corrupt entry
+    cache.extend(&key, &["chore: c".into()]).unwrap();
+    assert_eq!(cache.get(&key), ["chore: c"]);
+}
+
+#[test]
+fn clear_removes_entries_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = ResponseCache::new(dir.path());
+    assert_eq!(cache.clear().unwrap(), 0);
+
+    cache
+        .extend(&key(&profile(), "one"), &["feat: a".into()])
+        .unwrap();
+    cache
+        .extend(&key(&profile(), "two"), &["feat: b".into()])
+        .unwrap();
+    std::fs::write(dir.path().join("README"), "keep me").unwrap();
+
+    assert_eq!(cache.clear().unwrap(), 2);
+    assert!(cache.get(&key(&profile(), "one")).is_empty());
+    assert!(dir.path().join("README").exists());
+
+    // Clearing a cache dir that was never created is not an error
+    let missing = ResponseCache::new(dir.path().join("missing"));
+    assert_eq!(missing.clear().unwrap(), 0);
+}
@@ -0,0 +1,197 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use commitbee::services::changelog::{BumpLevel, ChangelogBuilder};
+use commitbee::services::history::{CommitRecord, HistoryService};
+
+fn record(sha: &str, subject: &str, body: &str) -> CommitRecord {
+    CommitRecord {
+        sha: sha.to_string(),
+        subject: subject.to_string(),
+        body: body.to_string(),
+    }
+}
+
+// ─── Semver Bump ─────────────────────────────────────────────────────────────
+
+#[test]
+fn bump_level_from_commit_types() {
+    let patch = ChangelogBuilder::build("a..b", &[record("1", "fix: x", "")]);
+    assert_eq!(patch.bump, BumpLevel::Patch);
+
+    let minor = ChangelogBuilder::build(
+        "a..b",
+        &[record("1", "fix: x", ""), record("2", "feat: y", "")],
+    );
+    assert_eq!(minor.bump, BumpLevel::Minor);
+
+    let major = ChangelogBuilder::build(
+        "a..b",
+        &[
+            record("1", "feat: y", ""),
+            record("2", "chore: z", "BREAKING CHANGE: drops Node 18"),
+        ],
+    );
+    assert_eq!(major.bump, BumpLevel::Major);
+
+    let none = ChangelogBuilder::build(
+        "a..b",
+        &[record("1", "docs: x", ""), record("2", "Update README", "")],
+    );
+    assert_eq!(none.bump, BumpLevel::None);
+}
+
+#[test]
+fn bump_apply_to_versions() {
+    assert_eq!(BumpLevel::Major.apply("v1.2.3").as_deref(), Some("v2.0.0"));
+    assert_eq!(BumpLevel::Minor.apply("1.2.3").as_deref(), Some("1.3.0"));
+    assert_eq!(
+        BumpLevel::Patch.apply("v1.2.3-rc.1").as_deref(),
+        Some("v1.2.4")
+    );
+    // Pre-1.0: breaking changes bump the minor version
+    assert_eq!(BumpLevel::Major.apply("v0.6.0").as_deref(), Some("v0.7.0"));
+    assert_eq!(BumpLevel::None.apply("v1.2.3"), None);
+    assert_eq!(BumpLevel::Minor.apply("main"), None);
+    assert_eq!(BumpLevel::Minor.apply("v1.2"), None);
+}
+
+// ─── Grouping ────────────────────────────────────────────────────────────────
+
+#[test]
+fn build_groups_by_section_and_scope() {
+    let commits = vec![
+        record("aaaaaaa1", "feat(cli): add changelog command", ""),
+        record("aaaaaaa2", "fix: handle empty ranges", ""),
+        record("aaaaaaa3", "feat(api): expose bump level", ""),
+        record("aaaaaaa4", "feat: support JSON output", ""),
+        record("aaaaaaa5", "ci: cache cargo registry", ""),
+        record("aaaaaaa6", "Initial import", ""),
+    ];
+    let changelog = ChangelogBuilder::build("v1.0.0..HEAD", &commits);
+
+    let titles: Vec<&str> = changelog
+        .sections
+        .iter()
+        .map(|s| s.title.as_str())
+        .collect();
+    assert_eq!(titles, ["Added", "Fixed", "Maintenance", "Other"]);
+
+    let added: Vec<(Option<&str>, &str)> = changelog.sections[0]
+        .entries
+        .iter()
+        .map(|e| (e.scope.as_deref(), e.description.as_str()))
+        .collect();
+    assert_eq!(
+        added,
+        [
+            (Some("api"), "expose bump level"),
+            (Some("cli"), "add changelog command"),
+            (None, "support JSON output"),
+        ]
+    );
+
+    let other = &changelog.sections[3].entries[0];
+    assert!(other.commit_type.is_none());
+    assert_eq!(other.description, "Initial import");
+}
+
+#[test]
+fn render_markdown_keep_a_changelog() {
+    let commits = vec![
+        record("1111111aaa", "feat(config): add profiles", ""),
+        record(
+            "2222222bbb",
+            "refactor(config)!: rename provider keys",
+            "BREAKING CHANGE: `provider` moved under `[providers]`",
+        ),
+        record("3333333ccc", "fix: trim trailing whitespace", ""),
+    ];
+    let mut changelog = ChangelogBuilder::build("v1.4.0..HEAD", &commits);
+    changelog.version = changelog.bump.apply("v1.4.0");
+    changelog.date = Some("2026-10-16".into());
+
+    insta::assert_snapshot!(changelog.render_markdown(), @r"
+    ## [v2.0.0] - 2026-10-16
+
+    ### Breaking Changes
+
+    - **config:** `provider` moved under `[providers]` (`2222222`)
+
+    ### Added
+
+    - **config:** add profiles (`1111111`)
+
+    ### Changed
+
+    - **config:** rename provider keys (`2222222`)
+
+    ### Fixed
+
+    - trim trailing whitespace (`3333333`)
+    ");
+}
+
+#[test]
+fn render_markdown_unreleased_without_version() {
+    let changelog = ChangelogBuilder::build("main..HEAD", &[record("1", "docs: fix typo", "")]);
+    assert!(changelog.render_markdown().starts_with("## [Unreleased]\n"));
+}
+
+#[test]
+fn json_output_shape() {
+    let changelog = ChangelogBuilder::build(
+        "a..b",
+        &[record("abcdef123", "feat(cli)!: drop --legacy", "")],
+    );
+    let json = serde_json::to_value(&changelog).unwrap();
+    assert_eq!(json["bump"], "major");
+    assert_eq!(json["version"], serde_json::Value::Null);
+    assert_eq!(json["sections"][0]["title"], "Added");
+    assert_eq!(json["sections"][0]["entries"][0]["type"], "feat");
+    assert_eq!(json["sections"][0]["entries"][0]["breaking"], true);
+    assert_eq!(json["breaking_changes"][0]["description"], "drop --legacy");
+}
+
+// ─── Git Integration ─────────────────────────────────────────────────────────
+
+fn git(path: &std::path::Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+#[tokio::test]
+async fn fetch_commits_skips_merges() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path();
+    git(path, &["init", "-b", "main"]);
+    git(path, &["config", "user.email", "test@test.com"]);
+    git(path, &["config", "user.name", "Test"]);
+    git(path, &["commit", "--allow-empty", "-m", "chore: init"]);
+    git(path, &["tag", "v0.1.0"]);
+    git(path, &["checkout", "-b", "topic"]);
+    git(path, &["commit", "--allow-empty", "-m", "feat: topic work"]);
+    git(path, &["checkout", "main"]);
+    git(path, &["commit", "--allow-empty", "-m", "fix: main work"]);
+    git(
+        path,
+        &["merge", "--no-ff", "-m", "Merge branch 'topic'", "topic"],
+    );
+
+    let commits = HistoryService::fetch_commits(path, "v0.1.0..HEAD")
+        .await
+        .unwrap();
+    let subjects: Vec<&str> = commits.iter().map(|c| c.subject.as_str()).collect();
+    assert_eq!(subjects.len(), 2);
+    assert!(subjects.contains(&"feat: topic work"));
+    assert!(subjects.contains(&"fix: main work"));
+
+    let changelog = ChangelogBuilder::build("v0.1.0..HEAD", &commits);
+    assert_eq!(changelog.bump.apply("v0.1.0").as_deref(), Some("v0.2.0"));
+}
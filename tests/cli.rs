@@ -0,0 +1,407 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// ─── Helpers ─────────────────────────────────────────────────────────────────
+
+fn git(path: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+fn commit_file(path: &Path, file: &str, content: &str, message: &str) {
+    std::fs::write(path.join(file), content).unwrap();
+    git(path, &["add", file]);
+    git(path, &["commit", "-q", "-m", message]);
+}
+
+fn repo() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path();
+    git(path, &["init", "-q", "-b", "main"]);
+    git(path, &["config", "user.email", "test@test.com"]);
+    git(path, &["config", "user.name", "Test"]);
+    git(path, &["config", "commit.gpgsign", "false"]);
+    dir
+}
+
+/// An Ollama server that answers every generate request with `response`.
+async fn mock_ollama(response: &str) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/tags"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({ "models": [{ "name": "qwen3:4b" }] })),
+        )
+        .mount(&server)
+        .await;
+    let body = [
+        json!({ "response": response, "done": false }).to_string(),
+        json!({ "response": "", "done": true }).to_string(),
+    ]
+    .join("\n");
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Run commitbee in `repo` against `ollama`, isolated from the user's config.
+fn commitbee(repo: &Path, home: &Path, ollama: &MockServer, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_commitbee"))
+        .args(args)
+        .current_dir(repo)
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home)
+        .env("XDG_CACHE_HOME", home)
+        .env("XDG_DATA_HOME", home)
+        .env("COMMITBEE_PROVIDER", "ollama")
+        .env("COMMITBEE_MODEL", "qwen3:4b")
+        .env("COMMITBEE_OLLAMA_HOST", ollama.uri())
+        .env_remove("COMMITBEE_LOG")
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+// ─── Reword ──────────────────────────────────────────────────────────────────
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reword_rewrites_only_the_range() {
+    let repo = repo();
+    let path = repo.path();
+    commit_file(path, "a.txt", "a\n", "init");
+    commit_file(path, "b.txt", "b\n", "wip");
+    let base = git(path, &["rev-parse", "HEAD~1"]);
+    let tree = git(path, &["rev-parse", "HEAD^{tree}"]);
+
+    let home = tempfile::tempdir().unwrap();
+    let ollama =
+        mock_ollama(r#"{"type":"feat","scope":null,"subject":"add b.txt","body":null}"#).await;
+    let output = commitbee(path, home.path(), &ollama, &["--yes", "reword", "HEAD"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    assert_eq!(git(path, &["log", "-1", "--format=%s"]), "feat: add b.txt");
+    assert_eq!(git(path, &["rev-parse", "HEAD~1"]), base);
+    assert_eq!(git(path, &["rev-parse", "HEAD^{tree}"]), tree);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reword_refuses_commits_merged_from_another_branch() {
+    let repo = repo();
+    let path = repo.path();
+    commit_file(path, "a.txt", "a\n", "init");
+    git(path, &["checkout", "-q", "-b", "side"]);
+    commit_file(path, "side.txt", "side\n", "side wip");
+    let side = git(path, &["rev-parse", "HEAD"]);
+    git(path, &["checkout", "-q", "main"]);
+    commit_file(path, "main.txt", "main\n", "main wip");
+    git(
+        path,
+        &["merge", "-q", "--no-ff", "-m", "Merge side", "side"],
+    );
+    commit_file(path, "c.txt", "c\n", "after merge");
+    let merge = git(path, &["rev-parse", "HEAD~1"]);
+    let head = git(path, &["rev-parse", "HEAD"]);
+
+    let home = tempfile::tempdir().unwrap();
+    let ollama =
+        mock_ollama(r#"{"type":"feat","scope":null,"subject":"add c.txt","body":null}"#).await;
+
+    // Only reachable through the merge's second parent
+    let output = commitbee(path, home.path(), &ollama, &["--yes", "reword", &side]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("merge commit"),
+        "{}",
+        stderr(&output)
+    );
+    assert_eq!(git(path, &["rev-parse", "HEAD"]), head);
+
+    // A range pulls in the side branch too
+    let output = commitbee(
+        path,
+        home.path(),
+        &ollama,
+        &["--yes", "reword", "HEAD~2..HEAD"],
+    );
+    assert!(!output.status.success());
+    assert_eq!(git(path, &["rev-parse", "HEAD"]), head);
+    assert!(
+        ollama
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .all(|r| r.url.path() != "/api/generate"),
+        "no message should be generated for a rejected range"
+    );
+
+    // Commits after the merge can still be reworded; the merge is kept
+    let output = commitbee(path, home.path(), &ollama, &["--yes", "reword", "HEAD"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(git(path, &["log", "-1", "--format=%s"]), "feat: add c.txt");
+    assert_eq!(git(path, &["rev-parse", "HEAD~1"]), merge);
+}
+
+// ─── Split ───────────────────────────────────────────────────────────────────
+
+#[tokio::test(flavor = "multi_thread")]
+async fn split_auto_without_a_split_commits_the_single_message() {
+    let repo = repo();
+    let path = repo.path();
+    commit_file(path, "a.txt", "a\n", "init");
+    std::fs::write(path.join("a.txt"), "a\nb\n").unwrap();
+    git(path, &["add", "a.txt"]);
+
+    let home = tempfile::tempdir().unwrap();
+    let ollama =
+        mock_ollama(r#"{"type":"feat","scope":null,"subject":"add b line","body":null}"#).await;
+    // No --yes and no terminal: --split auto alone must not prompt
+    let output = commitbee(path, home.path(), &ollama, &["--split", "auto"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(git(path, &["log", "-1", "--format=%s"]), "feat: add b line");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn split_auto_keeps_unstaged_edits_to_split_files() {
+    let repo = repo();
+    let path = repo.path();
+    std::fs::create_dir_all(path.join("src/auth")).unwrap();
+    std::fs::create_dir_all(path.join("src/db")).unwrap();
+    std::fs::write(
+        path.join("src/auth/login.rs"),
+        "pub fn login() -> bool {\n    true\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        path.join("src/db/pool.rs"),
+        "pub fn pool() -> u32 {\n    1\n}\n",
+    )
+    .unwrap();
+    git(path, &["add", "-A"]);
+    git(path, &["commit", "-q", "-m", "init"]);
+
+    let login = "pub fn login() -> bool {\n    true\n}\n\n\
+                 pub fn logout() {\n    let session = 1;\n    drop(session);\n}\n\n\
+                 pub fn refresh() -> u32 {\n    let token = 2;\n    token\n}\n";
+    let pool = "pub fn pool() -> u32 {\n    4\n}\n";
+    std::fs::write(path.join("src/auth/login.rs"), login).unwrap();
+    std::fs::write(path.join("src/db/pool.rs"), pool).unwrap();
+    git(path, &["add", "-A"]);
+
+    // Unstaged edits on top of the staged ones, in the same files
+    let login_wip = format!("{login}// wip login\n");
+    let pool_wip = format!("{pool}// wip pool\n");
+    std::fs::write(path.join("src/auth/login.rs"), &login_wip).unwrap();
+    std::fs::write(path.join("src/db/pool.rs"), &pool_wip).unwrap();
+
+    let home = tempfile::tempdir().unwrap();
+    let ollama =
+        mock_ollama(r#"{"type":"refactor","scope":null,"subject":"split work","body":null}"#).await;
+    let output = commitbee(path, home.path(), &ollama, &["--split", "auto"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    assert_eq!(git(path, &["rev-list", "--count", "HEAD"]), "3");
+    assert_eq!(
+        git(path, &["show", "HEAD~1", "--format=", "--name-only"])
+            + "\n"
+            + &git(path, &["show", "HEAD", "--format=", "--name-only"]),
+        "src/auth/login.rs\nsrc/db/pool.rs"
+    );
+    // The commits hold exactly what was staged
+    assert_eq!(
+        git(path, &["show", "HEAD:src/auth/login.rs"]),
+        login.trim_end()
+    );
+    assert_eq!(git(path, &["show", "HEAD:src/db/pool.rs"]), pool.trim_end());
+    // Nothing is left staged and the unstaged edits survive in the work tree
+    assert_eq!(git(path, &["diff", "--cached", "--name-only"]), "");
+    assert_eq!(
+        std::fs::read_to_string(path.join("src/auth/login.rs")).unwrap(),
+        login_wip
+    );
+    assert_eq!(
+        std::fs::read_to_string(path.join("src/db/pool.rs")).unwrap(),
+        pool_wip
+    );
+    assert_eq!(
+        git(path, &["diff", "--name-only"]),
+        "src/auth/login.rs\nsrc/db/pool.rs"
+    );
+}
+
+// ─── Changelog ───────────────────────────────────────────────────────────────
+
+#[tokio::test(flavor = "multi_thread")]
+async fn changelog_rejects_symmetric_difference_range() {
+    let repo = repo();
+    let path = repo.path();
+    commit_file(path, "a.txt", "a\n", "feat: first");
+    git(path, &["tag", "v1.0.0"]);
+    commit_file(path, "b.txt", "b\n", "fix: second");
+
+    let home = tempfile::tempdir().unwrap();
+    let ollama = mock_ollama("").await;
+    let output = commitbee(path, home.path(), &ollama, &["changelog", "v1.0.0...HEAD"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("<from>..<to>"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = commitbee(path, home.path(), &ollama, &["changelog", "v1.0.0..HEAD"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("second"));
+}
+
+// ─── Project config ──────────────────────────────────────────────────────────
+
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread")]
+async fn project_config_cannot_replace_user_profiles_or_network() {
+    let repo = repo();
+    let home = tempfile::tempdir().unwrap();
+    let ollama = mock_ollama("").await;
+
+    let user_dir = home.path().join("commitbee");
+    std::fs::create_dir_all(&user_dir).unwrap();
+    std::fs::write(
+        user_dir.join("config.toml"),
+        format!(
+            "[network]\n\
+             proxy = \"http://user-proxy.invalid:3128\"\n\
+             no_proxy = \"127.0.0.1,localhost\"\n\
+             [providers.cloud]\n\
+             kind = \"ollama\"\n\
+             base_url = \"{}\"\n\
+             model = \"qwen3:4b\"\n",
+            ollama.uri()
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        repo.path().join(".commitbee.toml"),
+        "[network]\n\
+         proxy = \"http://evil.invalid:1\"\n\
+         [providers.cloud]\n\
+         base_url = \"http://evil.invalid:2\"\n\
+         [providers.extra]\n\
+         kind = \"ollama\"\n",
+    )
+    .unwrap();
+
+    let output = commitbee(
+        repo.path(),
+        home.path(),
+        &ollama,
+        &["--provider", "cloud", "doctor"],
+    );
+    let stderr = stderr(&output);
+    assert!(stderr.contains(&ollama.uri()), "{stderr}");
+    assert!(stderr.contains("user-proxy.invalid"), "{stderr}");
+    assert!(!stderr.contains("evil.invalid"), "{stderr}");
+
+    // Profiles defined only by the project are not selectable
+    let output = commitbee(
+        repo.path(),
+        home.path(),
+        &ollama,
+        &["--provider", "extra", "config"],
+    );
+    assert!(!output.status.success());
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread")]
+async fn no_proxy_without_proxy_is_rejected() {
+    let repo = repo();
+    let home = tempfile::tempdir().unwrap();
+    let ollama = mock_ollama("").await;
+
+    let user_dir = home.path().join("commitbee");
+    std::fs::create_dir_all(&user_dir).unwrap();
+    std::fs::write(
+        user_dir.join("config.toml"),
+        "[network]\nno_proxy = \"localhost\"\n",
+    )
+    .unwrap();
+
+    let output = commitbee(repo.path(), home.path(), &ollama, &["config"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("NO_PROXY"), "{}", stderr(&output));
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread")]
+async fn cost_budget_requires_prices_for_cloud_profiles() {
+    let repo = repo();
+    let home = tempfile::tempdir().unwrap();
+    let ollama = mock_ollama("").await;
+
+    let user_dir = home.path().join("commitbee");
+    std::fs::create_dir_all(&user_dir).unwrap();
+    let config = "fallback = [\"cloud\"]\n\
+                  [providers.cloud]\n\
+                  kind = \"openai\"\n\
+                  model = \"gpt-4o\"\n\
+                  [usage]\n\
+                  daily_cost = 1.0\n";
+    std::fs::write(user_dir.join("config.toml"), config).unwrap();
+
+    let output = commitbee(repo.path(), home.path(), &ollama, &["config"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("[usage.prices.\"gpt-4o\"]"),
+        "{}",
+        stderr(&output)
+    );
+
+    // The local primary needs no price
+    std::fs::write(
+        user_dir.join("config.toml"),
+        format!("{config}[usage.prices.\"gpt-4o\"]\ninput = 2.5\noutput = 10.0\n"),
+    )
+    .unwrap();
+    let output = commitbee(repo.path(), home.path(), &ollama, &["config"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+// ─── Lint ────────────────────────────────────────────────────────────────────
+
+#[tokio::test(flavor = "multi_thread")]
+async fn commit_msg_hook_failure_points_to_warn_mode() {
+    let repo = repo();
+    let path = repo.path();
+    commit_file(path, "a.txt", "a\n", "init");
+    std::fs::write(path.join(".commitbee.toml"), "lint_mode = \"error\"\n").unwrap();
+    std::fs::write(path.join("MSG"), "Fixed stuff.\n").unwrap();
+
+    let home = tempfile::tempdir().unwrap();
+    let ollama = mock_ollama("").await;
+    let output = commitbee(path, home.path(), &ollama, &["hook", "lint", "MSG"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("lint_mode = \"warn\""),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -6,18 +6,24 @@
 //!
 //! Uses `wiremock` to mock HTTP endpoints so no real LLM servers are needed.
 
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
-use commitbee::config::{CommitFormat, Config, Provider};
+use commitbee::config::{Auth, CommitFormat, Config, Provider};
 use commitbee::error::Error;
-use commitbee::services::llm::SYSTEM_PROMPT;
 use commitbee::services::llm::anthropic::AnthropicProvider;
 use commitbee::services::llm::ollama::OllamaProvider;
 use commitbee::services::llm::openai::OpenAiProvider;
-use commitbee::services::sanitizer::CommitSanitizer;
+use commitbee::services::llm::{SYSTEM_PROMPT, create_provider};
+use commitbee::services::sanitizer::{CommitSanitizer, StructuredCommit};
+use commitbee::services::usage::{TokenUsage, UsageLog};
 
 // ─── Test helpers ────────────────────────────────────────────────────────────
 
@@ -72,6 +78,62 @@ async fn drain_tokens(mut rx: mpsc::Receiver<String>) -> Vec<String> {
     tokens
 }
 
+/// A server that starts every streamed response with `first_line`, stalls
+/// for `stall` and then drops the connection. Returns its URL and a count of
+/// requests served.
+fn broken_stream_server(first_line: &'static str, stall: Duration) -> (String, Arc<AtomicUsize>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let served = Arc::new(AtomicUsize::new(0));
+    let count = Arc::clone(&served);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let count = Arc::clone(&count);
+            std::thread::spawn(move || {
+                read_request(&mut stream);
+                count.fetch_add(1, Ordering::SeqCst);
+                let chunk = format!("{first_line}\n");
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{chunk}\r\n",
+                    chunk.len(),
+                );
+                let _ = stream.write_all(response.as_bytes());
+                let _ = stream.flush();
+                std::thread::sleep(stall);
+                // Closing without the final chunk breaks the stream
+            });
+        }
+    });
+    (url, served)
+}
+
+/// Read an HTTP request's headers and `Content-Length` body.
+fn read_request(stream: &mut std::net::TcpStream) {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    while let Ok(n) = stream.read(&mut buf) {
+        if n == 0 {
+            return;
+        }
+        request.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&request);
+        if let Some(end) = text.find("\r\n\r\n") {
+            let length = text[..end]
+                .lines()
+                .find_map(|l| {
+                    l.to_ascii_lowercase()
+                        .strip_prefix("content-length:")
+                        .and_then(|v| v.trim().parse::<usize>().ok())
+                })
+                .unwrap_or(0);
+            if request.len() >= end + 4 + length {
+                return;
+            }
+        }
+    }
+}
+
 // ─── Ollama health check ─────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -89,7 +151,7 @@ async fn ollama_health_check_success() {
         .mount(&server)
         .await;
 
-    let provider = OllamaProvider::new(&ollama_config(&server.uri())).unwrap();
+    let provider = OllamaProvider::new(&ollama_config(&server.uri()).active_profile()).unwrap();
     let models = provider.health_check().await.unwrap();
 
     assert_eq!(models.len(), 2);
@@ -100,7 +162,8 @@ async fn ollama_health_check_success() {
 #[tokio::test]
 async fn ollama_health_check_connection_refused() {
     // Use a port that is almost certainly not listening
-    let provider = OllamaProvider::new(&ollama_config("http://127.0.0.1:1")).unwrap();
+    let provider =
+        OllamaProvider::new(&ollama_config("http://127.0.0.1:1").active_profile()).unwrap();
     let result = provider.health_check().await;
 
     assert!(result.is_err(), "expected error for connection refused");
@@ -128,7 +191,7 @@ async fn ollama_model_not_found() {
         .mount(&server)
         .await;
 
-    let provider = OllamaProvider::new(&ollama_config(&server.uri())).unwrap();
+    let provider = OllamaProvider::new(&ollama_config(&server.uri()).active_profile()).unwrap();
     let result = provider.verify_model().await;
 
     assert!(result.is_err(), "expected error when model is not found");
@@ -164,12 +227,12 @@ async fn ollama_streaming_response() {
         .mount(&server)
         .await;
 
-    let provider = OllamaProvider::new(&ollama_config(&server.uri())).unwrap();
+    let provider = OllamaProvider::new(&ollama_config(&server.uri()).active_profile()).unwrap();
     let (tx, rx) = mpsc::channel(32);
     let cancel = CancellationToken::new();
 
     let result = provider
-        .generate("test prompt", SYSTEM_PROMPT, tx, cancel)
+        .generate("test prompt", SYSTEM_PROMPT, None, tx, cancel)
         .await
         .unwrap();
 
@@ -195,12 +258,12 @@ async fn ollama_server_error() {
         .mount(&server)
         .await;
 
-    let provider = OllamaProvider::new(&ollama_config(&server.uri())).unwrap();
+    let provider = OllamaProvider::new(&ollama_config(&server.uri()).active_profile()).unwrap();
     let (tx, _rx) = mpsc::channel(32);
     let cancel = CancellationToken::new();
 
     let result = provider
-        .generate("test prompt", SYSTEM_PROMPT, tx, cancel)
+        .generate("test prompt", SYSTEM_PROMPT, None, tx, cancel)
         .await;
 
     assert!(result.is_err(), "expected error for 500 response");
@@ -239,12 +302,12 @@ async fn openai_streaming_response() {
         .mount(&server)
         .await;
 
-    let provider = OpenAiProvider::new(&openai_config(&server.uri())).unwrap();
+    let provider = OpenAiProvider::new(&openai_config(&server.uri()).active_profile()).unwrap();
     let (tx, rx) = mpsc::channel(32);
     let cancel = CancellationToken::new();
 
     let result = provider
-        .generate("test prompt", SYSTEM_PROMPT, tx, cancel)
+        .generate("test prompt", SYSTEM_PROMPT, None, tx, cancel)
         .await
         .unwrap();
 
@@ -272,7 +335,7 @@ async fn openai_unauthorized() {
         .mount(&server)
         .await;
 
-    let provider = OpenAiProvider::new(&openai_config(&server.uri())).unwrap();
+    let provider = OpenAiProvider::new(&openai_config(&server.uri()).active_profile()).unwrap();
     let result = provider.verify_connection().await;
 
     assert!(result.is_err(), "expected error for 401 response");
@@ -289,6 +352,188 @@ async fn openai_unauthorized() {
     }
 }
 
+// ─── OpenAI-compatible profile without a key ─────────────────────────────────
+
+#[tokio::test]
+async fn openai_profile_without_key_omits_authorization() {
+    let server = MockServer::start().await;
+
+    let body = [
+        r#"data: {"choices":[{"delta":{"content":"fix: keyless"},"finish_reason":"stop"}]}"#,
+        "",
+        "data: [DONE]",
+        "",
+    ]
+    .join("\n");
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&server)
+        .await;
+
+    let toml_str = format!(
+        r#"
+profile = "local"
+
+[providers.local]
+kind = "openai"
+base_url = "{}/v1/"
+model = "qwen2.5-coder"
+"#,
+        server.uri()
+    );
+    let config: Config = toml::from_str(&toml_str).unwrap();
+    let profile = config.active_profile();
+    assert!(profile.api_key.is_none());
+
+    let provider = OpenAiProvider::new(&profile).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    let result = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, "fix: keyless");
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(!requests[0].headers.contains_key("authorization"));
+    let sent: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(sent["model"], "qwen2.5-coder");
+}
+
+// ─── Custom auth header and extra headers ────────────────────────────────────
+
+#[tokio::test]
+async fn openai_profile_sends_key_in_custom_header() {
+    let server = MockServer::start().await;
+    mount_openai_completion(&server, "fix: via gateway").await;
+
+    let toml_str = format!(
+        r#"
+profile = "gateway"
+
+[providers.gateway]
+kind = "openai"
+base_url = "{}/v1"
+model = "gpt-4o-mini"
+auth = "header"
+auth_header = "api-key"
+
+[providers.gateway.headers]
+X-Team-Id = "platform"
+"#,
+        server.uri()
+    );
+    let config: Config = toml::from_str(&toml_str).unwrap();
+    let mut profile = config.active_profile();
+    assert_eq!(profile.auth, Auth::Header("api-key".into()));
+    profile.api_key = Some("gateway-key".into());
+
+    let provider = OpenAiProvider::new(&profile).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    let result = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, "fix: via gateway");
+
+    let requests = server.received_requests().await.unwrap();
+    let headers = &requests[0].headers;
+    assert_eq!(headers["api-key"], "gateway-key");
+    assert_eq!(headers["x-team-id"], "platform");
+    assert!(!headers.contains_key("authorization"));
+}
+
+#[tokio::test]
+async fn anthropic_profile_with_bearer_auth() {
+    let server = MockServer::start().await;
+
+    let body = [
+        r#"data: {"type":"content_block_delta","delta":{"type":"text_delta","text":"fix: bearer"}}"#,
+        "",
+        r#"data: {"type":"message_stop"}"#,
+        "",
+    ]
+    .join("\n");
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&server)
+        .await;
+
+    let toml_str = format!(
+        r#"
+[providers.proxy]
+kind = "anthropic"
+base_url = "{}/v1"
+model = "claude-sonnet-4-20250514"
+auth = "bearer"
+"#,
+        server.uri()
+    );
+    let config: Config = toml::from_str(&toml_str).unwrap();
+    let mut profile = config.named_profile("proxy").unwrap();
+    profile.api_key = Some("proxy-key".into());
+
+    let provider = AnthropicProvider::new(&profile).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let headers = &requests[0].headers;
+    assert_eq!(headers["authorization"], "Bearer proxy-key");
+    assert!(!headers.contains_key("x-api-key"));
+}
+
+#[test]
+fn unset_header_variable_is_a_config_error() {
+    let toml_str = r#"
+[providers.gateway]
+kind = "openai"
+base_url = "http://localhost:1/v1"
+model = "m"
+
+[providers.gateway.headers]
+X-Team-Id = "env:COMMITBEE_TEST_UNSET_TEAM_ID"
+"#;
+    let config: Config = toml::from_str(toml_str).unwrap();
+    let err = OpenAiProvider::new(&config.named_profile("gateway").unwrap())
+        .err()
+        .unwrap();
+    match err {
+        Error::Config(message) => {
+            assert!(
+                message.contains("COMMITBEE_TEST_UNSET_TEAM_ID"),
+                "{message}"
+            )
+        }
+        other => panic!("expected Config error, got: {other:?}"),
+    }
+}
+
 // ─── Anthropic streaming response ─────────────────────────────────────────────
 
 #[tokio::test]
@@ -315,12 +560,13 @@ async fn anthropic_streaming_response() {
         .mount(&server)
         .await;
 
-    let provider = AnthropicProvider::new(&anthropic_config(&server.uri())).unwrap();
+    let provider =
+        AnthropicProvider::new(&anthropic_config(&server.uri()).active_profile()).unwrap();
     let (tx, rx) = mpsc::channel(32);
     let cancel = CancellationToken::new();
 
     let result = provider
-        .generate("test prompt", SYSTEM_PROMPT, tx, cancel)
+        .generate("test prompt", SYSTEM_PROMPT, None, tx, cancel)
         .await
         .unwrap();
 
@@ -345,7 +591,7 @@ async fn anthropic_verify_missing_key() {
         ..Config::default()
     };
 
-    let provider = AnthropicProvider::new(&config).unwrap();
+    let provider = AnthropicProvider::new(&config.active_profile()).unwrap();
     let result = provider.verify_connection().await;
 
     assert!(result.is_err(), "expected error for missing API key");
@@ -374,12 +620,13 @@ async fn anthropic_server_error() {
         .mount(&server)
         .await;
 
-    let provider = AnthropicProvider::new(&anthropic_config(&server.uri())).unwrap();
+    let provider =
+        AnthropicProvider::new(&anthropic_config(&server.uri()).active_profile()).unwrap();
     let (tx, _rx) = mpsc::channel(32);
     let cancel = CancellationToken::new();
 
     let result = provider
-        .generate("test prompt", SYSTEM_PROMPT, tx, cancel)
+        .generate("test prompt", SYSTEM_PROMPT, None, tx, cancel)
         .await;
 
     assert!(result.is_err(), "expected error for 500 response");
@@ -396,6 +643,877 @@ async fn anthropic_server_error() {
     }
 }
 
+// ─── Fallback chain ──────────────────────────────────────────────────────────
+
+/// Ollama on a closed port as the primary, falling back to `fallback` profiles.
+fn fallback_config(local_url: &str, fallback: &[&str]) -> Config {
+    let toml_str = format!(
+        r#"
+provider = "ollama"
+model = "qwen3.5:4b"
+ollama_host = "http://127.0.0.1:1"
+timeout_secs = 5
+fallback = {fallback:?}
+
+[providers.cloud]
+kind = "anthropic"
+base_url = "https://api.example.com/v1"
+model = "claude-sonnet-4-20250514"
+
+[providers.local]
+kind = "openai"
+base_url = "{local_url}/v1"
+model = "qwen2.5-coder"
+"#
+    );
+    toml::from_str(&toml_str).unwrap()
+}
+
+async fn mount_openai_completion(server: &MockServer, content: &str) {
+    let body = [
+        format!(
+            r#"data: {{"choices":[{{"delta":{{"content":"{content}"}},"finish_reason":"stop"}}]}}"#
+        ),
+        String::new(),
+        "data: [DONE]".into(),
+        String::new(),
+    ]
+    .join("\n");
+
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"data":[]}"#))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn fallback_chain_fails_over_when_primary_unreachable() {
+    let server = MockServer::start().await;
+    mount_openai_completion(&server, "fix: from fallback").await;
+
+    let provider = create_provider(&fallback_config(&server.uri(), &["local"])).unwrap();
+    assert_eq!(provider.name(), "ollama");
+    assert!(provider.fallback_label().is_none());
+
+    provider.verify().await.unwrap();
+    assert_eq!(provider.name(), "local");
+    assert_eq!(
+        provider.fallback_label().as_deref(),
+        Some("local (qwen2.5-coder)")
+    );
+
+    let (tx, _rx) = mpsc::channel(32);
+    let result = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, "fix: from fallback");
+}
+
+#[tokio::test]
+async fn fallback_chain_exhausted_returns_primary_error() {
+    // Both hops point at a closed port
+    let config = fallback_config("http://127.0.0.1:1", &["local"]);
+
+    let provider = create_provider(&config).unwrap();
+    let err = provider.verify().await.unwrap_err();
+    assert!(
+        matches!(err, Error::OllamaNotRunning { .. }),
+        "expected the primary's error, got: {err:?}"
+    );
+}
+
+#[tokio::test]
+async fn fallback_chain_skips_remote_hops_for_sensitive_diffs() {
+    let server = MockServer::start().await;
+    mount_openai_completion(&server, "fix: kept local").await;
+
+    // Only remote fallbacks: nothing eligible once secrets are approved
+    let provider = create_provider(&fallback_config(&server.uri(), &["cloud"])).unwrap();
+    provider.mark_sensitive();
+    let err = provider.verify().await.unwrap_err();
+    assert!(
+        matches!(err, Error::OllamaNotRunning { .. }),
+        "expected the primary's error, got: {err:?}"
+    );
+
+    // The remote hop is skipped in favour of the local one behind it
+    let provider = create_provider(&fallback_config(&server.uri(), &["cloud", "local"])).unwrap();
+    provider.mark_sensitive();
+    provider.verify().await.unwrap();
+    assert_eq!(provider.name(), "local");
+}
+
+#[tokio::test]
+async fn fallback_chain_keeps_a_stream_that_was_already_shown() {
+    let server = MockServer::start().await;
+    mount_openai_completion(&server, "fix: from fallback").await;
+
+    // The primary streams a token, then stalls past its timeout
+    let (url, served) = broken_stream_server(
+        r#"{"response":"feat: half","done":false}"#,
+        Duration::from_secs(3),
+    );
+    let mut config = fallback_config(&server.uri(), &["local"]);
+    config.ollama_host = url;
+    config.timeout_secs = 1;
+    config.retry.max_attempts = 1;
+    let provider = create_provider(&config).unwrap();
+
+    // Failing over would print a second message after the first one
+    let (tx, rx) = mpsc::channel(32);
+    let err = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::ProviderUnreachable { .. }), "{err:?}");
+    assert_eq!(drain_tokens(rx).await, vec!["feat: half"]);
+    assert_eq!(provider.name(), "ollama");
+
+    // Nothing shown yet: fail over as usual
+    let (tx, rx) = mpsc::channel(32);
+    drop(rx);
+    let result = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, "fix: from fallback");
+    assert_eq!(provider.name(), "local");
+    assert_eq!(served.load(Ordering::SeqCst), 2);
+}
+
+// ─── Retries ─────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn openai_retries_throttled_request_after_retry_after() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("Retry-After", "0")
+                .set_body_string("slow down"),
+        )
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    mount_openai_completion(&server, "feat: add retries").await;
+
+    let config = openai_config(&format!("{}/v1", server.uri()));
+    let provider = OpenAiProvider::new(&config.active_profile()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    let result = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result, "feat: add retries");
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn retries_stop_at_max_attempts() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(529).insert_header("Retry-After", "0"))
+        .mount(&server)
+        .await;
+
+    let mut config = anthropic_config(&server.uri());
+    config.retry.max_attempts = 2;
+    let provider = AnthropicProvider::new(&config.active_profile()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    let err = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, Error::Provider { ref message, .. } if message.contains("529")),
+        "expected the last HTTP error, got: {err:?}"
+    );
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn retry_after_too_large_for_a_duration_gives_up() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(
+            ResponseTemplate::new(503).insert_header("Retry-After", "99999999999999999999"),
+        )
+        .mount(&server)
+        .await;
+
+    let provider = OllamaProvider::new(&ollama_config(&server.uri()).active_profile()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    let err = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, Error::Provider { ref message, .. } if message.contains("503")),
+        "expected the HTTP error, got: {err:?}"
+    );
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn retry_after_http_date_is_honored() {
+    let server = MockServer::start().await;
+
+    // A date in the past: retry right away
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("Retry-After", "Wed, 21 Oct 2015 07:28:00 GMT"),
+        )
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    // A date past the deadline: give up
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(503)
+                .insert_header("Retry-After", "Fri, 31 Dec 9999 23:59:59 GMT"),
+        )
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    mount_openai_completion(&server, "feat: add retries").await;
+
+    let config = openai_config(&format!("{}/v1", server.uri()));
+    let provider = OpenAiProvider::new(&config.active_profile()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    let err = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, Error::Provider { ref message, .. } if message.contains("503")),
+        "expected the HTTP error, got: {err:?}"
+    );
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn client_errors_and_long_retry_after_are_not_retried() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("bad request"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    // Retry-After beyond the deadline: give up instead of waiting
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "600"))
+        .mount(&server)
+        .await;
+
+    let provider = OllamaProvider::new(&ollama_config(&server.uri()).active_profile()).unwrap();
+    for _ in 0..2 {
+        let (tx, _rx) = mpsc::channel(32);
+        provider
+            .generate(
+                "test prompt",
+                SYSTEM_PROMPT,
+                None,
+                tx,
+                CancellationToken::new(),
+            )
+            .await
+            .unwrap_err();
+    }
+
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn stream_broken_after_shown_tokens_is_not_retried() {
+    let (url, served) =
+        broken_stream_server(r#"{"response":"feat: half","done":false}"#, Duration::ZERO);
+    let mut config = ollama_config(&url);
+    config.retry.max_attempts = 3;
+    let provider = OllamaProvider::new(&config.active_profile()).unwrap();
+
+    // Tokens reach a live printer: a retry would print the message twice
+    let (tx, rx) = mpsc::channel(32);
+    let err = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Provider { .. }), "{err:?}");
+    assert_eq!(drain_tokens(rx).await, vec!["feat: half"]);
+    assert_eq!(served.load(Ordering::SeqCst), 1);
+
+    // Nobody is listening (multi-candidate runs): retrying is harmless
+    let (tx, rx) = mpsc::channel(32);
+    drop(rx);
+    provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(served.load(Ordering::SeqCst), 4);
+}
+
+// ─── Network ─────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn proxy_carries_provider_requests() {
+    let proxy = MockServer::start().await;
+    mount_openai_completion(&proxy, "feat: add proxy").await;
+
+    let mut config = openai_config("http://llm.invalid/v1");
+    config.network.proxy = Some(proxy.uri());
+    let provider = OpenAiProvider::new(&config.active_profile()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    let result = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result, "feat: add proxy");
+    let requests = proxy.received_requests().await.unwrap();
+    assert_eq!(requests[0].url.host_str(), Some("llm.invalid"));
+}
+
+#[tokio::test]
+async fn no_proxy_hosts_bypass_the_proxy() {
+    let server = MockServer::start().await;
+    mount_openai_completion(&server, "feat: skip proxy").await;
+
+    let mut config = openai_config(&format!("{}/v1", server.uri()));
+    // Nothing listens here; only a bypassed request can succeed
+    config.network.proxy = Some("http://127.0.0.1:9".into());
+    config.network.no_proxy = Some("localhost,127.0.0.1".into());
+    let provider = OpenAiProvider::new(&config.active_profile()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    let result = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result, "feat: skip proxy");
+}
+
+#[tokio::test]
+async fn tls_failures_are_reported_as_tls_errors() {
+    let server = MockServer::start().await;
+
+    // A plain HTTP server behind an https:// URL fails the handshake
+    let config = anthropic_config(&server.uri().replace("http://", "https://"));
+    let provider = create_provider(&config).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    let err = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::Tls { ref provider, .. } if provider == "anthropic"),
+        "expected a TLS error, got: {err:?}"
+    );
+    assert!(err.is_failover());
+
+    let err = provider.probe().await.unwrap_err();
+    assert!(matches!(err, Error::Tls { .. }), "got: {err:?}");
+}
+
+#[test]
+fn unreadable_or_invalid_certificates_are_config_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let not_pem = dir.path().join("ca.pem");
+    std::fs::write(&not_pem, "not a certificate").unwrap();
+
+    let mut config = openai_config("https://llm.example.com/v1");
+    config.network.ca_bundle_path = Some(dir.path().join("missing.pem"));
+    assert!(matches!(
+        create_provider(&config),
+        Err(Error::Config(ref m)) if m.contains("ca_bundle_path")
+    ));
+
+    config.network.ca_bundle_path = None;
+    config.network.client_cert_path = Some(not_pem);
+    assert!(matches!(
+        create_provider(&config),
+        Err(Error::Config(ref m)) if m.contains("client_cert_path")
+    ));
+}
+
+// ─── Structured output ───────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn ollama_sends_schema_as_format() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(r#"{"response":"{}","done":true}"#),
+        )
+        .mount(&server)
+        .await;
+
+    let provider = OllamaProvider::new(&ollama_config(&server.uri()).active_profile()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            Some(StructuredCommit::json_schema()),
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let sent: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(&sent["format"], StructuredCommit::json_schema());
+}
+
+#[tokio::test]
+async fn openai_sends_strict_json_schema_response_format() {
+    let server = MockServer::start().await;
+    mount_openai_completion(&server, "{}").await;
+
+    let config = openai_config(&format!("{}/v1", server.uri()));
+    let provider = OpenAiProvider::new(&config.active_profile()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            Some(StructuredCommit::json_schema()),
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let sent: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    let format = &sent["response_format"];
+    assert_eq!(format["type"], "json_schema");
+    assert_eq!(format["json_schema"]["strict"], true);
+    assert_eq!(
+        &format["json_schema"]["schema"],
+        StructuredCommit::json_schema()
+    );
+}
+
+#[tokio::test]
+async fn structured_output_disabled_omits_schema() {
+    let server = MockServer::start().await;
+    mount_openai_completion(&server, "feat: add test").await;
+
+    let config = Config {
+        structured_output: false,
+        ..openai_config(&format!("{}/v1", server.uri()))
+    };
+    let provider = OpenAiProvider::new(&config.active_profile()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            Some(StructuredCommit::json_schema()),
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let sent: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert!(sent.get("response_format").is_none());
+}
+
+#[tokio::test]
+async fn stream_usage_disabled_omits_stream_options() {
+    let server = MockServer::start().await;
+    mount_openai_completion(&server, "feat: add test").await;
+
+    let toml_str = format!(
+        "[providers.vllm]\nkind = \"openai\"\nmodel = \"m\"\nbase_url = \"{}/v1\"\nstream_usage = false\n",
+        server.uri()
+    );
+    let config: Config = toml::from_str(&toml_str).unwrap();
+    let provider = OpenAiProvider::new(&config.named_profile("vllm").unwrap()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let sent: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert!(sent.get("stream_options").is_none());
+}
+
+#[tokio::test]
+async fn anthropic_forces_commit_tool_and_streams_its_input() {
+    let server = MockServer::start().await;
+
+    let body = [
+        "event: content_block_start",
+        r#"data: {"type":"content_block_start","index":0,"content_block":{"type":"tool_use","id":"toolu_1","name":"commit_message","input":{}}}"#,
+        "",
+        "event: content_block_delta",
+        r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"type\":\"feat\",\"scope\":null,"}}"#,
+        "",
+        "event: content_block_delta",
+        r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"\"subject\":\"add tool output\",\"body\":null,\"breaking_change\":null}"}}"#,
+        "",
+        "event: message_stop",
+        r#"data: {"type":"message_stop"}"#,
+        "",
+    ]
+    .join("\n");
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&server)
+        .await;
+
+    let provider =
+        AnthropicProvider::new(&anthropic_config(&server.uri()).active_profile()).unwrap();
+    let (tx, _rx) = mpsc::channel(32);
+    let result = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            Some(StructuredCommit::json_schema()),
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        CommitSanitizer::sanitize(&result, &default_format()).unwrap(),
+        "feat: add tool output"
+    );
+
+    let requests = server.received_requests().await.unwrap();
+    let sent: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(sent["tool_choice"]["type"], "tool");
+    assert_eq!(sent["tool_choice"]["name"], sent["tools"][0]["name"]);
+    assert_eq!(
+        &sent["tools"][0]["input_schema"],
+        StructuredCommit::json_schema()
+    );
+}
+
+// ─── Token usage ─────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn ollama_reports_eval_counts() {
+    let server = MockServer::start().await;
+
+    let body = [
+        r#"{"response":"fix: count","done":false}"#,
+        r#"{"response":"","done":true,"prompt_eval_count":412,"eval_count":23}"#,
+    ]
+    .join("\n");
+
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&server)
+        .await;
+
+    let log = UsageLog::default();
+    let provider = OllamaProvider::new(&ollama_config(&server.uri()).active_profile())
+        .unwrap()
+        .with_usage_log(log.clone());
+    let (tx, _rx) = mpsc::channel(32);
+    provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    let requests = log.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].tokens,
+        Some(TokenUsage {
+            input_tokens: 412,
+            output_tokens: 23
+        })
+    );
+    assert_eq!(requests[0].model, "qwen3.5:4b");
+    assert!(!requests[0].cloud);
+}
+
+#[tokio::test]
+async fn openai_reads_usage_chunk_after_finish() {
+    let server = MockServer::start().await;
+
+    let body = [
+        r#"data: {"choices":[{"delta":{"content":"fix: usage"},"finish_reason":"stop"}],"usage":null}"#,
+        "",
+        r#"data: {"choices":[],"usage":{"prompt_tokens":812,"completion_tokens":41,"total_tokens":853}}"#,
+        "",
+        "data: [DONE]",
+        "",
+    ]
+    .join("\n");
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&server)
+        .await;
+
+    let log = UsageLog::default();
+    let provider =
+        OpenAiProvider::new(&openai_config(&format!("{}/v1", server.uri())).active_profile())
+            .unwrap()
+            .with_usage_log(log.clone());
+    let (tx, _rx) = mpsc::channel(32);
+    let result = provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result, "fix: usage");
+
+    let requests = log.requests();
+    assert_eq!(
+        requests[0].tokens,
+        Some(TokenUsage {
+            input_tokens: 812,
+            output_tokens: 41
+        })
+    );
+
+    let sent = server.received_requests().await.unwrap();
+    let sent: serde_json::Value = serde_json::from_slice(&sent[0].body).unwrap();
+    assert_eq!(sent["stream_options"]["include_usage"], true);
+}
+
+#[tokio::test]
+async fn openai_without_usage_records_request() {
+    let server = MockServer::start().await;
+    mount_openai_completion(&server, "fix: no usage").await;
+
+    let log = UsageLog::default();
+    let provider =
+        OpenAiProvider::new(&openai_config(&format!("{}/v1", server.uri())).active_profile())
+            .unwrap()
+            .with_usage_log(log.clone());
+    let (tx, _rx) = mpsc::channel(32);
+    provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    let requests = log.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].tokens.is_none());
+}
+
+#[tokio::test]
+async fn anthropic_reads_message_start_and_delta_usage() {
+    let server = MockServer::start().await;
+
+    let body = [
+        "event: message_start",
+        r#"data: {"type":"message_start","message":{"usage":{"input_tokens":900,"cache_read_input_tokens":100,"output_tokens":1}}}"#,
+        "",
+        "event: content_block_delta",
+        r#"data: {"type":"content_block_delta","delta":{"type":"text_delta","text":"fix: tokens"}}"#,
+        "",
+        "event: message_delta",
+        r#"data: {"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":37}}"#,
+        "",
+        "event: message_stop",
+        r#"data: {"type":"message_stop"}"#,
+        "",
+    ]
+    .join("\n");
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&server)
+        .await;
+
+    let log = UsageLog::default();
+    let provider = AnthropicProvider::new(&anthropic_config(&server.uri()).active_profile())
+        .unwrap()
+        .with_usage_log(log.clone());
+    let (tx, _rx) = mpsc::channel(32);
+    provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        log.requests()[0].tokens,
+        Some(TokenUsage {
+            input_tokens: 1_000,
+            output_tokens: 37
+        })
+    );
+}
+
+#[tokio::test]
+async fn every_attempt_is_metered() {
+    let (url, served) = broken_stream_server(
+        r#"data: {"type":"message_start","message":{"usage":{"input_tokens":900,"output_tokens":1}}}"#,
+        Duration::ZERO,
+    );
+    let mut config = anthropic_config(&url);
+    config.retry.max_attempts = 2;
+    let log = UsageLog::default();
+    let provider = AnthropicProvider::new(&config.active_profile())
+        .unwrap()
+        .with_usage_log(log.clone());
+
+    // Both attempts break after the input was billed
+    let (tx, _rx) = mpsc::channel(32);
+    provider
+        .generate(
+            "test prompt",
+            SYSTEM_PROMPT,
+            None,
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+
+    assert_eq!(served.load(Ordering::SeqCst), 2);
+    let requests = log.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests.iter().all(|r| r.tokens
+        == Some(TokenUsage {
+            input_tokens: 900,
+            output_tokens: 1
+        })));
+}
+
 // ─── Sanitizer: full JSON pipeline ───────────────────────────────────────────
 
 #[test]
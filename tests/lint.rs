@@ -0,0 +1,134 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+mod helpers;
+
+use std::path::PathBuf;
+
+use commitbee::config::Config;
+use commitbee::domain::{ChangeStatus, CodeSymbol, PromptContext, SymbolKind};
+use commitbee::services::context::ContextBuilder;
+use commitbee::services::lint::CommitLinter;
+use helpers::{make_file_change, make_staged_changes};
+
+fn removed_pub_fn(name: &str) -> CodeSymbol {
+    CodeSymbol {
+        kind: SymbolKind::Function,
+        name: name.to_string(),
+        file: PathBuf::from("src/lib.rs"),
+        line: 1,
+        end_line: 3,
+        is_public: true,
+        is_added: false,
+        is_whitespace_only: None,
+        span_change_kind: None,
+        signature: None,
+        parent_scope: None,
+    }
+}
+
+/// Context for a diff that removes `pub fn old_api` without any bug comments.
+fn api_removal_context() -> PromptContext {
+    let changes = make_staged_changes(vec![make_file_change(
+        "src/lib.rs",
+        ChangeStatus::Modified,
+        "-pub fn old_api() {\n-    1\n-}",
+        0,
+        3,
+    )]);
+    ContextBuilder::build(
+        &changes,
+        &[removed_pub_fn("old_api")],
+        &[],
+        &Config::default(),
+    )
+}
+
+// ─── Message cleanup ─────────────────────────────────────────────────────────
+
+#[test]
+fn clean_message_strips_comments_and_scissors() {
+    let raw = "\nfeat: add api  \n\n# Please enter the commit message\nBody line\n\
+               # ------------------------ >8 ------------------------\n\
+               diff --git a/x b/x\n";
+    assert_eq!(
+        CommitLinter::clean_message(raw),
+        "feat: add api\n\nBody line"
+    );
+}
+
+#[test]
+fn git_generated_messages_are_exempt() {
+    assert!(CommitLinter::is_exempt("Merge branch 'topic'"));
+    assert!(CommitLinter::is_exempt("Revert \"feat: add api\""));
+    assert!(CommitLinter::is_exempt("fixup! feat: add api"));
+    assert!(!CommitLinter::is_exempt("feat: add api"));
+}
+
+// ─── Format rules ────────────────────────────────────────────────────────────
+
+#[test]
+fn lint_rejects_non_conventional_header() {
+    let issues = CommitLinter::lint("Update README", None);
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].contains("valid type"), "{issues:?}");
+
+    assert_eq!(CommitLinter::lint("", None), vec!["Message is empty."]);
+}
+
+#[test]
+fn lint_requires_blank_line_after_header() {
+    let issues = CommitLinter::lint("feat: add api\nbody right away", None);
+    assert!(
+        issues.iter().any(|i| i.contains("blank line")),
+        "{issues:?}"
+    );
+    assert!(CommitLinter::lint("feat: add api\n\nbody", None).is_empty());
+}
+
+// ─── Evidence rules ──────────────────────────────────────────────────────────
+
+#[test]
+fn lint_flags_fix_without_bug_evidence_and_missing_breaking_marker() {
+    let context = api_removal_context();
+    let issues = CommitLinter::lint("fix(api): drop old api", Some(&context));
+    assert!(issues.iter().any(|i| i.contains("\"fix\"")), "{issues:?}");
+    assert!(
+        issues
+            .iter()
+            .any(|i| i.contains("Public APIs were removed")),
+        "{issues:?}"
+    );
+}
+
+#[test]
+fn lint_accepts_breaking_marker_or_footer() {
+    let context = api_removal_context();
+    assert!(CommitLinter::lint("refactor(api)!: drop old_api", Some(&context)).is_empty());
+    assert!(
+        CommitLinter::lint(
+            "refactor(api): drop old_api\n\nBREAKING CHANGE: old_api was removed",
+            Some(&context)
+        )
+        .is_empty()
+    );
+}
+
+#[test]
+fn lint_flags_feat_for_docs_only_diff() {
+    let changes = make_staged_changes(vec![make_file_change(
+        "README.md",
+        ChangeStatus::Modified,
+        "+## Usage",
+        1,
+        0,
+    )]);
+    let context = ContextBuilder::build(&changes, &[], &[], &Config::default());
+    let issues = CommitLinter::lint("feat: describe usage", Some(&context));
+    assert!(
+        issues.iter().any(|i| i.contains("Use \"docs\"")),
+        "{issues:?}"
+    );
+    assert!(CommitLinter::lint("docs: describe usage", Some(&context)).is_empty());
+}
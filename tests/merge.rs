@@ -0,0 +1,106 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::path::PathBuf;
+
+use commitbee::services::history::CommitRecord;
+use commitbee::services::merge::{ConflictResolution, MergeBuilder, MergeInfo, Resolution};
+
+/// `MERGE_MSG` as written by a `git merge feature` that stopped on conflicts.
+const MERGE_MSG: &str = "\
+Merge branch 'feature'
+
+# Conflicts:
+#\tsrc/config.rs
+#\tREADME.md
+#
+# It looks like you may be committing a merge.
+# If this is not correct, please run
+#\tgit update-ref -d MERGE_HEAD
+# and try again.
+";
+
+fn record(subject: &str) -> CommitRecord {
+    CommitRecord {
+        sha: "abc1234".into(),
+        subject: subject.into(),
+        body: String::new(),
+    }
+}
+
+#[test]
+fn parse_merge_msg() {
+    assert_eq!(
+        MergeBuilder::merge_title(MERGE_MSG).as_deref(),
+        Some("Merge branch 'feature'")
+    );
+    assert_eq!(
+        MergeBuilder::parse_conflicts(MERGE_MSG),
+        vec![PathBuf::from("src/config.rs"), PathBuf::from("README.md")]
+    );
+
+    // Uncommented lists are read too; a clean merge has none
+    let plain = "Merge branch 'x'\n\nConflicts:\n\tsrc/lib.rs\n";
+    assert_eq!(
+        MergeBuilder::parse_conflicts(plain),
+        vec![PathBuf::from("src/lib.rs")]
+    );
+    assert!(MergeBuilder::parse_conflicts("Merge branch 'x'\n").is_empty());
+    assert_eq!(MergeBuilder::merge_title("# only comments\n"), None);
+}
+
+#[test]
+fn classify_compares_resolution_to_both_parents() {
+    let classify = MergeBuilder::classify;
+    assert_eq!(classify(Some("a"), Some("a"), Some("b")), Resolution::Ours);
+    assert_eq!(
+        classify(Some("b"), Some("a"), Some("b")),
+        Resolution::Theirs
+    );
+    assert_eq!(
+        classify(Some("ab"), Some("a"), Some("b")),
+        Resolution::Combined
+    );
+    assert_eq!(classify(None, Some("a"), Some("b")), Resolution::Deleted);
+    assert_eq!(classify(Some("b"), None, Some("b")), Resolution::Theirs);
+}
+
+#[test]
+fn build_prompt_lists_commits_and_resolutions() {
+    let info = MergeInfo {
+        title: Some("Merge branch 'feature'".into()),
+        commits: vec![record("feat(config): load toml"), record("fix: typo")],
+        conflicts: vec![
+            ConflictResolution {
+                path: PathBuf::from("src/config.rs"),
+                resolution: Resolution::Combined,
+                from_theirs: vec!["Config::load".into()],
+                from_ours: vec!["Config::default".into()],
+            },
+            ConflictResolution {
+                path: PathBuf::from("README.md"),
+                resolution: Resolution::Ours,
+                from_theirs: Vec::new(),
+                from_ours: vec!["ignored".into()],
+            },
+        ],
+    };
+    let prompt = MergeBuilder::build_prompt("BASE PROMPT", &info);
+
+    assert!(prompt.starts_with(
+        "BASE PROMPT\n\n---\nThese changes complete a merge (\"Merge branch 'feature'\")."
+    ));
+    assert!(prompt.contains("INCOMING COMMITS:\n  - feat(config): load toml\n  - fix: typo"));
+    assert!(prompt.contains(
+        "  - src/config.rs: combined both sides\n      from incoming: Config::load\n      kept from current: Config::default\n  - README.md: kept the current branch's version\n"
+    ));
+    assert!(!prompt.contains("ignored"));
+    assert!(prompt.contains("how each was resolved"));
+    assert!(prompt.ends_with("Respond with ONLY the JSON object."));
+
+    // A clean merge doesn't ask about conflicts
+    let clean = MergeBuilder::build_prompt("BASE PROMPT", &MergeInfo::default());
+    assert!(clean.contains("These changes complete a merge.\n\nSummarize"));
+    assert!(!clean.contains("how each was resolved"));
+}
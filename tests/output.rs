@@ -0,0 +1,175 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+mod helpers;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use commitbee::config::Config;
+use commitbee::domain::{ChangeStatus, CommitType};
+use commitbee::services::context::ContextBuilder;
+use commitbee::services::output::{CommitOutput, SCHEMA_VERSION, SplitPlan, parse_message};
+use commitbee::services::splitter::CommitGroup;
+use helpers::{make_file_change, make_staged_changes};
+
+// ─── Message parsing ─────────────────────────────────────────────────────────
+
+#[test]
+fn parse_message_splits_header_and_body() {
+    let parsed = parse_message("feat(cli): add json output\n\nEmit a versioned schema.").unwrap();
+    assert_eq!(parsed.commit_type, "feat");
+    assert_eq!(parsed.scope.as_deref(), Some("cli"));
+    assert_eq!(parsed.subject, "add json output");
+    assert_eq!(parsed.body.as_deref(), Some("Emit a versioned schema."));
+    assert!(parsed.breaking_change.is_none());
+
+    let bare = parse_message("fix: handle empty diff").unwrap();
+    assert!(bare.scope.is_none());
+    assert!(bare.body.is_none());
+}
+
+#[test]
+fn parse_message_extracts_wrapped_breaking_footer() {
+    let message = "refactor(config)!: rename provider keys\n\n\
+                   Group provider settings under one table.\n\n\
+                   BREAKING CHANGE: `provider` and `model` moved under a\n  \
+                   `[providers]` table";
+    let parsed = parse_message(message).unwrap();
+    assert_eq!(
+        parsed.body.as_deref(),
+        Some("Group provider settings under one table.")
+    );
+    assert_eq!(
+        parsed.breaking_change.as_deref(),
+        Some("`provider` and `model` moved under a `[providers]` table")
+    );
+}
+
+#[test]
+fn parse_message_extracts_trailers_from_footer_paragraph() {
+    let message = "feat!: drop v1 routes\n\n\
+                   Note: prose stays in the body.\n\
+                   Refs: not a trailer here\n\n\
+                   BREAKING CHANGE: v1 routes return 404\n\
+                   Refs: #42\n\
+                   Signed-off-by: Ada <ada@example.com>";
+    let parsed = parse_message(message).unwrap();
+    assert_eq!(
+        parsed.body.as_deref(),
+        Some("Note: prose stays in the body.\nRefs: not a trailer here")
+    );
+    assert_eq!(
+        parsed.breaking_change.as_deref(),
+        Some("v1 routes return 404")
+    );
+    let trailers: Vec<String> = parsed.trailers.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        trailers,
+        vec!["Refs: #42", "Signed-off-by: Ada <ada@example.com>"]
+    );
+}
+
+#[test]
+fn parse_message_rejects_non_conventional() {
+    assert!(parse_message("Update README").is_none());
+    assert!(parse_message("").is_none());
+}
+
+// ─── Output document ─────────────────────────────────────────────────────────
+
+#[test]
+fn output_json_shape() {
+    let changes = make_staged_changes(vec![
+        make_file_change(
+            "src/lib.rs",
+            ChangeStatus::Modified,
+            "+pub fn run() {}",
+            1,
+            0,
+        ),
+        make_file_change("README.md", ChangeStatus::Modified, "+docs", 1, 0),
+    ]);
+    let context = ContextBuilder::build(&changes, &[], &[], &Config::default());
+    let groups = vec![
+        CommitGroup {
+            files: vec![PathBuf::from("src/lib.rs")],
+            commit_type: CommitType::Feat,
+            scope: Some("core".into()),
+            hunks: BTreeMap::new(),
+        },
+        CommitGroup {
+            files: vec![PathBuf::from("README.md")],
+            commit_type: CommitType::Docs,
+            scope: None,
+            hunks: BTreeMap::new(),
+        },
+    ];
+
+    let message = "feat!: add run entry point\n\nBREAKING CHANGE: replaces start()";
+    let output = CommitOutput::build(
+        message,
+        vec![message.to_string(), "feat: add run".into()],
+        &context,
+        Some(&groups),
+    );
+    let json: serde_json::Value = serde_json::from_str(&output.to_json()).unwrap();
+
+    assert_eq!(json["schema_version"], SCHEMA_VERSION);
+    assert_eq!(json["message"], message);
+    assert_eq!(json["commit"]["type"], "feat");
+    assert_eq!(json["commit"]["scope"], serde_json::Value::Null);
+    assert_eq!(json["commit"]["breaking_change"], "replaces start()");
+    assert_eq!(json["breaking"], true);
+    assert_eq!(json["candidates"].as_array().unwrap().len(), 2);
+    assert_eq!(json["suggested_type"], context.suggested_type.as_str());
+    assert!(json["evidence"]["is_mechanical"].is_boolean());
+    assert!(json["evidence"]["breaking_signals"].is_array());
+    assert_eq!(json["split"]["suggested"], true);
+    assert_eq!(json["split"]["groups"][0]["type"], "feat");
+    assert_eq!(json["split"]["groups"][0]["files"][0], "src/lib.rs");
+    assert_eq!(json["split"]["groups"][1]["scope"], serde_json::Value::Null);
+    assert!(json["split"]["groups"][0].get("hunks").is_none());
+    assert_eq!(json["committed"], false);
+}
+
+#[test]
+fn output_without_split_or_conventional_message() {
+    let changes = make_staged_changes(vec![make_file_change(
+        "README.md",
+        ChangeStatus::Modified,
+        "+docs",
+        1,
+        0,
+    )]);
+    let context = ContextBuilder::build(&changes, &[], &[], &Config::default());
+    let output = CommitOutput::build("Update docs", vec!["Update docs".into()], &context, None);
+
+    assert!(output.commit.is_none());
+    assert!(!output.breaking);
+    assert!(!output.split.suggested);
+    assert!(output.split.groups.is_empty());
+}
+
+#[test]
+fn split_plan_json_shape() {
+    let groups = vec![CommitGroup {
+        files: vec![PathBuf::from("src/lib.rs"), PathBuf::from("tests/lib.rs")],
+        commit_type: CommitType::Feat,
+        scope: Some("core".into()),
+        hunks: BTreeMap::from([(PathBuf::from("src/lib.rs"), vec![0, 2])]),
+    }];
+    let json: serde_json::Value =
+        serde_json::from_str(&SplitPlan::new(Some(&groups)).to_json()).unwrap();
+
+    assert_eq!(json["schema_version"], SCHEMA_VERSION);
+    assert_eq!(json["suggested"], true);
+    assert_eq!(json["groups"][0]["scope"], "core");
+    assert_eq!(json["groups"][0]["files"][1], "tests/lib.rs");
+    assert_eq!(json["groups"][0]["hunks"]["src/lib.rs"][1], 2);
+
+    let json: serde_json::Value = serde_json::from_str(&SplitPlan::new(None).to_json()).unwrap();
+    assert_eq!(json["suggested"], false);
+    assert!(json["groups"].as_array().unwrap().is_empty());
+}
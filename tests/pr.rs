@@ -0,0 +1,179 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+mod helpers;
+
+use commitbee::config::{CommitFormat, Config};
+use commitbee::domain::{ChangeStatus, CommitType};
+use commitbee::services::context::ContextBuilder;
+use commitbee::services::history::CommitRecord;
+use commitbee::services::pr::{PR_SYSTEM_PROMPT, PrBuilder, PrDescription};
+use helpers::{make_file_change, make_staged_changes};
+
+fn record(sha: &str, subject: &str, body: &str) -> CommitRecord {
+    CommitRecord {
+        sha: sha.to_string(),
+        subject: subject.to_string(),
+        body: body.to_string(),
+    }
+}
+
+fn description(breaking: Vec<&str>) -> PrDescription {
+    PrDescription {
+        pr_type: "feat".into(),
+        scope: Some("cli".into()),
+        title: "Add pr subcommand".into(),
+        summary: "Generates PR descriptions from the branch diff.".into(),
+        changes: vec!["add `PrBuilder`".into(), "  ".into()],
+        breaking_changes: breaking.into_iter().map(String::from).collect(),
+        test_notes: Some("Covered by tests/pr.rs".into()),
+    }
+}
+
+// ─── Prompt ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn pr_system_prompt_type_list_matches_commit_type_all() {
+    let types_line = PR_SYSTEM_PROMPT
+        .lines()
+        .find(|line| line.contains("feat, fix, refactor"))
+        .expect("PR_SYSTEM_PROMPT must contain the commit type list line");
+    let found: Vec<&str> = types_line.split(',').map(str::trim).collect();
+    assert_eq!(found, CommitType::ALL);
+}
+
+#[test]
+fn build_prompt_lists_commits_and_declared_breaking() {
+    let changes = make_staged_changes(vec![make_file_change(
+        "src/cli.rs",
+        ChangeStatus::Modified,
+        "+pub fn run() {}\n",
+        1,
+        0,
+    )]);
+    let context = ContextBuilder::build(&changes, &[], &[], &Config::default());
+    let commits = vec![
+        record("aaaaaaaa", "feat: add run", ""),
+        record(
+            "bbbbbbbb",
+            "refactor!: drop legacy flag",
+            "BREAKING CHANGE: --legacy is gone",
+        ),
+    ];
+
+    let prompt = PrBuilder::build_prompt(&context, "feature/run", "main", &commits);
+
+    assert!(prompt.contains("branch \"feature/run\" targeting \"main\""));
+    assert!(prompt.contains("COMMITS (2):"));
+    assert!(prompt.contains("  - feat: add run"));
+    assert!(prompt.contains("BREAKING CHANGES DECLARED IN COMMITS:\n- --legacy is gone"));
+    assert!(prompt.contains("\"breaking_changes\": []"));
+}
+
+// ─── Parsing ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn parse_extracts_json_after_thoughts() {
+    let raw = r#"<think>{not json}</think>
+Here you go: {"type": "fix", "scope": null, "title": "handle empty diff", "summary": "Stops a panic.", "changes": ["guard empty input"], "breaking_changes": [], "test_notes": null}"#;
+    let pr = PrBuilder::parse(raw).expect("should parse");
+    assert_eq!(pr.pr_type, "fix");
+    assert_eq!(pr.title, "handle empty diff");
+    assert!(pr.test_notes.is_none());
+}
+
+#[test]
+fn parse_rejects_empty_title() {
+    let raw = r#"{"type": "fix", "title": " ", "summary": "x"}"#;
+    assert!(PrBuilder::parse(raw).is_none());
+}
+
+// ─── Title ───────────────────────────────────────────────────────────────────
+
+#[test]
+fn format_title_conventional_with_breaking_marker() {
+    let pr = description(vec![]);
+    let title = PrBuilder::format_title(&pr, CommitType::Refactor, true, &CommitFormat::default());
+    assert_eq!(title, "feat(cli)!: add pr subcommand");
+}
+
+#[test]
+fn format_title_falls_back_on_unknown_type() {
+    let mut pr = description(vec![]);
+    pr.pr_type = "feature".into();
+    pr.scope = None;
+    let title = PrBuilder::format_title(&pr, CommitType::Refactor, false, &CommitFormat::default());
+    assert_eq!(title, "refactor: add pr subcommand");
+}
+
+// ─── Breaking changes & Markdown ─────────────────────────────────────────────
+
+#[test]
+fn collect_breaking_merges_commit_footers_without_duplicates() {
+    let pr = description(vec!["Removed `--legacy`", "null"]);
+    let commits = vec![
+        record("a", "feat(api)!: rename endpoint", ""),
+        record("b", "chore: tidy", "BREAKING CHANGE: removed `--legacy`"),
+    ];
+    let breaking = PrBuilder::collect_breaking(&pr, &commits);
+    assert_eq!(breaking, vec!["Removed `--legacy`", "rename endpoint"]);
+}
+
+#[test]
+fn render_markdown_sections() {
+    let pr = description(vec![]);
+    let changes = make_staged_changes(vec![
+        make_file_change("src/cli.rs", ChangeStatus::Modified, "", 1, 0),
+        make_file_change("tests/pr.rs", ChangeStatus::Added, "", 10, 0),
+    ]);
+    let commits = vec![record("0123456789", "feat: add pr", "")];
+    let breaking = vec!["drop `--legacy`".to_string()];
+
+    let md = PrBuilder::render_markdown(&pr, &breaking, &commits, &changes);
+
+    insta::assert_snapshot!(md, @r"
+    ## Summary
+
+    Generates PR descriptions from the branch diff.
+
+    ## Changes
+
+    - add `PrBuilder`
+
+    ## Breaking Changes
+
+    - drop `--legacy`
+
+    ## Testing
+
+    Covered by tests/pr.rs
+
+    Test files changed: `tests/pr.rs`
+
+    ## Commits
+
+    - `0123456` feat: add pr
+    ");
+}
+
+// ─── Commit records ──────────────────────────────────────────────────────────
+
+#[test]
+fn breaking_notes_reads_multiline_footer() {
+    let c = record(
+        "a",
+        "feat: x",
+        "Body text.\n\nBREAKING CHANGE: config key renamed\n  from `a` to `b`\nRefs: #1",
+    );
+    assert_eq!(
+        c.breaking_notes(),
+        vec!["config key renamed from `a` to `b`"]
+    );
+}
+
+#[test]
+fn breaking_notes_ignores_non_conventional_bang() {
+    let c = record("a", "Wow!: exciting", "");
+    assert!(c.breaking_notes().is_empty());
+}
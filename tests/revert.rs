@@ -0,0 +1,83 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use commitbee::services::history::CommitRecord;
+use commitbee::services::revert::RevertBuilder;
+
+fn reverted() -> CommitRecord {
+    CommitRecord {
+        sha: "9c1e4f2a7b3d5e6f8a9b0c1d2e3f4a5b6c7d8e9f".into(),
+        subject: "feat(cache): keep responses for a week".into(),
+        body: "Entries expire after 7 days.".into(),
+    }
+}
+
+#[test]
+fn build_prompt_includes_reverted_message() {
+    let prompt = RevertBuilder::build_prompt("BASE PROMPT", &reverted());
+
+    assert!(prompt.starts_with("BASE PROMPT\n\n---\n"));
+    assert!(prompt.contains("revert commit 9c1e4f2"));
+    assert!(
+        prompt.contains("\"feat(cache): keep responses for a week\nEntries expire after 7 days.\"")
+    );
+    assert!(prompt.ends_with("Respond with ONLY the JSON object."));
+}
+
+#[test]
+fn finalize_references_reverted_commit() {
+    let message = RevertBuilder::finalize(
+        "revert(cache): drop week-long retention\n\nStale entries outlived config changes.",
+        &reverted(),
+    );
+    assert_eq!(
+        message,
+        "revert: feat(cache): keep responses for a week\n\n\
+         This reverts commit 9c1e4f2a7b3d5e6f8a9b0c1d2e3f4a5b6c7d8e9f.\n\n\
+         Stale entries outlived config changes."
+    );
+
+    // A generated reference line isn't repeated, and an empty body is fine
+    let message =
+        RevertBuilder::finalize("revert: cache\n\nThis reverts commit 9c1e4f2.", &reverted());
+    assert_eq!(
+        message,
+        "revert: feat(cache): keep responses for a week\n\n\
+         This reverts commit 9c1e4f2a7b3d5e6f8a9b0c1d2e3f4a5b6c7d8e9f."
+    );
+}
+
+#[test]
+fn finalize_reapplies_a_reverted_revert() {
+    let revert = CommitRecord {
+        sha: "1f2e3d4c5b6a79881f2e3d4c5b6a79881f2e3d4c".into(),
+        subject: "revert: feat(cache): keep responses for a week".into(),
+        body: String::new(),
+    };
+    let message = RevertBuilder::finalize("revert: restore cache retention", &revert);
+    assert_eq!(
+        message,
+        "feat(cache): keep responses for a week\n\n\
+         This reverts commit 1f2e3d4c5b6a79881f2e3d4c5b6a79881f2e3d4c."
+    );
+}
+
+#[test]
+fn finalize_keeps_generated_subject_when_original_is_too_long() {
+    let long = CommitRecord {
+        subject: "feat(cache): keep sanitized responses on disk for a week so reruns are instant"
+            .into(),
+        ..reverted()
+    };
+    let message = RevertBuilder::finalize(
+        "revert(cache): drop week-long retention\n\nStale entries outlived config changes.",
+        &long,
+    );
+    assert_eq!(
+        message,
+        "revert(cache): drop week-long retention\n\n\
+         This reverts commit 9c1e4f2a7b3d5e6f8a9b0c1d2e3f4a5b6c7d8e9f.\n\n\
+         Stale entries outlived config changes."
+    );
+}
@@ -5,6 +5,7 @@
 mod helpers;
 
 use commitbee::config::CommitFormat;
+use commitbee::domain::{CommitType, Trailer};
 use commitbee::services::sanitizer::{CommitSanitizer, CommitValidator, StructuredCommit};
 use proptest::prelude::*;
 
@@ -57,6 +58,42 @@ fn sanitize_json_invalid_type() {
     );
 }
 
+#[test]
+fn json_schema_matches_structured_commit() {
+    let schema = StructuredCommit::json_schema();
+
+    let types: Vec<&str> = schema["properties"]["type"]["enum"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t.as_str().unwrap())
+        .collect();
+    assert_eq!(types, CommitType::ALL);
+
+    // Every field the model fills in is a required schema property, and nothing else
+    let commit = StructuredCommit {
+        commit_type: "feat".into(),
+        scope: None,
+        subject: "x".into(),
+        body: None,
+        breaking_change: None,
+        trailers: Vec::new(),
+    };
+    let value = serde_json::to_value(&commit).unwrap();
+    let mut fields: Vec<&String> = value.as_object().unwrap().keys().collect();
+    let mut properties: Vec<&String> = schema["properties"].as_object().unwrap().keys().collect();
+    fields.sort();
+    properties.sort();
+    assert_eq!(fields, properties);
+    assert_eq!(schema["required"].as_array().unwrap().len(), fields.len());
+
+    let output = r#"{"type":"fix","scope":null,"subject":"handle empty diff","body":null,"breaking_change":null}"#;
+    assert_eq!(
+        CommitSanitizer::sanitize(output, &default_format()).unwrap(),
+        "fix: handle empty diff"
+    );
+}
+
 // ─── Plain text tests ─────────────────────────────────────────────────────────
 
 #[test]
@@ -505,6 +542,88 @@ fn sanitize_breaking_footer_continuation_lines_indented() {
     }
 }
 
+// ─── Candidate deduplication ─────────────────────────────────────────────────
+
+#[test]
+fn dedup_drops_near_identical_candidates() {
+    let candidates = vec![
+        "feat(cli): add verbose flag".to_string(),
+        "feat(cli): Add verbose flag.".to_string(),
+        "fix(cli): add verbose flag".to_string(),
+        "feat(cli):  add   verbose flag\n".to_string(),
+        "feat(cli): add a --verbose flag for debugging".to_string(),
+    ];
+    let deduped = CommitSanitizer::dedup_candidates(candidates);
+    assert_eq!(
+        deduped,
+        [
+            "feat(cli): add verbose flag",
+            "fix(cli): add verbose flag",
+            "feat(cli): add a --verbose flag for debugging",
+        ]
+    );
+}
+
+#[test]
+fn dedup_compares_bodies() {
+    let subject = "refactor(git): split diff parsing";
+    let body_a = "Move hunk parsing into its own module so the splitter can reuse it \
+                  without pulling in the full git service.";
+    let body_b = "Extract rename detection into a helper and cache results.";
+
+    let candidates = vec![
+        format!("{subject}\n\n{body_a}"),
+        format!("{subject}\n\n{body_a}\n"),
+        format!("{subject}\n\n{body_b}"),
+    ];
+    let deduped = CommitSanitizer::dedup_candidates(candidates);
+    assert_eq!(deduped.len(), 2);
+    assert!(deduped[1].ends_with(body_b));
+}
+
+// ─── Trailers ────────────────────────────────────────────────────────────────
+
+fn trailers() -> Vec<Trailer> {
+    vec![
+        Trailer::new("Refs", "#42"),
+        Trailer::new("Signed-off-by", "Ada <ada@example.com>"),
+    ]
+}
+
+#[test]
+fn sanitize_json_puts_trailers_after_breaking_footer() {
+    let raw = r#"{"type": "feat", "scope": null, "subject": "drop v1 routes", "body": "The v1 API is gone.", "breaking_change": "v1 routes return 404"}"#;
+    let result =
+        CommitSanitizer::sanitize_with_trailers(raw, &default_format(), &trailers()).unwrap();
+    assert_eq!(
+        result,
+        "feat!: drop v1 routes\n\n\
+         The v1 API is gone.\n\n\
+         BREAKING CHANGE: v1 routes return 404\n\
+         Refs: #42\n\
+         Signed-off-by: Ada <ada@example.com>"
+    );
+
+    // Trailers in LLM output are ignored
+    let raw = r##"{"type": "fix", "subject": "x", "trailers": [{"key": "Refs", "value": "#1"}]}"##;
+    let result = CommitSanitizer::sanitize_with_trailers(raw, &default_format(), &[]).unwrap();
+    assert_eq!(result, "fix: x");
+}
+
+#[test]
+fn sanitize_plain_text_appends_missing_trailers() {
+    let raw = "fix: handle empty diff\n\nRefs: #42";
+    let result =
+        CommitSanitizer::sanitize_with_trailers(raw, &default_format(), &trailers()).unwrap();
+    assert_eq!(
+        result,
+        "fix: handle empty diff\n\nRefs: #42\nSigned-off-by: Ada <ada@example.com>"
+    );
+
+    let result = CommitSanitizer::append_trailers("fix: x\n\nSome prose.", &trailers()[..1]);
+    assert_eq!(result, "fix: x\n\nSome prose.\n\nRefs: #42");
+}
+
 // ─── CommitValidator tests ──────────────────────────────────────────────────
 
 fn make_commit(commit_type: &str, breaking_change: Option<&str>) -> StructuredCommit {
@@ -514,6 +633,7 @@ fn make_commit(commit_type: &str, breaking_change: Option<&str>) -> StructuredCo
         subject: "test subject".to_string(),
         body: None,
         breaking_change: breaking_change.map(|s| s.to_string()),
+        trailers: Vec::new(),
     }
 }
 
@@ -611,6 +731,7 @@ fn validator_rejects_long_subject() {
         subject: long_subject,
         body: None,
         breaking_change: None,
+        trailers: Vec::new(),
     };
     let violations = CommitValidator::validate(&commit, false, false, 0, false);
     assert!(
@@ -630,6 +751,7 @@ fn validator_accepts_subject_at_boundary() {
         subject,
         body: None,
         breaking_change: None,
+        trailers: Vec::new(),
     };
     let violations = CommitValidator::validate(&commit, false, false, 0, false);
     assert!(
@@ -788,3 +910,20 @@ fn sanitize_conversational_preamble_with_json() {
         "refactor(splitter): upgrade clustering to hybrid Jaccard similarity"
     );
 }
+
+#[test]
+fn validator_rejects_invented_issue_refs() {
+    let mut commit = make_commit("feat", None);
+    commit.subject = "add retry (#12)".into();
+    commit.body = Some("Closes PROJ-7 and fixes #42.".into());
+
+    let known = vec!["PROJ-42".to_string()];
+    let violations = CommitValidator::validate_issue_refs(&commit, &known, "PROMPT mentions #42");
+    assert_eq!(violations.len(), 1);
+    assert!(violations[0].contains("#12, PROJ-7"), "{violations:?}");
+
+    // Known refs, refs quoted in the prompt and other dashed words are fine
+    commit.subject = "add UTF-8 retry for PROJ-42".into();
+    commit.body = Some("See #42.".into());
+    assert!(CommitValidator::validate_issue_refs(&commit, &known, "see #42").is_empty());
+}
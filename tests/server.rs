@@ -0,0 +1,269 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use std::path::Path;
+use std::process::Stdio;
+
+use serde_json::{Value, json};
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use commitbee::error::Error;
+use commitbee::server::{read_message, write_message};
+
+// ─── Framing ─────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn framing_round_trips_messages() {
+    let mut buf = Vec::new();
+    write_message(&mut buf, r#"{"a":1}"#).await.unwrap();
+    write_message(&mut buf, r#"{"b":"ü"}"#).await.unwrap();
+
+    let mut reader = BufReader::new(buf.as_slice());
+    assert_eq!(
+        read_message(&mut reader).await.unwrap().as_deref(),
+        Some(r#"{"a":1}"#)
+    );
+    assert_eq!(
+        read_message(&mut reader).await.unwrap().as_deref(),
+        Some(r#"{"b":"ü"}"#)
+    );
+    assert_eq!(read_message(&mut reader).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn framing_ignores_other_headers() {
+    let raw =
+        "Content-Type: application/vscode-jsonrpc; charset=utf-8\r\ncontent-length: 2\r\n\r\n{}";
+    let mut reader = BufReader::new(raw.as_bytes());
+    assert_eq!(
+        read_message(&mut reader).await.unwrap().as_deref(),
+        Some("{}")
+    );
+}
+
+#[tokio::test]
+async fn framing_rejects_bad_content_length() {
+    let mut reader = BufReader::new("Content-Length: lots\r\n\r\n{}".as_bytes());
+    assert!(matches!(
+        read_message(&mut reader).await,
+        Err(Error::Protocol(_))
+    ));
+
+    let mut reader = BufReader::new("Content-Length: 999999999\r\n\r\n".as_bytes());
+    assert!(matches!(
+        read_message(&mut reader).await,
+        Err(Error::Protocol(_))
+    ));
+}
+
+// ─── Server ──────────────────────────────────────────────────────────────────
+
+fn git(path: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+async fn mock_ollama(response: &str) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/tags"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({ "models": [{ "name": "qwen3:4b" }] })),
+        )
+        .mount(&server)
+        .await;
+    let body = [
+        json!({ "response": response, "done": false }).to_string(),
+        json!({ "response": "", "done": true }).to_string(),
+    ]
+    .join("\n");
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&server)
+        .await;
+    server
+}
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Client {
+    fn spawn(repo: &Path, home: &Path, ollama: &str) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_commitbee"))
+            .args(["serve", "--stdio"])
+            .current_dir(repo)
+            .env("HOME", home)
+            .env("XDG_CONFIG_HOME", home)
+            .env("XDG_CACHE_HOME", home)
+            .env("COMMITBEE_PROVIDER", "ollama")
+            .env("COMMITBEE_MODEL", "qwen3:4b")
+            .env("COMMITBEE_OLLAMA_HOST", ollama)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Self {
+            child,
+            stdin,
+            stdout,
+        }
+    }
+
+    async fn send(&mut self, message: Value) {
+        write_message(&mut self.stdin, &message.to_string())
+            .await
+            .unwrap();
+    }
+
+    async fn recv(&mut self) -> Value {
+        let raw = read_message(&mut self.stdout).await.unwrap().unwrap();
+        serde_json::from_str(&raw).unwrap()
+    }
+
+    /// Read until the response for `id`, collecting notifications on the way.
+    async fn response(&mut self, id: i64) -> (Value, Vec<Value>) {
+        let mut notifications = Vec::new();
+        loop {
+            let message = self.recv().await;
+            if message["id"] == id {
+                return (message, notifications);
+            }
+            notifications.push(message);
+        }
+    }
+}
+
+fn staged_repo() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path();
+    git(path, &["init", "-b", "main"]);
+    git(path, &["config", "user.email", "test@test.com"]);
+    git(path, &["config", "user.name", "Test"]);
+    std::fs::write(
+        path.join("greet.rs"),
+        "pub fn greet() -> &'static str {\n    \"hi\"\n}\n",
+    )
+    .unwrap();
+    git(path, &["add", "greet.rs"]);
+    dir
+}
+
+#[tokio::test]
+async fn serve_generates_with_streamed_tokens() {
+    let repo = staged_repo();
+    let home = tempfile::tempdir().unwrap();
+    let ollama =
+        mock_ollama(r#"{"type":"feat","scope":null,"subject":"add greet helper","body":null}"#)
+            .await;
+    let mut client = Client::spawn(repo.path(), home.path(), &ollama.uri());
+
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }))
+        .await;
+    let (init, _) = client.response(1).await;
+    assert_eq!(init["result"]["schema_version"], 1);
+    assert!(
+        init["result"]["methods"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("generate"))
+    );
+
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": 2, "method": "analyze" }))
+        .await;
+    let (analysis, _) = client.response(2).await;
+    assert_eq!(analysis["result"]["files"][0]["path"], "greet.rs");
+    assert_eq!(analysis["result"]["files"][0]["status"], "added");
+
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": 3, "method": "generate" }))
+        .await;
+    let (generated, notifications) = client.response(3).await;
+    assert_eq!(generated["result"]["message"], "feat: add greet helper");
+    assert!(!notifications.is_empty(), "expected $/token notifications");
+    assert!(notifications.iter().all(|n| n["method"] == "$/token"
+        && n["params"]["id"] == 3
+        && n["params"]["candidate"] == 0));
+
+    client
+        .send(json!({ "jsonrpc": "2.0", "method": "exit" }))
+        .await;
+    assert!(client.child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+async fn serve_reports_protocol_errors() {
+    let repo = staged_repo();
+    let home = tempfile::tempdir().unwrap();
+    let ollama = mock_ollama("").await;
+    let mut client = Client::spawn(repo.path(), home.path(), &ollama.uri());
+
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": 1, "method": "bogus" }))
+        .await;
+    let (response, _) = client.response(1).await;
+    assert_eq!(response["error"]["code"], -32601);
+
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": 2, "method": "generate", "params": { "candidates": 9 } }))
+        .await;
+    let (response, _) = client.response(2).await;
+    assert_eq!(response["error"]["code"], -32602);
+
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": 3, "method": "cancel", "params": { "id": 42 } }))
+        .await;
+    let (response, _) = client.response(3).await;
+    assert_eq!(response["result"]["cancelled"], false);
+
+    // Closing stdin ends the server like `exit`
+    client.stdin.shutdown().await.unwrap();
+    drop(client.stdin);
+    assert!(client.child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+async fn serve_rejects_cwd_outside_its_repository() {
+    let repo = staged_repo();
+    let other = staged_repo();
+    let home = tempfile::tempdir().unwrap();
+    let ollama = mock_ollama("").await;
+    let mut client = Client::spawn(repo.path(), home.path(), &ollama.uri());
+
+    // The other repository's config and excludes were never loaded
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": 1, "method": "analyze", "params": { "cwd": other.path() } }))
+        .await;
+    let (response, _) = client.response(1).await;
+    assert!(
+        response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("outside the repository"),
+        "{response}"
+    );
+
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": 2, "method": "analyze", "params": { "cwd": repo.path() } }))
+        .await;
+    let (response, _) = client.response(2).await;
+    assert_eq!(response["result"]["files"][0]["path"], "greet.rs");
+}
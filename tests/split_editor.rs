@@ -0,0 +1,190 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+mod helpers;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use commitbee::domain::{ChangeStatus, CommitType, StagedChanges};
+use commitbee::error::Error;
+use commitbee::services::split_editor::SplitEditor;
+use commitbee::services::splitter::CommitGroup;
+use helpers::{make_file_change, make_staged_changes};
+
+const TWO_HUNKS: &str = "\
+diff --git a/src/parser.rs b/src/parser.rs
+--- a/src/parser.rs
++++ b/src/parser.rs
+@@ -1,2 +1,2 @@
+ fn a() {}
+-fn b() {}
++fn b() { 1 }
+@@ -40,1 +40,2 @@
+ fn y() {}
++fn z() {}";
+
+fn changes() -> StagedChanges {
+    make_staged_changes(vec![
+        make_file_change("src/parser.rs", ChangeStatus::Modified, TWO_HUNKS, 2, 1),
+        make_file_change("src/lib.rs", ChangeStatus::Modified, "", 3, 0),
+        make_file_change("README.md", ChangeStatus::Modified, "", 1, 1),
+    ])
+}
+
+fn groups() -> Vec<CommitGroup> {
+    vec![
+        CommitGroup {
+            files: vec![PathBuf::from("src/parser.rs"), PathBuf::from("src/lib.rs")],
+            commit_type: CommitType::Feat,
+            scope: Some("parser".into()),
+            hunks: BTreeMap::from([(PathBuf::from("src/parser.rs"), vec![1])]),
+        },
+        CommitGroup {
+            files: vec![PathBuf::from("src/parser.rs")],
+            commit_type: CommitType::Fix,
+            scope: Some("parser".into()),
+            hunks: BTreeMap::from([(PathBuf::from("src/parser.rs"), vec![0])]),
+        },
+        CommitGroup {
+            files: vec![PathBuf::from("README.md")],
+            commit_type: CommitType::Docs,
+            scope: None,
+            hunks: BTreeMap::new(),
+        },
+    ]
+}
+
+fn parse_error(plan: &str) -> String {
+    match SplitEditor::parse(plan, &changes()) {
+        Err(Error::SplitPlan(msg)) => msg,
+        other => panic!("expected a plan error, got {other:?}"),
+    }
+}
+
+#[test]
+fn render_round_trips() {
+    let plan = SplitEditor::render(&groups());
+    assert!(plan.starts_with(
+        "group feat(parser)\nsrc/parser.rs #2\nsrc/lib.rs\n\ngroup fix(parser)\nsrc/parser.rs #1\n"
+    ));
+
+    let parsed = SplitEditor::parse(&plan, &changes()).unwrap();
+    assert_eq!(parsed.len(), 3);
+    for (a, b) in parsed.iter().zip(groups()) {
+        assert_eq!(a.files, b.files);
+        assert_eq!(a.commit_type, b.commit_type);
+        assert_eq!(a.scope, b.scope);
+        assert_eq!(a.hunks, b.hunks);
+    }
+    assert!(SplitEditor::left_out(&parsed, &changes()).is_empty());
+}
+
+#[test]
+fn edits_reorder_retype_and_move_files() {
+    let plan = "\
+group docs
+README.md
+src/lib.rs
+
+group refactor(core)
+src/parser.rs #2
+src/parser.rs #1
+";
+    let parsed = SplitEditor::parse(plan, &changes()).unwrap();
+
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].commit_type, CommitType::Docs);
+    assert_eq!(parsed[0].scope, None);
+    assert_eq!(
+        parsed[0].files,
+        vec![PathBuf::from("README.md"), PathBuf::from("src/lib.rs")]
+    );
+    assert_eq!(parsed[1].commit_type, CommitType::Refactor);
+    assert_eq!(parsed[1].scope.as_deref(), Some("core"));
+    assert!(
+        parsed[1].hunks.is_empty(),
+        "a file with all its hunks in one group is committed whole"
+    );
+}
+
+#[test]
+fn dropped_and_deleted_groups() {
+    // Deleting the fix group's header merges its hunk into the group above
+    let plan = "\
+group feat(parser)
+src/parser.rs #2
+src/parser.rs #1
+
+drop docs
+README.md
+";
+    let parsed = SplitEditor::parse(plan, &changes()).unwrap();
+
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed[0].files, vec![PathBuf::from("src/parser.rs")]);
+    assert_eq!(
+        SplitEditor::left_out(&parsed, &changes()),
+        vec![PathBuf::from("src/lib.rs"), PathBuf::from("README.md")]
+    );
+
+    let only_comments = "# group feat\n\n# src/lib.rs\n";
+    assert!(
+        SplitEditor::parse(only_comments, &changes())
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn partially_listed_file_is_left_out() {
+    let plan = "group fix\nsrc/parser.rs #1\nsrc/lib.rs\nREADME.md\n";
+    let parsed = SplitEditor::parse(plan, &changes()).unwrap();
+
+    assert_eq!(parsed[0].hunks[&PathBuf::from("src/parser.rs")], vec![0]);
+    assert_eq!(
+        SplitEditor::left_out(&parsed, &changes()),
+        vec![PathBuf::from("src/parser.rs")]
+    );
+}
+
+#[test]
+fn invalid_plans_are_rejected() {
+    assert!(parse_error("src/lib.rs\n").contains("line 1: 'src/lib.rs' is not inside a group"));
+    assert!(parse_error("group feature\n").contains("unknown commit type 'feature'"));
+    assert!(parse_error("group feat(core\n").contains("missing ')'"));
+    assert!(parse_error("group feat\nsrc/main.rs\n").contains("'src/main.rs' is not staged"));
+    assert!(
+        parse_error("group feat\nsrc/lib.rs\ngroup fix\nsrc/lib.rs\n")
+            .contains("line 4: 'src/lib.rs' is listed more than once")
+    );
+    assert!(
+        parse_error("group feat\nsrc/parser.rs\ngroup fix\nsrc/parser.rs #1\n")
+            .contains("listed more than once")
+    );
+    assert!(parse_error("group feat\nsrc/parser.rs #3\n").contains("has no hunk 3 (it has 2)"));
+    assert!(parse_error("group feat\nsrc/parser.rs #0\n").contains("invalid hunk number '0'"));
+    assert!(parse_error("group feat\nsrc/lib.rs #1\n").contains("can't be split by hunk"));
+}
+
+#[test]
+fn truncated_file_cannot_be_split_by_hunk() {
+    // The diff holds two hunks but the file has more changes past the cut
+    let changes = make_staged_changes(vec![make_file_change(
+        "src/parser.rs",
+        ChangeStatus::Modified,
+        TWO_HUNKS,
+        40,
+        1,
+    )]);
+    let err = SplitEditor::parse("group feat\nsrc/parser.rs #1\n", &changes).unwrap_err();
+    assert!(
+        matches!(&err, Error::SplitPlan(msg) if msg.contains("'src/parser.rs' is too large to split by hunk")),
+        "{err:?}"
+    );
+
+    let groups = SplitEditor::parse("group feat\nsrc/parser.rs\n", &changes).unwrap();
+    assert!(groups[0].hunks.is_empty());
+    assert!(SplitEditor::left_out(&groups, &changes).is_empty());
+}
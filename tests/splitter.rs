@@ -478,6 +478,164 @@ fn split_keeps_symbol_connected_files_together() {
     );
 }
 
+// ─── Hunk-level splitting tests ──────────────────────────────────────────────
+
+fn symbol_at(name: &str, file: &str, is_added: bool, line: usize, end_line: usize) -> CodeSymbol {
+    CodeSymbol {
+        line,
+        end_line,
+        ..make_symbol(name, SymbolKind::Function, file, true, is_added)
+    }
+}
+
+/// A one-line fix inside `parse_header` and a new `render_table` function
+/// further down the same file.
+const MIXED_DIFF: &str = "\
+diff --git a/src/services/parser.rs b/src/services/parser.rs
+--- a/src/services/parser.rs
++++ b/src/services/parser.rs
+@@ -10,7 +10,7 @@ fn parse_header(input: &str) -> usize {
+     let start = input.find(':').unwrap_or(0);
+     let len = input.len();
+     // the range end is exclusive
+-    let end = start + len;
++    let end = start + len - 1;
+     end
+ }
+ 
+@@ -80,3 +80,12 @@ fn tail() {}
+ // tables
+ 
+ pub fn footer() {}
++
++pub fn render_table(rows: &[Row]) -> String {
++    let mut out = String::new();
++    for row in rows {
++        out.push_str(&row.cells.join(\" | \"));
++        out.push('\\n');
++    }
++    out
++}";
+
+fn mixed_symbols() -> Vec<CodeSymbol> {
+    let file = "src/services/parser.rs";
+    vec![
+        symbol_at("parse_header", file, false, 8, 16),
+        symbol_at("parse_header", file, true, 8, 16),
+        symbol_at("render_table", file, true, 84, 90),
+    ]
+}
+
+#[test]
+fn unrelated_hunks_in_one_file_are_split() {
+    let changes = make_staged_changes(vec![make_file_change(
+        "src/services/parser.rs",
+        ChangeStatus::Modified,
+        MIXED_DIFF,
+        10,
+        1,
+    )]);
+
+    let SplitSuggestion::SuggestSplit(groups) = CommitSplitter::analyze(&changes, &mixed_symbols())
+    else {
+        panic!("expected the fix and the new function to be split");
+    };
+    assert_eq!(groups.len(), 2);
+
+    let path = PathBuf::from("src/services/parser.rs");
+    let mut assigned: Vec<usize> = groups
+        .iter()
+        .inspect(|g| assert_eq!(g.files, vec![path.clone()]))
+        .flat_map(|g| g.hunks[&path].clone())
+        .collect();
+    assigned.sort_unstable();
+    assert_eq!(assigned, vec![0, 1], "each hunk lands in exactly one group");
+
+    // The group context only carries the group's own hunk
+    let (sub, sub_symbols) = CommitSplitter::group_context(&groups[0], &changes, &mixed_symbols());
+    assert_eq!(sub.files[0].additions, 9);
+    assert!(sub.files[0].diff.contains("render_table"));
+    assert!(!sub.files[0].diff.contains("start + len - 1"));
+    assert!(sub_symbols.iter().all(|s| s.name == "render_table"));
+    assert_eq!(
+        groups[0].file_label(&path),
+        "src/services/parser.rs (hunk 2)"
+    );
+}
+
+#[test]
+fn hunks_calling_each_other_stay_together() {
+    let diff = MIXED_DIFF.replace(
+        "+    let end = start + len - 1;",
+        "+    let end = render_table(&[]).len();",
+    );
+    let changes = make_staged_changes(vec![make_file_change(
+        "src/services/parser.rs",
+        ChangeStatus::Modified,
+        &diff,
+        10,
+        1,
+    )]);
+
+    assert!(matches!(
+        CommitSplitter::analyze(&changes, &mixed_symbols()),
+        SplitSuggestion::SingleCommit
+    ));
+}
+
+#[test]
+fn truncated_diff_is_not_split_by_hunk() {
+    // Counts don't match the diff text, so hunks past the cut may be missing
+    let changes = make_staged_changes(vec![make_file_change(
+        "src/services/parser.rs",
+        ChangeStatus::Modified,
+        MIXED_DIFF,
+        40,
+        1,
+    )]);
+
+    assert!(matches!(
+        CommitSplitter::analyze(&changes, &mixed_symbols()),
+        SplitSuggestion::SingleCommit
+    ));
+}
+
+#[test]
+fn removed_line_starting_with_dashes_is_counted() {
+    // `-- let end` removed reads `--- let end`, like a file header
+    let diff = MIXED_DIFF.replace("-    let end = start + len;", "--- let end = start + len;");
+    let changes = make_staged_changes(vec![make_file_change(
+        "src/services/parser.rs",
+        ChangeStatus::Modified,
+        &diff,
+        10,
+        1,
+    )]);
+
+    assert!(matches!(
+        CommitSplitter::analyze(&changes, &mixed_symbols()),
+        SplitSuggestion::SuggestSplit(groups) if groups.len() == 2
+    ));
+
+    // A call seen only on such a line still ties the hunks together
+    let diff = MIXED_DIFF.replace(
+        "-    let end = start + len;",
+        "--- let end = render_table(&[]).len();",
+    );
+    let changes = make_staged_changes(vec![make_file_change(
+        "src/services/parser.rs",
+        ChangeStatus::Modified,
+        &diff,
+        10,
+        1,
+    )]);
+
+    assert!(matches!(
+        CommitSplitter::analyze(&changes, &mixed_symbols()),
+        SplitSuggestion::SingleCommit
+    ));
+}
+
 // ─── Module detection tests ──────────────────────────────────────────────────
 
 #[test]
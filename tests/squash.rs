@@ -0,0 +1,142 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use commitbee::services::history::CommitRecord;
+use commitbee::services::squash::SquashBuilder;
+
+/// `SQUASH_MSG` as written by `git merge --squash feature`.
+const SQUASH_MSG: &str = "\
+Squashed commit of the following:
+
+commit 9c1e4f2a7b3d5e6f8a9b0c1d2e3f4a5b6c7d8e9f
+Author: Ada <ada@example.com>
+Date:   Tue Oct 13 10:00:00 2026 +0200
+
+    wip: fix tests
+
+commit 3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b
+Author: Ada <ada@example.com>
+Date:   Mon Oct 12 09:00:00 2026 +0200
+
+    feat(api)!: drop v1 endpoints
+
+    The v1 handlers are gone.
+
+    BREAKING CHANGE: v1 routes return 404
+      from now on
+    Refs: #42
+    Co-authored-by: Bob <bob@example.com>
+
+commit 1f2e3d4c5b6a79880716253443526170f8e9d0c1
+Merge: 1111111 2222222
+Author: Bob <bob@example.com>
+Date:   Sun Oct 11 08:00:00 2026 +0200
+
+    wip
+
+    Co-authored-by: Bob <bob@example.com>
+    Refs: #43
+";
+
+fn record(subject: &str, body: &str) -> CommitRecord {
+    CommitRecord {
+        sha: "abc1234".into(),
+        subject: subject.into(),
+        body: body.into(),
+    }
+}
+
+#[test]
+fn parse_squash_msg_reads_every_commit() {
+    let commits = SquashBuilder::parse_squash_msg(SQUASH_MSG);
+
+    assert_eq!(commits.len(), 3);
+    assert_eq!(commits[0].sha, "9c1e4f2a7b3d5e6f8a9b0c1d2e3f4a5b6c7d8e9f");
+    assert_eq!(commits[0].subject, "wip: fix tests");
+    assert_eq!(commits[0].body, "");
+    assert_eq!(commits[1].subject, "feat(api)!: drop v1 endpoints");
+    assert!(
+        commits[1]
+            .body
+            .starts_with("The v1 handlers are gone.\n\nBREAKING CHANGE:")
+    );
+    assert_eq!(commits[2].subject, "wip");
+
+    assert!(SquashBuilder::parse_squash_msg("Squashed commit of the following:\n").is_empty());
+}
+
+#[test]
+fn collect_trailers_dedups_and_skips_breaking_footers() {
+    let commits = SquashBuilder::parse_squash_msg(SQUASH_MSG);
+
+    assert_eq!(
+        SquashBuilder::collect_trailers(&commits),
+        vec![
+            "Refs: #42",
+            "Co-authored-by: Bob <bob@example.com>",
+            "Refs: #43",
+        ]
+    );
+
+    // A prose body isn't a trailer block
+    let prose = [record("fix: x", "Note that: this is prose\nand more")];
+    assert!(SquashBuilder::collect_trailers(&prose).is_empty());
+}
+
+#[test]
+fn finalize_keeps_breaking_footers_and_trailers() {
+    let commits = SquashBuilder::parse_squash_msg(SQUASH_MSG);
+    let message = SquashBuilder::finalize(
+        "feat(api): replace v1 endpoints with v2\n\nRoutes now live under /v2.",
+        &commits,
+    );
+
+    assert_eq!(
+        message,
+        "feat(api): replace v1 endpoints with v2\n\n\
+         Routes now live under /v2.\n\n\
+         BREAKING CHANGE: v1 routes return 404 from now on\n\
+         Refs: #42\n\
+         Co-authored-by: Bob <bob@example.com>\n\
+         Refs: #43"
+    );
+}
+
+#[test]
+fn finalize_joins_existing_footers_without_duplicates() {
+    let commits = [record(
+        "feat!: new config",
+        "Refs: #7\nCo-authored-by: Eve <eve@example.com>",
+    )];
+
+    let message = SquashBuilder::finalize(
+        "feat: load config from toml\n\nBREAKING CHANGE: config.json is ignored\nRefs: #7",
+        &commits,
+    );
+    assert_eq!(
+        message,
+        "feat: load config from toml\n\n\
+         BREAKING CHANGE: config.json is ignored\n\
+         Refs: #7\n\
+         BREAKING CHANGE: new config\n\
+         Co-authored-by: Eve <eve@example.com>"
+    );
+
+    let plain = [record("wip", "")];
+    assert_eq!(SquashBuilder::finalize("fix: x\n", &plain), "fix: x");
+}
+
+#[test]
+fn build_prompt_lists_subjects_and_declared_breaking_changes() {
+    let commits = SquashBuilder::parse_squash_msg(SQUASH_MSG);
+    let prompt = SquashBuilder::build_prompt("BASE PROMPT", &commits);
+
+    assert!(prompt.starts_with("BASE PROMPT\n\n---\n"));
+    assert!(prompt.contains("  - wip: fix tests\n  - feat(api)!: drop v1 endpoints\n  - wip"));
+    assert!(
+        prompt
+            .contains("BREAKING CHANGES DECLARED IN COMMITS:\n- v1 routes return 404 from now on")
+    );
+    assert!(prompt.ends_with("Respond with ONLY the JSON object."));
+}
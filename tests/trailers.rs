@@ -0,0 +1,96 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use commitbee::config::TrailerConfig;
+use commitbee::domain::Trailer;
+use commitbee::services::trailers::TrailerBuilder;
+
+/// The example pattern from the generated config.
+const ISSUE_PATTERN: &str =
+    r"^(?:feature|feat|fix|bugfix|issue)[/-](#?\d+|[A-Z][A-Z0-9]+-\d+)(?:[/_-]|$)";
+
+fn issue(branch: &str) -> Option<String> {
+    TrailerBuilder::issue_ref(branch, ISSUE_PATTERN)
+}
+
+#[test]
+fn issue_ref_from_branch_name() {
+    assert_eq!(issue("feature/123-login").as_deref(), Some("#123"));
+    assert_eq!(issue("issue-17").as_deref(), Some("#17"));
+    assert_eq!(issue("fix/PROJ-42-oauth").as_deref(), Some("PROJ-42"));
+    assert_eq!(issue("fix/#9").as_deref(), Some("#9"));
+
+    assert_eq!(issue("main"), None);
+    assert_eq!(issue("release/2.0"), None);
+    assert_eq!(issue("feat/add-oauth2-login"), None);
+    assert_eq!(issue("dependabot/cargo/serde-1.0.200"), None);
+    assert_eq!(issue("release/2026-10"), None);
+    assert_eq!(issue("hotfix/2024-01-15"), None);
+
+    // Off unless configured
+    let default = TrailerConfig::default();
+    assert!(default.issue_pattern.is_empty());
+    assert_eq!(
+        TrailerBuilder::issue_ref("feature/123-login", &default.issue_pattern),
+        None
+    );
+
+    // Custom patterns use their first group, or the whole match; empty disables
+    assert_eq!(
+        TrailerBuilder::issue_ref("gh-88/cleanup", r"gh-(\d+)").as_deref(),
+        Some("#88")
+    );
+    assert_eq!(
+        TrailerBuilder::issue_ref("ABC-1", r"[A-Z]+-\d+").as_deref(),
+        Some("ABC-1")
+    );
+    assert_eq!(TrailerBuilder::issue_ref("feature/123", ""), None);
+}
+
+#[test]
+fn build_orders_issue_co_authors_and_sign_off() {
+    let mut config = TrailerConfig {
+        issue_pattern: ISSUE_PATTERN.into(),
+        issue_trailer: "Closes".into(),
+        pair: vec!["bob".into(), "ada".into(), "bob".into()],
+        sign_off: true,
+        ..TrailerConfig::default()
+    };
+    config
+        .roster
+        .insert("ada".into(), "Ada Lovelace <ada@example.com>".into());
+    config
+        .roster
+        .insert("bob".into(), "Bob <bob@example.com>".into());
+
+    let trailers = TrailerBuilder::build(
+        &config,
+        Some("feature/7-pairing"),
+        Some("Eve <eve@example.com>"),
+    );
+    assert_eq!(
+        trailers,
+        vec![
+            Trailer::new("Closes", "#7"),
+            Trailer::new("Co-authored-by", "Bob <bob@example.com>"),
+            Trailer::new("Co-authored-by", "Ada Lovelace <ada@example.com>"),
+            Trailer::new("Signed-off-by", "Eve <eve@example.com>"),
+        ]
+    );
+    assert_eq!(TrailerBuilder::issue_refs(&trailers, &config), vec!["#7"]);
+
+    // Detached HEAD, no pair, sign-off off
+    assert!(TrailerBuilder::build(&TrailerConfig::default(), None, Some("Eve <e@x>")).is_empty());
+}
+
+#[test]
+fn trailer_parse_and_display() {
+    let trailer = Trailer::parse("Co-authored-by: Ada <ada@example.com>").unwrap();
+    assert_eq!(trailer.key, "Co-authored-by");
+    assert_eq!(trailer.to_string(), "Co-authored-by: Ada <ada@example.com>");
+
+    assert_eq!(Trailer::parse("BREAKING CHANGE: gone"), None);
+    assert_eq!(Trailer::parse("Refs:"), None);
+    assert_eq!(Trailer::parse("-x: y"), None);
+}
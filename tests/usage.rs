@@ -0,0 +1,123 @@
+// SPDX-FileCopyrightText: 2026 Sephyi <me@sephy.io>
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Commercial
+
+use commitbee::config::{ModelPrice, UsageConfig};
+use commitbee::services::usage::{self, LedgerEntry, UsageLedger, UsageTotals};
+
+/// 2026-10-16T13:05:00Z
+const NOW: u64 = 1_792_155_900;
+/// 2026-10-16T00:00:00Z
+const TODAY: u64 = 1_792_108_800;
+/// 2026-10-01T00:00:00Z
+const MONTH: u64 = 1_790_812_800;
+
+fn entry(timestamp: u64, cloud: bool, input: u64, output: u64, cost: Option<f64>) -> LedgerEntry {
+    LedgerEntry {
+        timestamp,
+        profile: "gateway".into(),
+        provider: "openai".into(),
+        model: "gpt-4o-mini".into(),
+        cloud,
+        input_tokens: input,
+        output_tokens: output,
+        cost,
+    }
+}
+
+// ─── Periods ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn day_and_month_start_in_utc() {
+    assert_eq!(usage::day_start(NOW), TODAY);
+    assert_eq!(usage::month_start(NOW), MONTH);
+    assert_eq!(usage::month_start(MONTH), MONTH);
+    assert_eq!(usage::format_date(NOW), "2026-10-16");
+    // Leap day
+    assert_eq!(usage::format_date(1_709_208_000), "2024-02-29");
+    assert_eq!(usage::month_start(1_709_208_000), 1_706_745_600);
+}
+
+// ─── Ledger ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn ledger_appends_and_skips_unreadable_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let ledger = UsageLedger::new(dir.path().join("nested").join("usage.jsonl"));
+    assert!(ledger.entries().is_empty());
+
+    ledger
+        .append(&[entry(NOW, true, 100, 20, Some(0.001))])
+        .unwrap();
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(ledger.path())
+        .and_then(|mut f| std::io::Write::write_all(&mut f, b"{\"truncated\n"))
+        .unwrap();
+    ledger.append(&[entry(NOW, false, 50, 5, None)]).unwrap();
+
+    let entries = ledger.entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].cost, Some(0.001));
+    assert!(!entries[1].cloud);
+
+    let totals = UsageTotals::sum(&entries);
+    assert_eq!(totals.requests, 2);
+    assert_eq!(totals.tokens(), 175);
+    assert_eq!(totals.unpriced, 1);
+}
+
+// ─── Budgets ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn daily_token_budget_counts_only_todays_cloud_requests() {
+    let config = UsageConfig {
+        daily_tokens: Some(1_000),
+        ..UsageConfig::default()
+    };
+    let entries = vec![
+        entry(TODAY - 1, true, 5_000, 0, None), // yesterday
+        entry(NOW, false, 5_000, 0, None),      // local
+        entry(NOW, true, 600, 300, None),
+    ];
+    assert!(usage::budget_exceeded(&config, &entries, NOW).is_none());
+
+    let mut entries = entries;
+    entries.push(entry(NOW, true, 100, 0, None));
+    let message = usage::budget_exceeded(&config, &entries, NOW).unwrap();
+    assert!(message.contains("daily token budget of 1000"), "{message}");
+}
+
+#[test]
+fn monthly_cost_budget() {
+    let config = UsageConfig {
+        monthly_cost: Some(1.0),
+        ..UsageConfig::default()
+    };
+    let entries = vec![
+        entry(MONTH - 1, true, 0, 0, Some(5.0)), // last month
+        entry(MONTH, true, 0, 0, Some(0.5)),
+        entry(NOW, true, 0, 0, Some(0.25)),
+    ];
+    assert!(usage::budget_exceeded(&config, &entries, NOW).is_none());
+
+    let mut entries = entries;
+    entries.push(entry(NOW, true, 0, 0, Some(0.25)));
+    let message = usage::budget_exceeded(&config, &entries, NOW).unwrap();
+    assert!(message.contains("monthly cost budget"), "{message}");
+}
+
+#[test]
+fn cost_uses_prices_per_million_tokens() {
+    let mut config = UsageConfig::default();
+    config.prices.insert(
+        "gpt-4o-mini".into(),
+        ModelPrice {
+            input: 0.15,
+            output: 0.60,
+        },
+    );
+    let cost = config.cost("gpt-4o-mini", 1_000_000, 500_000).unwrap();
+    assert!((cost - 0.45).abs() < 1e-9);
+    assert!(config.cost("unknown", 10, 10).is_none());
+}